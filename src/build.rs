@@ -0,0 +1,249 @@
+//! Helpers for using the library from a build script
+//!
+//! ```no_run
+//! use codegen_rs::{build::Build, nodes::Module};
+//!
+//! let mut module = Module::new();
+//! module.add_struct("Generated");
+//! Build::new("generated")
+//!     .add_input("schema.txt")
+//!     .write(&module)
+//!     .unwrap();
+//! ```
+//!
+//! The crate can then use `include!(concat!(env!("OUT_DIR"), "/generated.rs"));`.
+
+use std::{env, fmt::Write, io, path::PathBuf};
+
+use crate::{
+    fs::write_if_changed,
+    generator::{Formatter, GenerateCode},
+    nodes::{raw::lines_in_string, Module},
+    Str,
+};
+
+/// Writes a module to `$OUT_DIR/<name>.rs`
+pub struct Build {
+    name: Str,
+    out_dir: Option<PathBuf>,
+    inputs: Vec<PathBuf>,
+    wrap_module: Option<Str>,
+}
+
+impl Build {
+    pub fn new(name: impl Into<Str>) -> Self {
+        Self {
+            name: name.into(),
+            out_dir: None,
+            inputs: Vec::new(),
+            wrap_module: None,
+        }
+    }
+
+    /// Set the output directory, `$OUT_DIR` is used by default
+    pub fn set_out_dir(&mut self, out_dir: impl Into<PathBuf>) -> &mut Self {
+        self.out_dir = Some(out_dir.into());
+        self
+    }
+
+    /// Adds a file the generated code depends on, cargo reruns the build script when it changes
+    pub fn add_input(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.inputs.push(path.into());
+        self
+    }
+
+    pub fn add_inputs<P: Into<PathBuf>>(
+        &mut self,
+        paths: impl IntoIterator<Item = P>,
+    ) -> &mut Self {
+        self.inputs.extend(paths.into_iter().map(Into::into));
+        self
+    }
+
+    /// Wraps the generated code in `mod name { ... }`
+    ///
+    /// This is required if the code starts with inner attributes (`#![...]`) or inner doc
+    /// comments (`//!`), which are not allowed in files that are included with `include!`.
+    pub fn set_wrap_module(&mut self, name: impl Into<Str>) -> &mut Self {
+        self.wrap_module = Some(name.into());
+        self
+    }
+
+    /// Renders the module and writes it to the output directory
+    ///
+    /// A `cargo:rerun-if-changed` line is printed for every input. The file is only rewritten
+    /// if its contents have changed. Returns the path of the written file.
+    pub fn write(&self, module: &Module) -> io::Result<PathBuf> {
+        let out_dir = match &self.out_dir {
+            Some(out_dir) => out_dir.clone(),
+            None => env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "OUT_DIR is not set"))?,
+        };
+        for input in &self.inputs {
+            println!("cargo:rerun-if-changed={}", input.display());
+        }
+        let code = self.render(module)?;
+        let path = out_dir.join(format!("{}.rs", self.name));
+        write_if_changed(&path, &code)?;
+        Ok(path)
+    }
+
+    /// Renders the module in a layout that can be used with `include!`
+    pub fn render(&self, module: &Module) -> io::Result<String> {
+        let Some(name) = &self.wrap_module else {
+            let code = module.to_string();
+            if has_inner_attributes(&code) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "inner attributes are not allowed in included files, use `Build::set_wrap_module`",
+                ));
+            }
+            return Ok(code);
+        };
+        let mut code = String::new();
+        let mut fmt = Formatter::new(&mut code);
+        _ = write!(fmt, "mod {name} ");
+        fmt.write_block(|fmt| module.generate(fmt));
+        Ok(code)
+    }
+}
+
+/// Writes a module to `$OUT_DIR/<name>.rs`, see [`Build`]
+pub fn write_module(name: impl Into<Str>, module: &Module) -> io::Result<PathBuf> {
+    Build::new(name).write(module)
+}
+
+/// Checks for inner attributes and inner doc comments that are not indented, i.e. apply to the
+/// file itself
+fn has_inner_attributes(code: &str) -> bool {
+    code.lines()
+        .zip(lines_in_string(code))
+        .any(|(line, in_string)| {
+            !in_string
+                && ["#![", "//!", "/*!"]
+                    .iter()
+                    .any(|start| line.starts_with(start))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        time::{Duration, SystemTime},
+    };
+
+    use super::*;
+    use crate::nodes::{raw::Raw, ModuleNode};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("codegen-rs-build-{}-{name}", std::process::id()));
+        _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn unchanged_file_is_not_rewritten() {
+        let dir = temp_dir("unchanged");
+        env::set_var("OUT_DIR", &dir);
+        let mut module = Module::new();
+        module.add_struct("Generated").add_field("id", "u32");
+
+        let path = write_module("generated", &module).unwrap();
+        assert_eq!(path, dir.join("generated.rs"));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "struct Generated {\n    id: u32,\n}\n"
+        );
+
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        write_module("generated", &module).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), old);
+
+        module.add_struct("Other");
+        write_module("generated", &module).unwrap();
+        assert_ne!(fs::metadata(&path).unwrap().modified().unwrap(), old);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn inner_attributes() {
+        let dir = temp_dir("inner");
+        let mut module = Module::new();
+        module.add_raw("#![allow(dead_code)]");
+        module.add_raw("struct Generated;");
+
+        let mut build = Build::new("generated");
+        build.set_out_dir(&dir);
+        let error = build.write(&module).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(!dir.join("generated.rs").exists());
+
+        build.set_wrap_module("generated");
+        let path = build.write(&module).unwrap();
+        assert_eq!(
+            fs::read_to_string(path).unwrap(),
+            "mod generated {\n    #![allow(dead_code)]\n\n    struct Generated;\n}\n"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn inner_doc_comments() {
+        let mut module = Module::new();
+        module.add_raw("//! Generated types");
+        module.add_raw("struct Generated;");
+        let mut build = Build::new("generated");
+        assert_eq!(
+            build.render(&module).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        module.remove(0);
+        module.insert_at(
+            0,
+            ModuleNode::Raw(Raw::new("/*! Generated types */".into())),
+        );
+        assert!(build.render(&module).is_err());
+
+        build.set_wrap_module("generated");
+        assert_eq!(
+            build.render(&module).unwrap(),
+            "mod generated {\n    /*! Generated types */\n\n    struct Generated;\n}\n"
+        );
+        // Only the start of a line counts, not a string literal
+        let mut module = Module::new();
+        module.add_raw("const DOC: &str = \"\n//! not a comment\n\";");
+        assert!(Build::new("generated").render(&module).is_ok());
+    }
+
+    #[test]
+    fn wrap_module_keeps_string_literals() {
+        let mut module = Module::new();
+        module
+            .add_function("usage")
+            .set_return_type("&'static str")
+            .add_body()
+            .add_raw("r#\"usage:\n    tool [options]\n\"#");
+        let mut build = Build::new("generated");
+        build.set_wrap_module("cli");
+        assert_eq!(
+            build.render(&module).unwrap(),
+            r##"mod cli {
+    fn usage() -> &'static str {
+        r#"usage:
+    tool [options]
+"#
+    }
+}
+"##
+        );
+    }
+}
@@ -0,0 +1,395 @@
+//! Comparing generated code with files on disk
+//!
+//! This is useful for generated code that is committed, a test can check that the committed
+//! file is up to date and print a diff otherwise.
+
+use std::{
+    fmt::{Display, Write},
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{fs::write_if_changed, nodes::Module};
+
+impl Module {
+    /// Checks that the file at `path` contains exactly the generated code
+    pub fn check_against(&self, path: impl AsRef<Path>) -> Result<(), CheckError> {
+        check(path.as_ref(), &self.to_string())
+    }
+
+    /// Checks the file at `path` like [`Module::check_against`] or overwrites it if `update` is
+    /// set
+    ///
+    /// A file that only differs in its line endings is up to date and not rewritten.
+    pub fn check_or_update(&self, path: impl AsRef<Path>, update: bool) -> Result<(), CheckError> {
+        let path = path.as_ref();
+        let code = self.to_string();
+        match check(path, &code) {
+            Err(err) if update && !matches!(err.kind, CheckErrorKind::Io(_)) => {
+                write_if_changed(path, &normalize_line_endings(&code))
+                    .map(|_| ())
+                    .map_err(|err| CheckError::new(path, CheckErrorKind::Io(err)))
+            }
+            result => result,
+        }
+    }
+}
+
+/// Checks that the file at `path` contains exactly `expected`
+///
+/// `\r\n` and `\n` line endings are treated as equal, e.g. for files checked out with
+/// `core.autocrlf`.
+pub fn check(path: &Path, expected: &str) -> Result<(), CheckError> {
+    let actual = match std::fs::read_to_string(path) {
+        Ok(actual) => actual,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Err(CheckError::new(path, CheckErrorKind::Missing))
+        }
+        Err(err) => return Err(CheckError::new(path, CheckErrorKind::Io(err))),
+    };
+    if normalize_line_endings(&actual) == normalize_line_endings(expected) {
+        return Ok(());
+    }
+    let name = path.display().to_string();
+    let diff = unified_diff(&actual, expected, &name, &format!("{name} (generated)"));
+    Err(CheckError::new(path, CheckErrorKind::Mismatch(diff)))
+}
+
+#[derive(Debug)]
+pub struct CheckError {
+    path: PathBuf,
+    kind: CheckErrorKind,
+}
+
+impl CheckError {
+    fn new(path: &Path, kind: CheckErrorKind) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            kind,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn kind(&self) -> &CheckErrorKind {
+        &self.kind
+    }
+}
+
+impl Display for CheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = self.path.display();
+        match &self.kind {
+            CheckErrorKind::Missing => write!(f, "{path} does not exist"),
+            CheckErrorKind::Mismatch(diff) => write!(f, "{path} is out of date:\n{diff}"),
+            CheckErrorKind::Io(err) => write!(f, "{path}: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for CheckError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            CheckErrorKind::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CheckErrorKind {
+    /// The file does not exist
+    Missing,
+    /// The file differs from the generated code, contains a unified diff
+    Mismatch(String),
+    Io(io::Error),
+}
+
+/// Lines of context around changes
+const CONTEXT: usize = 3;
+/// Above this many line comparisons the changed region is shown as fully replaced
+const MAX_TABLE_SIZE: usize = 1 << 22;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Creates a unified diff from `old` to `new`
+///
+/// `\r\n` line endings are compared as `\n`. A last line without a newline is marked with
+/// `\\ No newline at end of file`.
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    let (old, new) = (normalize_line_endings(old), normalize_line_endings(new));
+    // Lines keep their `\n`, so a missing newline at the end is a change
+    let old: Vec<&str> = old.split_inclusive('\n').collect();
+    let new: Vec<&str> = new.split_inclusive('\n').collect();
+    let lines = diff_lines(&old, &new);
+    let mut dst = String::new();
+    _ = writeln!(dst, "--- {old_name}");
+    _ = writeln!(dst, "+++ {new_name}");
+    let mut i = 0;
+    // Line numbers in old and new at position i
+    let (mut old_line, mut new_line) = (0, 0);
+    while i < lines.len() {
+        let Some(change) = lines[i..]
+            .iter()
+            .position(|line| !matches!(line, Line::Same(_)))
+        else {
+            break;
+        };
+        let skip = change.saturating_sub(CONTEXT);
+        i += skip;
+        old_line += skip;
+        new_line += skip;
+        // Extend the hunk until there are more than two times the context of unchanged lines
+        let start = i;
+        let mut end = i + change - skip;
+        let mut same = 0;
+        while end < lines.len() && same <= 2 * CONTEXT {
+            if matches!(lines[end], Line::Same(_)) {
+                same += 1;
+            } else {
+                same = 0;
+            }
+            end += 1;
+        }
+        end -= same.saturating_sub(CONTEXT);
+        let hunk = &lines[start..end];
+        let old_len = hunk
+            .iter()
+            .filter(|line| !matches!(line, Line::Added(_)))
+            .count();
+        let new_len = hunk
+            .iter()
+            .filter(|line| !matches!(line, Line::Removed(_)))
+            .count();
+        _ = writeln!(
+            dst,
+            "@@ -{} +{} @@",
+            range(old_line, old_len),
+            range(new_line, new_len)
+        );
+        for line in hunk {
+            let (prefix, line) = match line {
+                Line::Same(line) => (' ', line),
+                Line::Removed(line) => ('-', line),
+                Line::Added(line) => ('+', line),
+            };
+            match line.strip_suffix('\n') {
+                Some(line) => _ = writeln!(dst, "{prefix}{line}"),
+                None => _ = writeln!(dst, "{prefix}{line}\n\\ No newline at end of file"),
+            }
+        }
+        old_line += old_len;
+        new_line += new_len;
+        i = end;
+    }
+    dst
+}
+
+fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n")
+}
+
+fn range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{len}", start + 1),
+    }
+}
+
+/// Computes a line diff using the longest common subsequence
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+    let mut lines: Vec<Line> = old[..prefix].iter().map(|line| Line::Same(line)).collect();
+    let (n, m) = (old_mid.len(), new_mid.len());
+    if n * m > MAX_TABLE_SIZE {
+        lines.extend(old_mid.iter().map(|line| Line::Removed(line)));
+        lines.extend(new_mid.iter().map(|line| Line::Added(line)));
+    } else {
+        // table[i][j] is the length of the LCS of old_mid[i..] and new_mid[j..]
+        let mut table = vec![0u32; (n + 1) * (m + 1)];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                table[i * (m + 1) + j] = if old_mid[i] == new_mid[j] {
+                    table[(i + 1) * (m + 1) + j + 1] + 1
+                } else {
+                    table[(i + 1) * (m + 1) + j].max(table[i * (m + 1) + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old_mid[i] == new_mid[j] {
+                lines.push(Line::Same(old_mid[i]));
+                i += 1;
+                j += 1;
+            } else if i < n
+                && (j == m || table[(i + 1) * (m + 1) + j] >= table[i * (m + 1) + j + 1])
+            {
+                lines.push(Line::Removed(old_mid[i]));
+                i += 1;
+            } else {
+                lines.push(Line::Added(new_mid[j]));
+                j += 1;
+            }
+        }
+    }
+    lines.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|line| Line::Same(line)),
+    );
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(old: &str, new: &str) -> String {
+        unified_diff(old, new, "old", "new")
+    }
+
+    #[test]
+    fn update() {
+        let path = std::env::temp_dir().join(format!("codegen-rs-check-{}.rs", std::process::id()));
+        _ = std::fs::remove_file(&path);
+        let mut module = Module::new();
+        module.add_struct("A").add_field("x", "u8");
+        assert!(matches!(
+            module.check_against(&path).unwrap_err().kind(),
+            CheckErrorKind::Missing
+        ));
+        module.check_or_update(&path, true).unwrap();
+        module.check_against(&path).unwrap();
+
+        // Only the line endings differ, the file is kept as it is
+        let crlf = "struct A {\r\n    x: u8,\r\n}\r\n";
+        std::fs::write(&path, crlf).unwrap();
+        module.check_or_update(&path, false).unwrap();
+        module.check_or_update(&path, true).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), crlf);
+
+        module.add_struct("B").add_field("y", "u8");
+        assert!(matches!(
+            module.check_or_update(&path, false).unwrap_err().kind(),
+            CheckErrorKind::Mismatch(_)
+        ));
+        module.check_or_update(&path, true).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), module.to_string());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    /// Joins numbered lines, e.g. `lines(1..4)` is `"1\n2\n3\n"`
+    fn lines(range: std::ops::Range<usize>) -> String {
+        range.map(|i| format!("{i}\n")).collect()
+    }
+
+    #[test]
+    fn lcs() {
+        let old = ["a", "b", "c", "d", "e"];
+        let new = ["a", "c", "x", "d", "b"];
+        let lines = diff_lines(&old, &new);
+        assert!(
+            lines
+                == [
+                    Line::Same("a"),
+                    Line::Removed("b"),
+                    Line::Same("c"),
+                    Line::Added("x"),
+                    Line::Same("d"),
+                    Line::Removed("e"),
+                    Line::Added("b"),
+                ]
+        );
+    }
+
+    #[test]
+    fn single_hunk() {
+        assert_eq!(
+            diff("a\nb\nc\n", "a\nx\nc\n"),
+            "--- old\n+++ new\n@@ -1,3 +1,3 @@\n a\n-b\n+x\n c\n"
+        );
+    }
+
+    #[test]
+    fn context() {
+        let old = lines(1..21);
+        let new = old.replace("\n10\n", "\nten\n");
+        assert_eq!(
+            diff(&old, &new),
+            "--- old\n+++ new\n@@ -7,7 +7,7 @@\n 7\n 8\n 9\n-10\n+ten\n 11\n 12\n 13\n"
+        );
+    }
+
+    #[test]
+    fn merged_hunks() {
+        // Six unchanged lines between changes are shared context
+        let old = lines(1..21);
+        let new = old
+            .replace("\n5\n", "\nfive\n")
+            .replace("\n12\n", "\ntwelve\n");
+        let diff = diff(&old, &new);
+        assert_eq!(diff.matches("@@ -").count(), 1);
+        assert!(diff.contains("@@ -2,14 +2,14 @@\n"));
+    }
+
+    #[test]
+    fn separate_hunks() {
+        let old = lines(1..31);
+        let new = old
+            .replace("\n5\n", "\nfive\n")
+            .replace("\n20\n", "\ntwenty\n");
+        let diff = diff(&old, &new);
+        assert!(diff
+            .contains("@@ -2,7 +2,7 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n@@ -17,7 +17,7 @@\n"));
+    }
+
+    #[test]
+    fn insertion_and_removal() {
+        assert_eq!(diff("", "a\n"), "--- old\n+++ new\n@@ -0,0 +1 @@\n+a\n");
+        assert_eq!(
+            diff("a\nb\n", "b\n"),
+            "--- old\n+++ new\n@@ -1,2 +1 @@\n-a\n b\n"
+        );
+    }
+
+    #[test]
+    fn missing_newline() {
+        assert_eq!(
+            diff("a\nb", "a\nb\n"),
+            "--- old\n+++ new\n@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n"
+        );
+    }
+
+    #[test]
+    fn line_endings() {
+        assert_eq!(diff("a\r\nb\r\n", "a\nb\n"), "--- old\n+++ new\n");
+
+        let path = std::env::temp_dir().join(format!("codegen-rs-check-{}.rs", std::process::id()));
+        std::fs::write(&path, "struct A;\r\n").unwrap();
+        assert!(check(&path, "struct A;\n").is_ok());
+        let error = check(&path, "struct A;").unwrap_err();
+        let CheckErrorKind::Mismatch(diff) = error.kind() else {
+            panic!("expected a mismatch");
+        };
+        assert!(diff.ends_with("-struct A;\n+struct A;\n\\ No newline at end of file\n"));
+        std::fs::remove_file(path).unwrap();
+    }
+}
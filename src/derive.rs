@@ -0,0 +1,398 @@
+//! Manual implementations of standard traits for structs
+//!
+//! When a derive is not possible or a trait needs custom behavior, these functions build the
+//! [`Impl`] that the derive would expand to from a [`Struct`] definition. [`DeriveOptions`] can
+//! skip fields or replace the expressions used for them. Like a derive, the impls for generic
+//! structs require the trait for each type parameter.
+//!
+//! ```
+//! use codegen_rs::{derive::{self, DeriveOptions}, expr, generator::GenerateCode, nodes::Module};
+//!
+//! let mut module = Module::new();
+//! let user = module.add_struct("User");
+//! user.add_field("name", "String");
+//! user.add_field("password", "String");
+//! let user = user.clone();
+//!
+//! let mut options = DeriveOptions::new();
+//! options.set_field_expr("password", expr::str("***").reference());
+//! assert_eq!(
+//!     derive::debug(&user, &options).to_code_string(),
+//!     r#"impl std::fmt::Debug for User {
+//!     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+//!         f.debug_struct("User").field("name", &self.name).field("password", &"***").finish()
+//!     }
+//! }
+//! "#
+//! );
+//! ```
+
+use crate::{
+    expr::{self, Expr},
+    nodes::{r#impl::Impl, r#struct::Struct},
+    Str,
+};
+
+pub mod accessors;
+pub mod bitflags;
+pub mod builder;
+pub mod enum_helpers;
+pub mod error;
+pub mod newtype;
+
+/// Options for the functions of the [`derive`](self) module
+#[derive(Clone, Debug, Default)]
+pub struct DeriveOptions {
+    skip: Vec<Str>,
+    exprs: Vec<(Str, Expr)>,
+}
+
+impl DeriveOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Leaves a field out of `Debug`, `PartialEq` and `Hash`, `Clone` uses its default value
+    pub fn add_skip(&mut self, field: impl Into<Str>) -> &mut Self {
+        self.skip.push(field.into());
+        self
+    }
+
+    /// Replaces the expression for a field
+    ///
+    /// It is the value passed to `Debug` and the field value for `Clone` and `Default`.
+    /// `PartialEq` and `Hash` compare the fields themselves.
+    pub fn set_field_expr(&mut self, field: impl Into<Str>, expr: impl Into<Expr>) -> &mut Self {
+        let field = field.into();
+        self.exprs.retain(|(name, _)| *name != field);
+        self.exprs.push((field, expr.into()));
+        self
+    }
+
+    pub fn is_skipped(&self, field: &str) -> bool {
+        self.skip.iter().any(|skip| skip == field)
+    }
+
+    pub fn field_expr(&self, field: &str) -> Option<&Expr> {
+        self.exprs
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, expr)| expr)
+    }
+}
+
+/// Implements `Debug` with `debug_struct`, `finish_non_exhaustive` is used if fields are skipped
+pub fn debug(r#struct: &Struct, options: &DeriveOptions) -> Impl {
+    let mut r#impl = trait_impl(r#struct, "std::fmt::Debug");
+    let method = match r#struct.is_tuple() {
+        true => "debug_tuple",
+        false => "debug_struct",
+    };
+    let mut call = expr::binding("f")
+        .method(method)
+        .arg(expr::str(r#struct.name().to_owned()));
+    let mut skipped = false;
+    for field in r#struct.fields() {
+        if options.is_skipped(field.name()) {
+            skipped = true;
+            continue;
+        }
+        let value = match options.field_expr(field.name()) {
+            Some(value) => value.clone(),
+            None => self_field(field.name()).reference(),
+        };
+        call = Expr::from(call).method("field");
+        if !r#struct.is_tuple() {
+            call = call.arg(expr::str(field.name().trim_start_matches("r#").to_owned()));
+        }
+        call = call.arg(value);
+    }
+    let finish = match skipped {
+        true => "finish_non_exhaustive",
+        false => "finish",
+    };
+    r#impl
+        .add_function("fmt")
+        .add_self_param(true, false)
+        .add_param("f", "&mut std::fmt::Formatter<'_>")
+        .set_return_type("std::fmt::Result")
+        .add_body()
+        .add_tail(Expr::from(call).method(finish));
+    r#impl
+}
+
+/// Implements `Clone` by cloning each field, skipped fields are set to their default
+pub fn clone(r#struct: &Struct, options: &DeriveOptions) -> Impl {
+    let mut r#impl = trait_impl(r#struct, "Clone");
+    let init = init_self(r#struct, |field| match options.field_expr(field) {
+        Some(value) => value.clone(),
+        None if options.is_skipped(field) => default_value(),
+        None => self_field(field).method("clone").into(),
+    });
+    r#impl
+        .add_function("clone")
+        .add_self_param(true, false)
+        .set_return_type("Self")
+        .add_body()
+        .add_tail(init);
+    r#impl
+}
+
+/// Implements `PartialEq` by comparing all fields that are not skipped
+pub fn partial_eq(r#struct: &Struct, options: &DeriveOptions) -> Impl {
+    let mut r#impl = trait_impl(r#struct, "PartialEq");
+    let value = r#struct
+        .fields()
+        .iter()
+        .filter(|field| !options.is_skipped(field.name()))
+        .map(|field| {
+            self_field(field.name()).equals(expr::binding("other").field(field.name().to_owned()))
+        })
+        .reduce(Expr::and);
+    // `other` is unused if there are no fields to compare
+    let other = match value {
+        Some(_) => "other",
+        None => "_other",
+    };
+    r#impl
+        .add_function("eq")
+        .add_self_param(true, false)
+        .add_param(other, "&Self")
+        .set_return_type("bool")
+        .add_body()
+        .add_tail(value.unwrap_or_else(|| expr::bool(true)));
+    r#impl
+}
+
+/// Implements `Eq`, which has no methods
+pub fn eq(r#struct: &Struct) -> Impl {
+    trait_impl(r#struct, "Eq")
+}
+
+/// Implements `Hash` by hashing all fields that are not skipped
+pub fn hash(r#struct: &Struct, options: &DeriveOptions) -> Impl {
+    let mut r#impl = trait_impl(r#struct, "std::hash::Hash");
+    let fields: Vec<&str> = r#struct
+        .fields()
+        .iter()
+        .map(|field| field.name())
+        .filter(|field| !options.is_skipped(field))
+        .collect();
+    let state = match fields.is_empty() {
+        true => "_state",
+        false => "state",
+    };
+    let body = r#impl
+        .add_function("hash")
+        .add_generic("H: std::hash::Hasher")
+        .add_self_param(true, false)
+        .add_param(state, "&mut H")
+        .add_body();
+    for field in fields {
+        body.add_expr(self_field(field).method("hash").arg(expr::binding("state")));
+    }
+    r#impl
+}
+
+/// Implements `Default` with `Default::default()` or the expression of each field
+pub fn default(r#struct: &Struct, options: &DeriveOptions) -> Impl {
+    let mut r#impl = trait_impl(r#struct, "Default");
+    let init = init_self(r#struct, |field| {
+        options
+            .field_expr(field)
+            .cloned()
+            .unwrap_or_else(default_value)
+    });
+    r#impl
+        .add_function("default")
+        .set_return_type("Self")
+        .add_body()
+        .add_tail(init);
+    r#impl
+}
+
+/// Builds `Self { .. }`, or `Self(..)` for tuple structs, with a value for each field
+fn init_self(r#struct: &Struct, mut value: impl FnMut(&str) -> Expr) -> Expr {
+    let fields = r#struct.fields().iter().map(|field| field.name());
+    match r#struct.is_tuple() {
+        true => fields
+            .fold(expr::init_tuple_struct("Self"), |init, field| {
+                init.arg(value(field))
+            })
+            .into(),
+        false => fields
+            .fold(expr::init_struct("Self").multiline(true), |init, field| {
+                init.field(field.to_owned(), value(field))
+            })
+            .into(),
+    }
+}
+
+/// Builds an impl of `r#trait` that requires it for each type parameter, like a derive
+fn trait_impl(r#struct: &Struct, r#trait: &'static str) -> Impl {
+    let mut r#impl = generic_impl(r#struct.name(), r#struct.generics(), Some(r#trait));
+    r#impl.set_trait(r#trait);
+    r#impl
+}
+
+/// Builds an impl for the type `name` with the generic parameters `generics`
+///
+/// Defaults are removed from the parameters and `bound` is added to each type parameter, e.g.
+/// `impl<'a, T: Clone, const N: usize> Wrap<'a, T, N>` for `Wrap<'a, T = u8, const N: usize>`.
+pub(crate) fn generic_impl(name: &str, generics: &[Str], bound: Option<&str>) -> Impl {
+    let mut r#impl = Impl::new(generic_type(name, generics).into());
+    for generic in generics {
+        let param = strip_default(generic);
+        let is_type = !param.starts_with('\'') && !param.starts_with("const ");
+        match bound {
+            Some(bound) if is_type && param.contains(':') => {
+                r#impl.add_generic(format!("{param} + {bound}"))
+            }
+            Some(bound) if is_type => r#impl.add_generic(format!("{param}: {bound}")),
+            _ => r#impl.add_generic(param.to_owned()),
+        };
+    }
+    r#impl
+}
+
+/// The type `name` with the arguments for `generics`, e.g. `Wrap<'a, T, N>`
+pub(crate) fn generic_type(name: &str, generics: &[Str]) -> String {
+    if generics.is_empty() {
+        return name.to_owned();
+    }
+    let args: Vec<&str> = generics
+        .iter()
+        .map(|generic| {
+            let param = generic.strip_prefix("const ").unwrap_or(generic);
+            param.split([':', '=']).next().unwrap_or_default().trim()
+        })
+        .collect();
+    format!("{name}<{}>", args.join(", "))
+}
+
+/// Removes the default of a generic parameter, e.g. `= u8` from `T: Clone = u8`
+fn strip_default(generic: &str) -> &str {
+    let mut depth = 0usize;
+    for (i, c) in generic.char_indices() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth = depth.saturating_sub(1),
+            '=' if depth == 0 => return generic[..i].trim_end(),
+            _ => {}
+        }
+    }
+    generic
+}
+
+fn self_field(name: &str) -> Expr {
+    expr::binding("self").field(name.to_owned())
+}
+
+fn default_value() -> Expr {
+    expr::call("Default::default").into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, process::Command};
+
+    use super::*;
+    use crate::generator::GenerateCode;
+
+    /// Compiles `code` as a library crate and panics with the errors if it does not compile
+    pub(super) fn assert_compiles(name: &str, code: &str) {
+        let dir =
+            std::env::temp_dir().join(format!("codegen-rs-derive-{}-{name}", std::process::id()));
+        _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("lib.rs"), code).unwrap();
+        let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+        let output = Command::new(rustc)
+            .args([
+                "--edition",
+                "2021",
+                "--crate-type",
+                "lib",
+                "--emit",
+                "metadata",
+            ])
+            .arg("--out-dir")
+            .arg(&dir)
+            .arg(dir.join("lib.rs"))
+            .output()
+            .unwrap();
+        _ = fs::remove_dir_all(&dir);
+        assert!(
+            output.status.success(),
+            "{code}\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    #[test]
+    fn generics() {
+        let mut module = crate::nodes::Module::new();
+        let wrap = module.add_struct("Wrap");
+        wrap.add_generic("'a")
+            .add_generic("const N: usize")
+            .add_generic("T: Copy = u8")
+            .add_field("items", "[T; N]");
+        wrap.add_field("name", "Option<&'a str>");
+        let wrap = wrap.clone();
+        let options = DeriveOptions::new();
+        let impls = [
+            debug(&wrap, &options),
+            clone(&wrap, &options),
+            partial_eq(&wrap, &options),
+            eq(&wrap),
+            hash(&wrap, &options),
+        ];
+        assert_eq!(
+            impls[1].to_code_string(),
+            "impl<'a, const N: usize, T: Copy + Clone> Clone for Wrap<'a, N, T> {\n    \
+             fn clone(&self) -> Self {\n        Self {\n            \
+             items: self.items.clone(),\n            name: self.name.clone(),\n        }\n    \
+             }\n}\n"
+        );
+        for r#impl in impls {
+            module.add_node(crate::nodes::ModuleNode::Impl(r#impl));
+        }
+        assert_compiles("generics", &module.to_string());
+    }
+
+    #[test]
+    fn tuple_struct() {
+        let mut pair = Struct::new("Pair".into());
+        pair.add_tuple_field("u8");
+        pair.add_tuple_field("String");
+        let options = DeriveOptions::new();
+        assert_eq!(
+            clone(&pair, &options).to_code_string(),
+            "impl Clone for Pair {\n    fn clone(&self) -> Self {\n        \
+             Self(self.0.clone(), self.1.clone())\n    }\n}\n"
+        );
+        assert_eq!(
+            default(&pair, &options).to_code_string(),
+            "impl Default for Pair {\n    fn default() -> Self {\n        \
+             Self(Default::default(), Default::default())\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn no_fields() {
+        let mut options = DeriveOptions::new();
+        options.add_skip("cache");
+        let mut cached = Struct::new("Cached".into());
+        cached.add_field("cache", "u8");
+        assert_eq!(
+            partial_eq(&cached, &options).to_code_string(),
+            "impl PartialEq for Cached {\n    fn eq(&self, _other: &Self) -> bool {\n        \
+             true\n    }\n}\n"
+        );
+        assert_eq!(
+            hash(&cached, &options).to_code_string(),
+            "impl std::hash::Hash for Cached {\n    \
+             fn hash<H: std::hash::Hasher>(&self, _state: &mut H) {\n    }\n}\n"
+        );
+    }
+}
@@ -0,0 +1,304 @@
+//! Constructors and accessors for structs with private fields
+//!
+//! [`accessors`] generates the inherent impl that is usually written by hand: a `new`
+//! constructor taking every field, `field()` getters, `field_mut()` and `set_field()`. Getters of
+//! `Copy` primitives return the value, all others a reference. Field docs are copied to the
+//! generated methods.
+//!
+//! ```
+//! use codegen_rs::{derive::accessors::{accessors, AccessorOptions}, generator::GenerateCode, nodes::Module};
+//!
+//! let mut module = Module::new();
+//! let point = module.add_struct("Point");
+//! point.add_field("x", "f32").set_doc("The horizontal position");
+//! let point = point.clone();
+//!
+//! let mut options = AccessorOptions::new();
+//! options.set_getters_mut(false).set_setters(false);
+//! assert_eq!(
+//!     accessors(&point, &options).to_code_string(),
+//!     r#"impl Point {
+//!     pub fn new(x: f32) -> Self {
+//!         Self { x }
+//!     }
+//!
+//!     /// The horizontal position
+//!     pub fn x(&self) -> f32 {
+//!         self.x
+//!     }
+//! }
+//! "#
+//! );
+//! ```
+
+use crate::{
+    derive::{self, self_field},
+    expr,
+    nodes::{
+        function::Function,
+        r#impl::Impl,
+        r#struct::{Field, Struct},
+    },
+    Str,
+};
+
+/// Primitive types that getters return by value
+const COPY_TYPES: &[&str] = &[
+    "bool", "char", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128",
+    "isize", "f32", "f64",
+];
+
+/// Options for [`accessors`]
+#[derive(Clone, Debug)]
+pub struct AccessorOptions {
+    constructor: bool,
+    getters: bool,
+    getters_mut: bool,
+    setters: bool,
+    inline: bool,
+    skip: Vec<Str>,
+    copy: Vec<Str>,
+}
+
+impl AccessorOptions {
+    pub fn new() -> Self {
+        Self {
+            constructor: true,
+            getters: true,
+            getters_mut: true,
+            setters: true,
+            inline: false,
+            skip: Vec::new(),
+            copy: Vec::new(),
+        }
+    }
+
+    /// Generates `new` with a parameter per field, enabled by default
+    pub fn set_constructor(&mut self, constructor: bool) -> &mut Self {
+        self.constructor = constructor;
+        self
+    }
+
+    /// Generates `field()` getters, enabled by default
+    pub fn set_getters(&mut self, getters: bool) -> &mut Self {
+        self.getters = getters;
+        self
+    }
+
+    /// Generates `field_mut()` getters, enabled by default
+    pub fn set_getters_mut(&mut self, getters_mut: bool) -> &mut Self {
+        self.getters_mut = getters_mut;
+        self
+    }
+
+    /// Generates `set_field()` setters returning `&mut Self`, enabled by default
+    pub fn set_setters(&mut self, setters: bool) -> &mut Self {
+        self.setters = setters;
+        self
+    }
+
+    /// Marks the accessors `#[inline]`
+    pub fn set_inline(&mut self, inline: bool) -> &mut Self {
+        self.inline = inline;
+        self
+    }
+
+    /// Generates no accessors for a field, it is still a parameter of `new`
+    pub fn add_skip(&mut self, field: impl Into<Str>) -> &mut Self {
+        self.skip.push(field.into());
+        self
+    }
+
+    /// Returns fields of `type` by value, in addition to the primitive types
+    pub fn add_copy(&mut self, r#type: impl Into<Str>) -> &mut Self {
+        self.copy.push(r#type.into());
+        self
+    }
+
+    pub fn constructor(&self) -> bool {
+        self.constructor
+    }
+
+    pub fn getters(&self) -> bool {
+        self.getters
+    }
+
+    pub fn getters_mut(&self) -> bool {
+        self.getters_mut
+    }
+
+    pub fn setters(&self) -> bool {
+        self.setters
+    }
+
+    pub fn is_inline(&self) -> bool {
+        self.inline
+    }
+
+    pub fn is_skipped(&self, field: &str) -> bool {
+        self.skip.iter().any(|skip| skip == field)
+    }
+
+    pub fn is_copy(&self, r#type: &str) -> bool {
+        COPY_TYPES.contains(&r#type) || self.copy.iter().any(|copy| copy == r#type)
+    }
+}
+
+impl Default for AccessorOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Generates the inherent impl with the constructor and accessors of `r#struct`
+///
+/// The impl has the generic parameters of the struct, without their defaults.
+pub fn accessors(r#struct: &Struct, options: &AccessorOptions) -> Impl {
+    let mut r#impl = derive::generic_impl(r#struct.name(), r#struct.generics(), None);
+    if options.constructor {
+        let new = r#impl.add_function("new");
+        new.set_public(true);
+        let mut init = expr::init_struct("Self");
+        for field in r#struct.fields() {
+            new.add_param(field.name().to_owned(), field.r#type().to_owned());
+            init = init.field_auto(field.name().to_owned());
+        }
+        if options.inline {
+            new.add_attribute("inline");
+        }
+        new.set_return_type("Self").add_body().add_tail(init);
+    }
+    for field in r#struct.fields() {
+        if !options.is_skipped(field.name()) {
+            add_accessors(&mut r#impl, field, options);
+        }
+    }
+    r#impl
+}
+
+fn add_accessors(r#impl: &mut Impl, field: &Field, options: &AccessorOptions) {
+    let name = field.name().trim_start_matches("r#");
+    let r#type = field.r#type();
+    if options.getters {
+        let getter = r#impl.add_function(field.name().to_owned());
+        getter.set_public(true).add_self_param(true, false);
+        match options.is_copy(r#type) {
+            true => getter
+                .set_return_type(r#type.to_owned())
+                .add_body()
+                .add_tail(self_field(field.name())),
+            false => getter
+                .set_return_type(format!("&{type}"))
+                .add_body()
+                .add_tail(self_field(field.name()).reference()),
+        };
+        annotate(getter, field, options);
+    }
+    if options.getters_mut {
+        let getter = r#impl.add_function(format!("{name}_mut"));
+        getter
+            .set_public(true)
+            .add_self_param(true, true)
+            .set_return_type(format!("&mut {type}"))
+            .add_body()
+            .add_tail(self_field(field.name()).reference_mut());
+        annotate(getter, field, options);
+    }
+    if options.setters {
+        let setter = r#impl.add_function(format!("set_{name}"));
+        annotate(setter, field, options);
+        let body = setter
+            .set_public(true)
+            .add_self_param(true, true)
+            .add_param(field.name().to_owned(), r#type.to_owned())
+            .set_return_type("&mut Self")
+            .add_body();
+        body.add_assign(
+            self_field(field.name()),
+            expr::binding(field.name().to_owned()),
+        );
+        body.add_tail(expr::binding("self"));
+    }
+}
+
+/// Copies the field doc and adds `#[inline]` if enabled
+fn annotate(function: &mut Function, field: &Field, options: &AccessorOptions) {
+    if let Some(doc) = field.doc() {
+        function.set_doc(doc.to_owned());
+    }
+    if options.inline {
+        function.add_attribute("inline");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        derive::tests::assert_compiles,
+        generator::GenerateCode,
+        nodes::{Module, ModuleNode},
+    };
+
+    #[test]
+    fn keyword_fields() {
+        let mut r#struct = Struct::new("Token".into());
+        r#struct.add_field("r#type", "Kind").set_doc("The kind");
+        let mut options = AccessorOptions::new();
+        options.set_constructor(false).set_inline(true);
+        assert_eq!(
+            accessors(&r#struct, &options).to_code_string(),
+            concat!(
+                "impl Token {\n",
+                "    /// The kind\n    #[inline]\n",
+                "    pub fn r#type(&self) -> &Kind {\n        &self.r#type\n    }\n\n",
+                "    /// The kind\n    #[inline]\n",
+                "    pub fn type_mut(&mut self) -> &mut Kind {\n        &mut self.r#type\n    }\n\n",
+                "    /// The kind\n    #[inline]\n",
+                "    pub fn set_type(&mut self, r#type: Kind) -> &mut Self {\n",
+                "        self.r#type = r#type;\n        self\n    }\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn skip_and_copy() {
+        let mut r#struct = Struct::new("Cache".into());
+        r#struct.add_field("id", "Id");
+        r#struct.add_field("hits", "u64");
+        r#struct.add_field("entries", "Vec<u8>");
+        let mut options = AccessorOptions::new();
+        options
+            .set_getters_mut(false)
+            .set_setters(false)
+            .add_skip("entries")
+            .add_copy("Id");
+        assert_eq!(
+            accessors(&r#struct, &options).to_code_string(),
+            concat!(
+                "impl Cache {\n",
+                "    pub fn new(id: Id, hits: u64, entries: Vec<u8>) -> Self {\n",
+                "        Self { id, hits, entries }\n    }\n\n",
+                "    pub fn id(&self) -> Id {\n        self.id\n    }\n\n",
+                "    pub fn hits(&self) -> u64 {\n        self.hits\n    }\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn generics() {
+        let mut module = Module::new();
+        let wrap = module.add_struct("Wrap");
+        wrap.add_generic("'a")
+            .add_generic("T: Copy = u8")
+            .add_field("value", "&'a T");
+        let r#impl = accessors(wrap, &AccessorOptions::new());
+        assert!(r#impl
+            .to_code_string()
+            .starts_with("impl<'a, T: Copy> Wrap<'a, T> {\n"));
+        module.add_node(ModuleNode::Impl(r#impl));
+        assert_compiles("accessors-generics", &module.to_string());
+    }
+}
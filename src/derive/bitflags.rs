@@ -0,0 +1,367 @@
+//! Sets of bit flags stored in an integer
+//!
+//! [`bitflags`] generates a `#[repr(transparent)]` [newtype](super::newtype) over an integer with
+//! a constant per flag, the usual set operations and the `BitOr`, `BitAnd`, `BitXor` and `Not`
+//! operators. `Debug` lists the names of the set flags, e.g. `Access(READ | WRITE)`. Flag names
+//! are converted to screaming snake case.
+//!
+//! ```
+//! use codegen_rs::derive::bitflags::{bitflags, BitflagsOptions};
+//!
+//! let mut options = BitflagsOptions::new();
+//! options.set_public(true);
+//! let code = bitflags("Access", "u8", &[("read", 0), ("write", 1), ("exec", 7)], &options)
+//!     .unwrap()
+//!     .to_string();
+//! assert!(code.contains("#[repr(transparent)]\npub struct Access(u8);"));
+//! assert!(code.contains("pub const EXEC: Self = Self(0x80);"));
+//! assert!(code.contains("self.0 |= other.0;"));
+//!
+//! let error = bitflags("Access", "u8", &[("read", 8)], &options).unwrap_err();
+//! assert_eq!(error.to_string(), "bit 8 of `READ` does not fit into `u8`");
+//! ```
+
+use std::fmt::Display;
+
+use crate::{
+    derive::{
+        newtype::{newtype, NewtypeOptions, OperatorTrait},
+        self_field,
+    },
+    expr::{self, Expr, Operator},
+    naming,
+    nodes::{function::Function, r#impl::Impl, Module, ModuleNode},
+    Str,
+};
+
+/// Options for [`bitflags`]
+#[derive(Clone, Debug, Default)]
+pub struct BitflagsOptions {
+    public: bool,
+    derives: Vec<Str>,
+}
+
+impl BitflagsOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_public(&mut self, public: bool) -> &mut Self {
+        self.public = public;
+        self
+    }
+
+    /// Adds a trait to the `#[derive]` attribute, `Clone`, `Copy`, `PartialEq`, `Eq` and `Hash`
+    /// are always derived
+    pub fn add_derive(&mut self, r#trait: impl Into<Str>) -> &mut Self {
+        self.derives.push(r#trait.into());
+        self
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.public
+    }
+
+    pub fn derives(&self) -> &[Str] {
+        &self.derives
+    }
+}
+
+/// Invalid flags passed to [`bitflags`]
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BitflagsError {
+    /// A bit that does not fit into the integer type
+    BitOutOfRange {
+        flag: String,
+        bit: u32,
+        repr: String,
+    },
+    /// A flag name that is used twice after the conversion to screaming snake case
+    Duplicate(String),
+}
+
+impl Display for BitflagsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BitflagsError::BitOutOfRange { flag, bit, repr } => {
+                write!(f, "bit {bit} of `{flag}` does not fit into `{repr}`")
+            }
+            BitflagsError::Duplicate(flag) => write!(f, "`{flag}` is used twice"),
+        }
+    }
+}
+
+impl std::error::Error for BitflagsError {}
+
+/// Generates the flags type `name` over the integer type `repr`, `flags` are names and bit
+/// positions
+///
+/// Returns an error if a name is used twice or a bit does not fit into `repr`, bits of unknown
+/// types must fit into a `u128`.
+pub fn bitflags(
+    name: impl Into<Str>,
+    repr: impl Into<Str>,
+    flags: &[(&str, u32)],
+    options: &BitflagsOptions,
+) -> Result<Module, BitflagsError> {
+    let name: Str = name.into();
+    let repr: Str = repr.into();
+    let mut checked: Vec<(String, u128)> = Vec::with_capacity(flags.len());
+    for &(flag, bit) in flags {
+        let flag = naming::screaming_snake_ident(flag);
+        if bit >= width(&repr) {
+            return Err(BitflagsError::BitOutOfRange {
+                flag,
+                bit,
+                repr: repr.into_owned(),
+            });
+        }
+        if checked.iter().any(|(other, _)| *other == flag) {
+            return Err(BitflagsError::Duplicate(flag));
+        }
+        checked.push((flag, 1 << bit));
+    }
+    let flags = checked;
+
+    let mut newtype_options = NewtypeOptions::new();
+    for r#trait in ["Clone", "Copy", "PartialEq", "Eq", "Hash"] {
+        newtype_options.add_derive(r#trait);
+    }
+    for r#trait in &options.derives {
+        newtype_options.add_derive(r#trait.clone());
+    }
+    newtype_options
+        .set_public(options.public)
+        .set_from(false)
+        .set_into(false)
+        .add_operator(OperatorTrait::BitOr)
+        .add_operator(OperatorTrait::BitAnd)
+        .add_operator(OperatorTrait::BitXor);
+    let mut module = newtype(name.clone(), repr.clone(), &newtype_options);
+    if let Some(r#struct) = module.get_struct_mut(&name) {
+        r#struct.add_attribute("repr(transparent)");
+    }
+    // The inherent impl follows the struct, before the operators
+    module.insert_at(1, ModuleNode::Impl(inherent(&name, &repr, &flags)));
+    module.add_node(ModuleNode::Impl(not(&name)));
+    module.add_node(ModuleNode::Impl(debug(&name)));
+    Ok(module)
+}
+
+/// Generates the flag constants and the set operations
+fn inherent(name: &Str, repr: &Str, flags: &[(String, u128)]) -> Impl {
+    let value = || self_field("0");
+    let other = || expr::binding("other").field("0");
+    let mut r#impl = Impl::new(name.clone());
+    for (flag, bits) in flags {
+        r#impl
+            .add_const(
+                flag.clone(),
+                "Self",
+                expr::call("Self").arg(expr::hex(*bits)),
+            )
+            .set_public(true);
+    }
+    let names = flags.iter().fold(expr::array(), |names, (flag, _)| {
+        names.add(expr::tuple_of(vec![
+            expr::str(flag.clone()),
+            expr::binding(format!("Self::{flag}")),
+        ]))
+    });
+    r#impl.add_const(
+        "NAMES",
+        format!("[(&'static str, Self); {}]", flags.len()),
+        names,
+    );
+
+    let all = flags.iter().fold(0, |all, (_, bits)| all | bits);
+    add_const_fn(&mut r#impl, "empty", "Returns a set without flags")
+        .set_return_type("Self")
+        .add_body()
+        .add_tail(expr::call("Self").arg(expr::integer(0)));
+    add_const_fn(&mut r#impl, "all", "Returns a set with every flag")
+        .set_return_type("Self")
+        .add_body()
+        .add_tail(expr::call("Self").arg(expr::hex(all)));
+    add_const_fn(&mut r#impl, "bits", "Returns the underlying integer")
+        .add_self_param(true, false)
+        .set_return_type(repr.clone())
+        .add_body()
+        .add_tail(value());
+    add_const_fn(
+        &mut r#impl,
+        "from_bits_truncate",
+        "Creates a set from an integer, unknown bits are dropped",
+    )
+    .add_param("bits", repr.clone())
+    .set_return_type("Self")
+    .add_body()
+    .add_tail(
+        expr::call("Self")
+            .arg(expr::binding("bits").bit_and(Expr::from(expr::call("Self::all")).field("0"))),
+    );
+    add_const_fn(&mut r#impl, "is_empty", "Returns whether no flag is set")
+        .add_self_param(true, false)
+        .set_return_type("bool")
+        .add_body()
+        .add_tail(value().equals(expr::integer(0)));
+    add_const_fn(
+        &mut r#impl,
+        "contains",
+        "Returns whether every flag of `other` is set",
+    )
+    .add_self_param(true, false)
+    .add_param("other", "Self")
+    .set_return_type("bool")
+    .add_body()
+    .add_tail(value().bit_and(other()).equals(other()));
+
+    let updates: [(&str, &str, Operator, Expr); 3] = [
+        ("insert", "Sets the flags of `other`", Operator::Or, other()),
+        (
+            "remove",
+            "Clears the flags of `other`",
+            Operator::And,
+            other().not(),
+        ),
+        (
+            "toggle",
+            "Flips the flags of `other`",
+            Operator::Xor,
+            other(),
+        ),
+    ];
+    for (method, doc, operator, rhs) in updates {
+        r#impl
+            .add_function(method)
+            .set_doc(doc)
+            .set_public(true)
+            .add_self_param(true, true)
+            .add_param("other", "Self")
+            .add_body()
+            .add_assign(value(), rhs)
+            .set_operator(operator);
+    }
+    r#impl
+}
+
+/// Adds a documented `pub const fn`
+fn add_const_fn<'a>(
+    r#impl: &'a mut Impl,
+    name: &'static str,
+    doc: &'static str,
+) -> &'a mut Function {
+    r#impl
+        .add_function(name)
+        .set_doc(doc)
+        .set_public(true)
+        .set_const(true)
+}
+
+/// Implements `Not` as the complement within the known flags
+fn not(name: &Str) -> Impl {
+    let mut r#impl = Impl::new(name.clone());
+    r#impl
+        .set_trait("std::ops::Not")
+        .add_type_alias("Output", "Self");
+    r#impl
+        .add_function("not")
+        .add_self_param(false, false)
+        .set_return_type("Self::Output")
+        .add_body()
+        .add_tail(expr::call("Self::from_bits_truncate").arg(self_field("0").not()));
+    r#impl
+}
+
+/// Implements `Debug` by listing the names of the set flags
+fn debug(name: &Str) -> Impl {
+    let mut r#impl = Impl::new(name.clone());
+    r#impl
+        .set_trait("std::fmt::Debug")
+        .add_function("fmt")
+        .add_self_param(true, false)
+        .add_param("f", "&mut std::fmt::Formatter<'_>")
+        .set_return_type("std::fmt::Result")
+        .add_body()
+        .add_raw(format!(
+            r#"f.write_str("{name}(")?;
+let mut first = true;
+for (name, flag) in Self::NAMES {{
+    if self.contains(flag) {{
+        if !first {{
+            f.write_str(" | ")?;
+        }}
+        first = false;
+        f.write_str(name)?;
+    }}
+}}
+if first {{
+    f.write_str("0x0")?;
+}}
+f.write_str(")")"#
+        ));
+    r#impl
+}
+
+/// Returns the number of usable bits of an integer type, 128 for unknown types
+///
+/// The sign bit is excluded, a literal setting it would overflow. `usize` and `isize` are capped
+/// at the pointer width.
+fn width(repr: &str) -> u32 {
+    match repr {
+        "u8" => 8,
+        "u16" => 16,
+        "u32" => 32,
+        "u64" => 64,
+        "u128" => 128,
+        "usize" => usize::BITS,
+        "i8" => 7,
+        "i16" => 15,
+        "i32" => 31,
+        "i64" => 63,
+        "isize" => isize::BITS - 1,
+        "i128" => 127,
+        _ => 128,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(repr: &'static str, flags: &[(&str, u32)]) -> BitflagsError {
+        bitflags("Flags", repr, flags, &BitflagsOptions::new()).unwrap_err()
+    }
+
+    #[test]
+    fn bit_out_of_range() {
+        let out_of_range = |flag: &str, bit, repr: &str| BitflagsError::BitOutOfRange {
+            flag: flag.into(),
+            bit,
+            repr: repr.into(),
+        };
+        assert_eq!(error("i8", &[("sign", 7)]), out_of_range("SIGN", 7, "i8"));
+        assert_eq!(
+            error("usize", &[("a", usize::BITS)]),
+            out_of_range("A", usize::BITS, "usize")
+        );
+        // Unknown types are checked against the widest integer instead of overflowing
+        assert_eq!(error("Bits", &[("a", 128)]), out_of_range("A", 128, "Bits"));
+        let module = bitflags(
+            "Flags",
+            "usize",
+            &[("a", usize::BITS - 1)],
+            &Default::default(),
+        );
+        assert!(module.is_ok());
+    }
+
+    #[test]
+    fn duplicate() {
+        assert_eq!(
+            error("u8", &[("read_only", 0), ("ReadOnly", 1)]),
+            BitflagsError::Duplicate("READ_ONLY".into())
+        );
+    }
+}
@@ -0,0 +1,433 @@
+//! Builder types for structs
+//!
+//! [`builder`] generates a `FooBuilder` for a struct `Foo` with a setter per field and a `build`
+//! method that fails with a `BuilderError` naming the fields that were not set.
+//!
+//! ```
+//! use codegen_rs::{derive::builder::{builder, BuilderOptions}, expr, nodes::Module};
+//!
+//! let mut module = Module::new();
+//! let config = module.add_struct("Config");
+//! config.set_public(true);
+//! config.add_field("host", "String");
+//! config.add_field("port", "u16");
+//! config.add_field("timeout", "Option<u64>");
+//! let config = config.clone();
+//!
+//! let mut options = BuilderOptions::new();
+//! options.set_default("port", expr::u16(8080));
+//! module.merge(builder(&config, &options)).unwrap();
+//! assert!(module.get_struct("ConfigBuilder").is_some());
+//! assert!(module.get_struct("BuilderError").is_some());
+//! ```
+
+use crate::{
+    derive::{self, self_field, DeriveOptions},
+    expr::{self, Expr},
+    naming,
+    nodes::{
+        function::Function,
+        r#impl::{Impl, ImplNode},
+        r#struct::Struct,
+        statement::{LetStatement, ReturnStatement, Statement},
+        Module, ModuleNode,
+    },
+    Str,
+};
+
+/// Options for [`builder`]
+#[derive(Clone, Debug)]
+pub struct BuilderOptions {
+    by_ref: bool,
+    into: bool,
+    defaults: Vec<(Str, Option<Expr>)>,
+}
+
+impl BuilderOptions {
+    pub fn new() -> Self {
+        Self {
+            by_ref: false,
+            into: true,
+            defaults: Vec::new(),
+        }
+    }
+
+    /// Setters take and return `&mut Self` instead of `Self`, `build` clones the values
+    pub fn set_by_ref(&mut self, by_ref: bool) -> &mut Self {
+        self.by_ref = by_ref;
+        self
+    }
+
+    /// Setters accept `impl Into<T>`, enabled by default
+    pub fn set_into(&mut self, into: bool) -> &mut Self {
+        self.into = into;
+        self
+    }
+
+    /// Makes a field optional, it is `Default::default()` if it is not set
+    pub fn set_optional(&mut self, field: impl Into<Str>) -> &mut Self {
+        self.set_field_default(field.into(), None)
+    }
+
+    /// Makes a field optional with the value `expr` if it is not set
+    pub fn set_default(&mut self, field: impl Into<Str>, expr: impl Into<Expr>) -> &mut Self {
+        self.set_field_default(field.into(), Some(expr.into()))
+    }
+
+    pub fn is_by_ref(&self) -> bool {
+        self.by_ref
+    }
+
+    pub fn into(&self) -> bool {
+        self.into
+    }
+
+    /// Returns `Some` for optional fields, containing the default expression if there is one
+    pub fn default(&self, field: &str) -> Option<Option<&Expr>> {
+        self.defaults
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, expr)| expr.as_ref())
+    }
+
+    fn set_field_default(&mut self, field: Str, expr: Option<Expr>) -> &mut Self {
+        self.defaults.retain(|(name, _)| *name != field);
+        self.defaults.push((field, expr));
+        self
+    }
+}
+
+impl Default for BuilderOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Generates the builder for `r#struct`
+///
+/// The returned module contains `FooBuilder`, its impls, `Foo::builder()` and the shared
+/// `BuilderError` type. Merge it into the module of the struct with
+/// [`Module::merge`](crate::nodes::Module::merge), which keeps a single `BuilderError` for
+/// multiple builders. Fields of type `Option<T>` are always optional and default to `None`.
+///
+/// The builder has the generic parameters of the struct. Its impl requires `Clone` for field types
+/// with type parameters if the setters take `&mut self`, and `Default` for those that are optional.
+pub fn builder(r#struct: &Struct, options: &BuilderOptions) -> Module {
+    let name = r#struct.name();
+    let generics = r#struct.generics();
+    let builder_name = format!("{name}Builder");
+    let mut module = Module::new();
+
+    let builder = module.add_struct(builder_name.clone());
+    builder.set_public(r#struct.is_public());
+    for generic in generics {
+        builder.add_generic(generic.clone());
+    }
+    for field in r#struct.fields() {
+        let r#type = match option_type(field.r#type()) {
+            Some(_) => field.r#type().to_owned(),
+            None => format!("Option<{}>", field.r#type()),
+        };
+        builder.add_field(field.name().to_owned(), r#type);
+    }
+    // Unlike a derive, the impl does not require `T: Default` as the fields are options
+    let mut default = derive::generic_impl(&builder_name, generics, None);
+    default
+        .set_trait("Default")
+        .add_function("default")
+        .set_return_type("Self")
+        .add_body()
+        .add_tail(derive::init_self(builder, |_| derive::default_value()));
+
+    let mut r#impl = derive::generic_impl(&builder_name, generics, None);
+    for predicate in where_predicates(r#struct, options) {
+        r#impl.add_where_predicate(predicate);
+    }
+    r#impl
+        .add_function("new")
+        .set_public(true)
+        .set_return_type("Self")
+        .add_body()
+        .add_tail(expr::call("Self::default"));
+    for field in r#struct.fields() {
+        let param = naming::escape_ident(field.name());
+        let inner = option_type(field.r#type()).unwrap_or(field.r#type());
+        let r#type = match options.into {
+            true => format!("impl Into<{inner}>"),
+            false => inner.to_owned(),
+        };
+        let value = match options.into {
+            true => expr::binding(param.clone()).method("into").into(),
+            false => expr::binding(param.clone()),
+        };
+        let setter = r#impl.add_function(field.name().to_owned());
+        setter.set_public(true);
+        match options.by_ref {
+            true => setter
+                .add_self_param(true, true)
+                .set_return_type("&mut Self"),
+            false => setter.add_self_param(false, true).set_return_type("Self"),
+        };
+        let body = setter.add_param(param, r#type).add_body();
+        body.add_assign(self_field(field.name()), expr::call("Some").arg(value));
+        body.add_tail(expr::binding("self"));
+    }
+    r#impl.add_node(ImplNode::Function(build(r#struct, options)));
+
+    let mut constructor = derive::generic_impl(name, generics, None);
+    constructor
+        .add_function("builder")
+        .set_public(true)
+        .set_return_type(derive::generic_type(&builder_name, generics))
+        .add_body()
+        .add_tail(expr::call(format!("{builder_name}::default")));
+
+    module.add_node(ModuleNode::Impl(default));
+    module.add_node(ModuleNode::Impl(r#impl));
+    module.add_node(ModuleNode::Impl(constructor));
+    for node in builder_error().into_nodes() {
+        module.add_node(node);
+    }
+    module
+}
+
+/// The bounds that `build` needs for field types with type parameters: `Clone` for all fields if
+/// the setters take `&mut self` and `Default` for optional fields without a default expression
+fn where_predicates(r#struct: &Struct, options: &BuilderOptions) -> Vec<String> {
+    let params: Vec<&str> = r#struct
+        .generics()
+        .iter()
+        .filter(|generic| !generic.starts_with('\'') && !generic.starts_with("const "))
+        .filter_map(|generic| generic.split([':', '=']).next())
+        .map(str::trim)
+        .collect();
+    let mut predicates = Vec::new();
+    for field in r#struct.fields() {
+        let inner = option_type(field.r#type()).unwrap_or(field.r#type());
+        let is_generic = inner
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .any(|word| params.contains(&word));
+        if !is_generic {
+            continue;
+        }
+        let mut bounds = Vec::new();
+        if options.by_ref {
+            bounds.push("Clone");
+        }
+        if option_type(field.r#type()).is_none() && options.default(field.name()) == Some(None) {
+            bounds.push("Default");
+        }
+        let predicate = format!("{inner}: {}", bounds.join(" + "));
+        if !bounds.is_empty() && !predicates.contains(&predicate) {
+            predicates.push(predicate);
+        }
+    }
+    predicates
+}
+
+/// Generates `build`, which checks that the required fields are set
+fn build(r#struct: &Struct, options: &BuilderOptions) -> Function {
+    let mut build = Function::new("build".into());
+    build
+        .set_public(true)
+        .add_self_param(options.by_ref, false)
+        .set_return_type(format!(
+            "Result<{}, BuilderError>",
+            derive::generic_type(r#struct.name(), r#struct.generics())
+        ));
+    let body = build.add_body();
+    let required: Vec<_> = r#struct
+        .fields()
+        .iter()
+        .filter(|field| {
+            option_type(field.r#type()).is_none() && options.default(field.name()).is_none()
+        })
+        .collect();
+    if !required.is_empty() {
+        let mut missing = LetStatement::new("missing".into(), expr::call("Vec::new").into());
+        missing.set_mut(true);
+        body.add_statement(Statement::Let(Box::new(missing)));
+        for field in &required {
+            body.add_if(self_field(field.name()).method("is_none"))
+                .then_mut()
+                .add_expr(
+                    expr::binding("missing")
+                        .method("push")
+                        .arg(expr::str(field.name().trim_start_matches("r#").to_owned())),
+                );
+        }
+        let error = expr::call("Err").arg(expr::init_struct("BuilderError").field_auto("missing"));
+        body.add_if(expr::not(expr::binding("missing").method("is_empty")))
+            .then_mut()
+            .add_statement(Statement::Return(Box::new(ReturnStatement::new(
+                error.into(),
+            ))));
+    }
+    let mut init = expr::init_struct(r#struct.name().to_owned()).multiline(true);
+    for field in r#struct.fields() {
+        let mut value = self_field(field.name());
+        if options.by_ref {
+            value = value.method("clone").into();
+        }
+        let value: Expr = match options.default(field.name()) {
+            _ if option_type(field.r#type()).is_some() => value,
+            Some(Some(default)) => value.method("unwrap_or").arg(default.clone()).into(),
+            Some(None) => value.method("unwrap_or_default").into(),
+            None => value.method("unwrap").into(),
+        };
+        init = init.field(field.name().to_owned(), value);
+    }
+    body.add_tail(expr::call("Ok").arg(init));
+    build
+}
+
+/// Generates the error returned by `build`
+pub fn builder_error() -> Module {
+    let mut module = Module::new();
+    let error = module.add_struct("BuilderError");
+    error.set_public(true);
+    error.add_field("missing", "Vec<&'static str>");
+    let debug = derive::debug(error, &DeriveOptions::new());
+
+    let mut r#impl = Impl::new("BuilderError".into());
+    r#impl
+        .add_function("missing")
+        .set_public(true)
+        .add_self_param(true, false)
+        .set_return_type("&[&'static str]")
+        .add_body()
+        .add_tail(self_field("missing").reference());
+
+    let mut display = Impl::new("BuilderError".into());
+    display
+        .set_trait("std::fmt::Display")
+        .add_function("fmt")
+        .add_self_param(true, false)
+        .add_param("f", "&mut std::fmt::Formatter<'_>")
+        .set_return_type("std::fmt::Result")
+        .add_body()
+        .add_raw(r#"write!(f, "missing fields: {}", self.missing.join(", "))"#);
+
+    let mut error = Impl::new("BuilderError".into());
+    error.set_trait("std::error::Error");
+
+    module.add_node(ModuleNode::Impl(debug));
+    module.add_node(ModuleNode::Impl(r#impl));
+    module.add_node(ModuleNode::Impl(display));
+    module.add_node(ModuleNode::Impl(error));
+    module
+}
+
+/// Returns `T` for `Option<T>`
+fn option_type(r#type: &str) -> Option<&str> {
+    r#type.strip_prefix("Option<")?.strip_suffix('>')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{derive::tests::assert_compiles, generator::GenerateCode};
+
+    fn config() -> Module {
+        let mut module = Module::new();
+        let config = module.add_struct("Config");
+        config.set_public(true);
+        config.add_field("host", "String");
+        config.add_field("r#type", "u8");
+        config.add_field("port", "u16");
+        config.add_field("retries", "u8");
+        config.add_field("timeout", "Option<u64>");
+        module
+    }
+
+    #[test]
+    fn setters_and_build() {
+        let mut module = config();
+        let mut options = BuilderOptions::new();
+        options
+            .set_default("port", expr::u16(8080))
+            .set_optional("retries");
+        module
+            .merge(builder(module.get_struct("Config").unwrap(), &options))
+            .unwrap();
+        let r#impl = module.get_impl("ConfigBuilder", None).unwrap();
+        assert_eq!(
+            r#impl.function("r#type").unwrap().to_code_string(),
+            "pub fn r#type(mut self, r#type: impl Into<u8>) -> Self {\n    \
+             self.r#type = Some(r#type.into());\n    self\n}\n"
+        );
+        assert_eq!(
+            r#impl.function("build").unwrap().to_code_string(),
+            concat!(
+                "pub fn build(self) -> Result<Config, BuilderError> {\n",
+                "    let mut missing = Vec::new();\n",
+                "    if self.host.is_none() {\n        missing.push(\"host\");\n    }\n",
+                "    if self.r#type.is_none() {\n        missing.push(\"type\");\n    }\n",
+                "    if !missing.is_empty() {\n",
+                "        return Err(BuilderError { missing });\n    }\n",
+                "    Ok(Config {\n",
+                "        host: self.host.unwrap(),\n",
+                "        r#type: self.r#type.unwrap(),\n",
+                "        port: self.port.unwrap_or(8080u16),\n",
+                "        retries: self.retries.unwrap_or_default(),\n",
+                "        timeout: self.timeout,\n",
+                "    })\n",
+                "}\n",
+            )
+        );
+        assert_eq!(
+            module.get_struct("ConfigBuilder").unwrap().to_code_string(),
+            "pub struct ConfigBuilder {\n    host: Option<String>,\n    r#type: Option<u8>,\n    \
+             port: Option<u16>,\n    retries: Option<u8>,\n    timeout: Option<u64>,\n}\n"
+        );
+        assert_compiles("builder", &module.to_string());
+    }
+
+    #[test]
+    fn by_ref() {
+        let mut module = config();
+        let mut options = BuilderOptions::new();
+        options.set_by_ref(true).set_into(false);
+        module
+            .merge(builder(module.get_struct("Config").unwrap(), &options))
+            .unwrap();
+        let r#impl = module.get_impl("ConfigBuilder", None).unwrap();
+        assert_eq!(
+            r#impl.function("timeout").unwrap().to_code_string(),
+            "pub fn timeout(&mut self, timeout: u64) -> &mut Self {\n    \
+             self.timeout = Some(timeout);\n    self\n}\n"
+        );
+        let build = r#impl.function("build").unwrap().to_code_string();
+        assert!(build.starts_with("pub fn build(&self) -> Result<Config, BuilderError> {\n"));
+        assert!(build.contains("        port: self.port.clone().unwrap(),\n"));
+        assert!(build.contains("        timeout: self.timeout.clone(),\n"));
+        assert_compiles("builder-by-ref", &module.to_string());
+    }
+
+    #[test]
+    fn generics() {
+        let mut module = Module::new();
+        let wrap = module.add_struct("Wrap");
+        wrap.add_generic("'a").add_generic("T: Copy");
+        wrap.add_field("value", "T");
+        wrap.add_field("items", "Vec<T>");
+        wrap.add_field("name", "&'a str");
+        let mut options = BuilderOptions::new();
+        options.set_by_ref(true).set_optional("items");
+        module
+            .merge(builder(module.get_struct("Wrap").unwrap(), &options))
+            .unwrap();
+        let code = module.to_string();
+        assert!(code.contains("struct WrapBuilder<'a, T: Copy> {\n"));
+        assert!(code.contains("impl<'a, T: Copy> Default for WrapBuilder<'a, T> {\n"));
+        assert!(code.contains(
+            "impl<'a, T: Copy> WrapBuilder<'a, T>\nwhere\n    T: Clone,\n    \
+             Vec<T>: Clone + Default,\n{\n"
+        ));
+        assert!(code.contains("    pub fn build(&self) -> Result<Wrap<'a, T>, BuilderError> {\n"));
+        assert!(code.contains(
+            "impl<'a, T: Copy> Wrap<'a, T> {\n    pub fn builder() -> WrapBuilder<'a, T> {\n"
+        ));
+        assert_compiles("builder-generics", &code);
+    }
+}
@@ -0,0 +1,490 @@
+//! String and integer conversions for C-like enums
+//!
+//! [`enum_helpers`] generates `as_str()`, `Display` and `FromStr` from the variant names, the
+//! `ALL` and `COUNT` constants and, for enums with explicit discriminants, conversions from and to
+//! the integer representation. The string form of each variant is its name converted with a
+//! [`RenameRule`].
+//!
+//! ```
+//! use codegen_rs::{
+//!     derive::enum_helpers::{enum_helpers, EnumHelperOptions, RenameRule},
+//!     nodes::Module,
+//! };
+//!
+//! let mut module = Module::new();
+//! let color = module.add_enum("Color");
+//! color.add_variant("DarkRed");
+//! color.add_variant("LightBlue");
+//! let color = color.clone();
+//!
+//! let mut options = EnumHelperOptions::new();
+//! options.set_rename(RenameRule::KebabCase);
+//! module.merge(enum_helpers(&color, &options)).unwrap();
+//! assert!(module.to_string().contains(r#"Self::DarkRed => "dark-red","#));
+//! assert!(module.get_struct("ParseColorError").is_some());
+//! ```
+
+use crate::{
+    derive::{
+        self,
+        accessors::{accessors, AccessorOptions},
+        DeriveOptions,
+    },
+    expr::{self, Expr},
+    generator::GenerateCode,
+    naming,
+    nodes::{
+        r#enum::{Enum, VariantFields},
+        r#impl::Impl,
+        Module, ModuleNode,
+    },
+    Str,
+};
+
+/// How variant names are converted to strings
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RenameRule {
+    /// The variant name as written
+    #[default]
+    None,
+    /// `darkred`
+    LowerCase,
+    /// `DARKRED`
+    UpperCase,
+    /// `darkRed`
+    CamelCase,
+    /// `DarkRed`
+    PascalCase,
+    /// `dark_red`
+    SnakeCase,
+    /// `DARK_RED`
+    ScreamingSnakeCase,
+    /// `dark-red`
+    KebabCase,
+    /// `DARK-RED`
+    ScreamingKebabCase,
+}
+
+impl RenameRule {
+    /// Converts a variant name, see [`naming`] for how names are split into words
+    pub fn apply(self, name: &str) -> String {
+        let name = name.trim_start_matches("r#");
+        match self {
+            RenameRule::None => name.to_owned(),
+            RenameRule::LowerCase => name.to_lowercase(),
+            RenameRule::UpperCase => name.to_uppercase(),
+            RenameRule::CamelCase => naming::to_camel_case(name),
+            RenameRule::PascalCase => naming::to_pascal_case(name),
+            RenameRule::SnakeCase => naming::to_snake_case(name),
+            RenameRule::ScreamingSnakeCase => naming::to_screaming_snake_case(name),
+            RenameRule::KebabCase => naming::to_kebab_case(name),
+            RenameRule::ScreamingKebabCase => naming::to_kebab_case(name).to_uppercase(),
+        }
+    }
+}
+
+/// Options for [`enum_helpers`]
+#[derive(Clone, Debug, Default)]
+pub struct EnumHelperOptions {
+    rename: RenameRule,
+    renames: Vec<(Str, Str)>,
+    case_insensitive: bool,
+    error: Option<Str>,
+    repr: Option<Str>,
+}
+
+impl EnumHelperOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the rule converting variant names to strings
+    pub fn set_rename(&mut self, rule: RenameRule) -> &mut Self {
+        self.rename = rule;
+        self
+    }
+
+    /// Uses `string` for a variant instead of applying the rename rule
+    pub fn set_variant_str(
+        &mut self,
+        variant: impl Into<Str>,
+        string: impl Into<Str>,
+    ) -> &mut Self {
+        let variant = variant.into();
+        self.renames.retain(|(name, _)| *name != variant);
+        self.renames.push((variant, string.into()));
+        self
+    }
+
+    /// `FromStr` ignores ASCII case
+    pub fn set_case_insensitive(&mut self, case_insensitive: bool) -> &mut Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// Sets the name of the `FromStr` error, `ParseFooError` by default
+    pub fn set_error(&mut self, name: impl Into<Str>) -> &mut Self {
+        self.error = Some(name.into());
+        self
+    }
+
+    /// Sets the integer type for the conversions, defaults to the `#[repr]` of the enum or `isize`
+    pub fn set_repr(&mut self, r#type: impl Into<Str>) -> &mut Self {
+        self.repr = Some(r#type.into());
+        self
+    }
+
+    pub fn rename(&self) -> RenameRule {
+        self.rename
+    }
+
+    pub fn is_case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn repr(&self) -> Option<&str> {
+        self.repr.as_deref()
+    }
+
+    /// Returns the string form of a variant
+    pub fn variant_str(&self, variant: &str) -> String {
+        self.renames
+            .iter()
+            .find(|(name, _)| name == variant)
+            .map_or_else(
+                || self.rename.apply(variant),
+                |(_, string)| string.to_string(),
+            )
+    }
+}
+
+/// Generates the helpers for the C-like enum `r#enum`
+///
+/// The returned module contains the inherent impl with `ALL`, `COUNT` and `as_str`, the `Display`
+/// and `FromStr` impls and the error type of `FromStr`. `TryFrom` and `From` for the integer
+/// representation are only generated if a variant has an explicit discriminant or the enum has a
+/// `#[repr]` attribute. The impls for the enum have its generic parameters, such as const
+/// parameters.
+///
+/// # Panics
+///
+/// Panics if a variant has fields.
+pub fn enum_helpers(r#enum: &Enum, options: &EnumHelperOptions) -> Module {
+    let name = r#enum.name();
+    assert!(
+        r#enum
+            .variants()
+            .iter()
+            .all(|variant| matches!(variant.fields(), VariantFields::Unit)),
+        "`{name}` is not a C-like enum"
+    );
+    let variants: Vec<(String, String)> = r#enum
+        .variants()
+        .iter()
+        .map(|variant| {
+            (
+                format!("Self::{}", variant.name()),
+                options.variant_str(variant.name()),
+            )
+        })
+        .collect();
+    let count = variants.len();
+    let mut module = Module::new();
+
+    let mut r#impl = derive::generic_impl(name, r#enum.generics(), None);
+    let all = variants.iter().fold(expr::array(), |all, (path, _)| {
+        all.add(expr::binding(path.clone()))
+    });
+    r#impl
+        .add_const("ALL", format!("[Self; {count}]"), all)
+        .set_public(true);
+    r#impl
+        .add_const("COUNT", "usize", expr::integer(count as u128))
+        .set_public(true);
+    let as_str = variants.iter().fold(
+        expr::r#match(expr::binding("self")),
+        |as_str, (path, string)| as_str.arm(path.clone(), expr::str(string.clone())),
+    );
+    r#impl
+        .add_function("as_str")
+        .set_public(true)
+        .set_const(true)
+        .add_self_param(true, false)
+        .set_return_type("&'static str")
+        .add_body()
+        .add_tail(as_str);
+    module.add_node(ModuleNode::Impl(r#impl));
+
+    let mut display = derive::generic_impl(name, r#enum.generics(), None);
+    display
+        .set_trait("std::fmt::Display")
+        .add_function("fmt")
+        .add_self_param(true, false)
+        .add_param("f", "&mut std::fmt::Formatter<'_>")
+        .set_return_type("std::fmt::Result")
+        .add_body()
+        .add_tail(
+            expr::binding("f")
+                .method("write_str")
+                .arg(expr::binding("self").method("as_str")),
+        );
+    module.add_node(ModuleNode::Impl(display));
+
+    let error = options
+        .error
+        .clone()
+        .unwrap_or_else(|| format!("Parse{name}Error").into());
+    let mut from_str = derive::generic_impl(name, r#enum.generics(), None);
+    from_str.set_trait("std::str::FromStr");
+    from_str.add_type_alias("Err", error.clone());
+    let mut parse = expr::r#match(expr::binding("s"));
+    for (path, string) in &variants {
+        let value = expr::call("Ok").arg(expr::binding(path.clone()));
+        parse = match options.case_insensitive {
+            true => parse.arm_if(
+                "_",
+                expr::binding("s")
+                    .method("eq_ignore_ascii_case")
+                    .arg(expr::str(string.clone())),
+                value,
+            ),
+            false => parse.arm(expr::str(string.clone()).to_code_string(), value),
+        };
+    }
+    let err =
+        expr::init_struct(error.clone()).field("input", expr::binding("s").method("to_owned"));
+    parse = parse.arm("_", expr::call("Err").arg(err));
+    from_str
+        .add_function("from_str")
+        .add_param("s", "&str")
+        .set_return_type("Result<Self, Self::Err>")
+        .add_body()
+        .add_tail(parse);
+    module.add_node(ModuleNode::Impl(from_str));
+
+    let repr = options.repr.clone().or_else(|| repr(r#enum));
+    let discriminants = r#enum
+        .variants()
+        .iter()
+        .any(|variant| variant.discriminant().is_some());
+    if discriminants || repr.is_some() {
+        let repr = repr.unwrap_or("isize".into());
+        for r#impl in integer_conversions(r#enum, &repr) {
+            module.add_node(ModuleNode::Impl(r#impl));
+        }
+    }
+
+    for node in parse_error(r#enum, &error).into_nodes() {
+        module.add_node(node);
+    }
+    module
+}
+
+/// Generates `TryFrom<repr>` for the enum and `From<enum>` for `repr`
+fn integer_conversions(r#enum: &Enum, repr: &str) -> [Impl; 2] {
+    let mut parse = expr::r#match(expr::binding("value"));
+    for variant in r#enum.variants() {
+        let path = format!("Self::{}", variant.name());
+        let value = expr::call("Ok").arg(expr::binding(path.clone()));
+        // Literal discriminants are used as patterns, others are compared in a guard
+        parse = match variant.discriminant() {
+            Some(discriminant @ Expr::Literal(_)) => {
+                parse.arm(discriminant.to_code_string(), value)
+            }
+            _ => parse.arm_if(
+                "_",
+                expr::binding("value").equals(expr::binding(path).cast(repr.to_owned())),
+                value,
+            ),
+        };
+    }
+    parse = parse.arm("_", expr::call("Err").arg(expr::binding("value")));
+    let mut try_from = derive::generic_impl(r#enum.name(), r#enum.generics(), None);
+    try_from
+        .set_trait(format!("TryFrom<{repr}>"))
+        .add_type_alias("Error", repr.to_owned());
+    try_from
+        .add_function("try_from")
+        .add_param("value", repr.to_owned())
+        .set_return_type("Result<Self, Self::Error>")
+        .add_body()
+        .add_tail(parse);
+
+    let r#type = derive::generic_type(r#enum.name(), r#enum.generics());
+    // An impl for `repr` that still needs the generic parameters of the enum
+    let mut from = derive::generic_impl(repr, r#enum.generics(), None);
+    from.set_target(repr.to_owned());
+    from.set_trait(format!("From<{type}>"))
+        .add_function("from")
+        .add_param("value", r#type)
+        .set_return_type("Self")
+        .add_body()
+        .add_tail(expr::binding("value").cast(repr.to_owned()));
+    [try_from, from]
+}
+
+/// Generates the error returned by `FromStr`, it keeps the input
+fn parse_error(r#enum: &Enum, name: &str) -> Module {
+    let mut module = Module::new();
+    let error = module.add_struct(name.to_owned());
+    error.set_public(r#enum.is_public());
+    error.add_field("input", "String");
+    let error = error.clone();
+    let debug = derive::debug(&error, &DeriveOptions::new());
+    let mut getters = AccessorOptions::new();
+    getters
+        .set_constructor(false)
+        .set_getters_mut(false)
+        .set_setters(false);
+    let mut input = accessors(&error, &getters);
+    if let Some(function) = input.function_mut("input") {
+        function.set_doc("The string that did not match a variant");
+    }
+
+    let mut display = Impl::new(name.to_owned().into());
+    display
+        .set_trait("std::fmt::Display")
+        .add_function("fmt")
+        .add_self_param(true, false)
+        .add_param("f", "&mut std::fmt::Formatter<'_>")
+        .set_return_type("std::fmt::Result")
+        .add_body()
+        .add_raw(format!(
+            r#"write!(f, "unknown {} `{{}}`", self.input)"#,
+            r#enum.name()
+        ));
+
+    let mut impl_error = Impl::new(name.to_owned().into());
+    impl_error.set_trait("std::error::Error");
+
+    module.add_node(ModuleNode::Impl(debug));
+    module.add_node(ModuleNode::Impl(input));
+    module.add_node(ModuleNode::Impl(display));
+    module.add_node(ModuleNode::Impl(impl_error));
+    module
+}
+
+/// Returns the integer type of a `#[repr(...)]` attribute
+fn repr(r#enum: &Enum) -> Option<Str> {
+    r#enum.attributes().iter().find_map(|attribute| {
+        let inner = attribute.strip_prefix("repr(")?.strip_suffix(')')?;
+        inner
+            .split(',')
+            .map(str::trim)
+            .find(|repr| repr.starts_with('u') || repr.starts_with('i'))
+            .map(|repr| repr.to_owned().into())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::derive::tests::assert_compiles;
+
+    /// Merges the helpers of `r#enum` into a module containing it
+    fn helpers(r#enum: Enum, options: &EnumHelperOptions) -> Module {
+        let mut module = Module::new();
+        module.add_node(ModuleNode::Enum(r#enum.clone()));
+        module.merge(enum_helpers(&r#enum, options)).unwrap();
+        module
+    }
+
+    #[test]
+    fn strings() {
+        let mut r#enum = Enum::new("Keyword".into());
+        r#enum.set_public(true);
+        r#enum.add_variant("r#type");
+        r#enum.add_variant("HttpRequest");
+        r#enum.add_variant("Other");
+        let mut options = EnumHelperOptions::new();
+        options
+            .set_rename(RenameRule::ScreamingKebabCase)
+            .set_variant_str("Other", "*")
+            .set_case_insensitive(true)
+            .set_error("UnknownKeyword");
+        let module = helpers(r#enum, &options);
+        let r#impl = module.get_impl("Keyword", None).unwrap();
+        assert_eq!(
+            r#impl.to_code_string(),
+            concat!(
+                "impl Keyword {\n",
+                "    pub const ALL: [Self; 3] = [Self::r#type, Self::HttpRequest, Self::Other];\n",
+                "    pub const COUNT: usize = 3;\n\n",
+                "    pub const fn as_str(&self) -> &'static str {\n",
+                "        match self {\n",
+                "            Self::r#type => \"TYPE\",\n",
+                "            Self::HttpRequest => \"HTTP-REQUEST\",\n",
+                "            Self::Other => \"*\",\n",
+                "        }\n    }\n}\n",
+            )
+        );
+        let from_str = module
+            .get_impl("Keyword", Some("std::str::FromStr"))
+            .unwrap();
+        assert_eq!(
+            from_str.function("from_str").unwrap().to_code_string(),
+            concat!(
+                "fn from_str(s: &str) -> Result<Self, Self::Err> {\n",
+                "    match s {\n",
+                "        _ if s.eq_ignore_ascii_case(\"TYPE\") => Ok(Self::r#type),\n",
+                "        _ if s.eq_ignore_ascii_case(\"HTTP-REQUEST\") => Ok(Self::HttpRequest),\n",
+                "        _ if s.eq_ignore_ascii_case(\"*\") => Ok(Self::Other),\n",
+                "        _ => Err(UnknownKeyword { input: s.to_owned() }),\n",
+                "    }\n}\n",
+            )
+        );
+        assert!(module.get_struct("UnknownKeyword").unwrap().is_public());
+        assert!(module.get_impl("Keyword", Some("TryFrom<isize>")).is_none());
+        assert_compiles("enum-helpers", &module.to_string());
+    }
+
+    #[test]
+    fn integer_conversions() {
+        let mut r#enum = Enum::new("Level".into());
+        r#enum.add_attribute("repr(u8)");
+        r#enum.add_variant("Low").set_discriminant(expr::u8(1));
+        r#enum
+            .add_variant("High")
+            .set_discriminant(expr::binding("Self::Low as u8 + 1"));
+        let module = helpers(r#enum, &EnumHelperOptions::new());
+        let try_from = module.get_impl("Level", Some("TryFrom<u8>")).unwrap();
+        assert_eq!(
+            try_from.function("try_from").unwrap().to_code_string(),
+            concat!(
+                "fn try_from(value: u8) -> Result<Self, Self::Error> {\n",
+                "    match value {\n",
+                "        1u8 => Ok(Self::Low),\n",
+                "        _ if value == (Self::High as u8) => Ok(Self::High),\n",
+                "        _ => Err(value),\n",
+                "    }\n}\n",
+            )
+        );
+        assert!(module.get_impl("u8", Some("From<Level>")).is_some());
+        assert_compiles("enum-helpers-repr", &module.to_string());
+    }
+
+    #[test]
+    fn generics() {
+        let mut r#enum = Enum::new("Axis".into());
+        r#enum.add_generic("const N: usize");
+        r#enum.add_variant("X").set_discriminant(expr::integer(0));
+        r#enum.add_variant("Y");
+        let module = helpers(r#enum, &EnumHelperOptions::new());
+        let code = module.to_string();
+        for header in [
+            "impl<const N: usize> Axis<N> {\n",
+            "impl<const N: usize> std::fmt::Display for Axis<N> {\n",
+            "impl<const N: usize> std::str::FromStr for Axis<N> {\n",
+            "impl<const N: usize> TryFrom<isize> for Axis<N> {\n",
+            "impl<const N: usize> From<Axis<N>> for isize {\n",
+        ] {
+            assert!(code.contains(header), "{header}");
+        }
+        assert_compiles("enum-helpers-generics", &code);
+    }
+}
@@ -0,0 +1,621 @@
+//! Error enums with `Display`, `Error` and `From` impls
+//!
+//! An [`ErrorEnum`] describes the variants of an error type, each with a message template like
+//! `"invalid id {id}"`. [`error_enum`] generates the enum together with the impls that would
+//! otherwise be derived by an error crate, so the generated code has no dependencies.
+//!
+//! Placeholders in a template name a field of the variant, or its index for tuple variants, and
+//! may have a format spec such as `{id:?}`. A field marked as [source](ErrorField::set_source) is
+//! returned by `Error::source`, a field marked as [from](ErrorField::set_from) is also the source
+//! and gets a `From` impl.
+//!
+//! ```
+//! use codegen_rs::derive::error::{error_enum, ErrorEnum};
+//!
+//! let mut error = ErrorEnum::new("ConfigError");
+//! error.set_public(true);
+//! error
+//!     .add_variant("InvalidId", "invalid id {id}")
+//!     .add_field("id", "u32");
+//! error
+//!     .add_variant("Io", "failed to read the config")
+//!     .add_tuple_field("std::io::Error")
+//!     .set_from(true);
+//!
+//! let code = error_enum(&error).unwrap().to_string();
+//! assert!(code.contains(r#"Self::InvalidId { id } => write!(f, "invalid id {id}"),"#));
+//! assert!(code.contains("Self::Io(_0) => Some(_0),"));
+//! assert!(code.contains("impl From<std::io::Error> for ConfigError {"));
+//!
+//! error.add_variant("Missing", "missing {name}");
+//! let error = error_enum(&error).unwrap_err();
+//! assert_eq!(error.to_string(), "`{name}` is not a field of `Missing`");
+//! ```
+
+use std::fmt::Display;
+
+use crate::{
+    expr::{self, Expr},
+    nodes::{r#enum::Enum, r#impl::Impl, Module, ModuleNode},
+    Str,
+};
+
+/// The description of an error enum
+#[derive(Clone, Debug)]
+pub struct ErrorEnum {
+    name: Str,
+    public: bool,
+    doc: Option<Str>,
+    variants: Vec<ErrorVariant>,
+}
+
+impl ErrorEnum {
+    pub fn new(name: impl Into<Str>) -> Self {
+        Self {
+            name: name.into(),
+            public: false,
+            doc: None,
+            variants: Vec::new(),
+        }
+    }
+
+    pub fn set_public(&mut self, public: bool) -> &mut Self {
+        self.public = public;
+        self
+    }
+
+    pub fn set_doc(&mut self, doc: impl Into<Str>) -> &mut Self {
+        self.doc = Some(doc.into());
+        self
+    }
+
+    /// Adds a variant displayed with the template `message`
+    pub fn add_variant(
+        &mut self,
+        name: impl Into<Str>,
+        message: impl Into<Str>,
+    ) -> &mut ErrorVariant {
+        self.variants
+            .push(ErrorVariant::new(name.into(), message.into()));
+        self.variants.last_mut().unwrap()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.public
+    }
+
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
+    pub fn variants(&self) -> &[ErrorVariant] {
+        &self.variants
+    }
+
+    pub fn variant_mut(&mut self, name: &str) -> Option<&mut ErrorVariant> {
+        self.variants
+            .iter_mut()
+            .find(|variant| variant.name == name)
+    }
+}
+
+/// A variant of an [`ErrorEnum`], either without fields, with named fields or with tuple fields
+#[derive(Clone, Debug)]
+pub struct ErrorVariant {
+    name: Str,
+    message: Str,
+    doc: Option<Str>,
+    fields: Vec<ErrorField>,
+}
+
+impl ErrorVariant {
+    pub fn new(name: Str, message: Str) -> Self {
+        Self {
+            name,
+            message,
+            doc: None,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Adds a named field, a variant cannot have both named and tuple fields
+    pub fn add_field(&mut self, name: impl Into<Str>, r#type: impl Into<Str>) -> &mut ErrorField {
+        self.fields
+            .push(ErrorField::new(Some(name.into()), r#type.into()));
+        self.fields.last_mut().unwrap()
+    }
+
+    /// Adds a tuple field, a variant cannot have both named and tuple fields
+    pub fn add_tuple_field(&mut self, r#type: impl Into<Str>) -> &mut ErrorField {
+        self.fields.push(ErrorField::new(None, r#type.into()));
+        self.fields.last_mut().unwrap()
+    }
+
+    pub fn set_doc(&mut self, doc: impl Into<Str>) -> &mut Self {
+        self.doc = Some(doc.into());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
+    pub fn fields(&self) -> &[ErrorField] {
+        &self.fields
+    }
+
+    fn is_tuple(&self) -> bool {
+        self.fields.iter().any(|field| field.name.is_none())
+    }
+
+    /// Returns the binding of each field in patterns, `_0`, `_1`, ... for tuple fields
+    fn bindings(&self) -> Vec<String> {
+        self.fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| match &field.name {
+                Some(name) => name.to_string(),
+                None => format!("_{i}"),
+            })
+            .collect()
+    }
+
+    /// Returns a pattern binding the fields in `used`
+    fn pattern(&self, used: &[String]) -> String {
+        let path = format!("Self::{}", self.name);
+        let bindings = self.bindings();
+        if bindings.is_empty() {
+            return path;
+        }
+        if self.is_tuple() {
+            if used.is_empty() {
+                return format!("{path}(..)");
+            }
+            let fields: Vec<&str> = bindings
+                .iter()
+                .map(|binding| match used.contains(binding) {
+                    true => binding.as_str(),
+                    false => "_",
+                })
+                .collect();
+            return format!("{path}({})", fields.join(", "));
+        }
+        let mut fields: Vec<&str> = bindings
+            .iter()
+            .filter(|binding| used.contains(binding))
+            .map(String::as_str)
+            .collect();
+        if fields.len() < bindings.len() {
+            fields.push("..");
+        }
+        format!("{path} {{ {} }}", fields.join(", "))
+    }
+}
+
+/// A field of an [`ErrorVariant`]
+#[derive(Clone, Debug)]
+pub struct ErrorField {
+    name: Option<Str>,
+    r#type: Str,
+    source: bool,
+    from: bool,
+}
+
+impl ErrorField {
+    fn new(name: Option<Str>, r#type: Str) -> Self {
+        Self {
+            name,
+            r#type,
+            source: false,
+            from: false,
+        }
+    }
+
+    /// Returns the field from `Error::source`, its type must implement `Error + 'static`
+    pub fn set_source(&mut self, source: bool) -> &mut Self {
+        self.source = source;
+        self
+    }
+
+    /// Generates `From` for the field type and makes the field the source
+    ///
+    /// The field must be the only field of its variant.
+    pub fn set_from(&mut self, from: bool) -> &mut Self {
+        self.from = from;
+        self.source |= from;
+        self
+    }
+
+    /// Returns the name of a named field
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn r#type(&self) -> &str {
+        &self.r#type
+    }
+
+    pub fn is_source(&self) -> bool {
+        self.source
+    }
+
+    pub fn is_from(&self) -> bool {
+        self.from
+    }
+}
+
+/// Invalid variants passed to [`error_enum`]
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ErrorEnumError {
+    /// A variant with both named and tuple fields
+    MixedFields(String),
+    /// A `{` or `}` in a template that is neither escaped nor part of a placeholder
+    UnbalancedBraces { variant: String, message: String },
+    /// A placeholder that does not name a field of its variant
+    UnknownField {
+        variant: String,
+        placeholder: String,
+    },
+    /// A variant with more than one source
+    MultipleSources(String),
+    /// A variant with a `from` field and other fields
+    FromWithOtherFields(String),
+    /// A type that is the `from` field of two variants
+    DuplicateFrom(String),
+}
+
+impl Display for ErrorEnumError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorEnumError::MixedFields(variant) => {
+                write!(f, "`{variant}` has both named and tuple fields")
+            }
+            ErrorEnumError::UnbalancedBraces { variant, message } => {
+                write!(
+                    f,
+                    "unbalanced braces in the message of `{variant}`: `{message}`"
+                )
+            }
+            ErrorEnumError::UnknownField {
+                variant,
+                placeholder,
+            } => write!(f, "`{{{placeholder}}}` is not a field of `{variant}`"),
+            ErrorEnumError::MultipleSources(variant) => {
+                write!(f, "`{variant}` has more than one source")
+            }
+            ErrorEnumError::FromWithOtherFields(variant) => {
+                write!(f, "`{variant}` has a `from` field and other fields")
+            }
+            ErrorEnumError::DuplicateFrom(r#type) => {
+                write!(f, "`From<{type}>` would be implemented twice")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ErrorEnumError {}
+
+/// Generates the enum described by `error` with its `Display`, `Error` and `From` impls
+///
+/// The enum derives `Debug`, which `Error` requires.
+///
+/// Returns an error if a variant has both named and tuple fields, a template has unbalanced
+/// braces or a placeholder that is not a field of its variant, a variant has more than one source
+/// or a `from` field is not the only field of its variant or has the type of another one.
+pub fn error_enum(error: &ErrorEnum) -> Result<Module, ErrorEnumError> {
+    if let Some(variant) = error.variants.iter().find(|variant| {
+        variant.is_tuple() && variant.fields.iter().any(|field| field.name.is_some())
+    }) {
+        return Err(ErrorEnumError::MixedFields(variant.name.to_string()));
+    }
+    let mut module = Module::new();
+    let r#enum = module.add_enum(error.name.clone());
+    r#enum
+        .set_public(error.public)
+        .add_attribute("derive(Debug)");
+    if let Some(doc) = &error.doc {
+        r#enum.set_doc(doc.clone());
+    }
+    add_variants(r#enum, error);
+    module.add_node(ModuleNode::Impl(display(error)?));
+    module.add_node(ModuleNode::Impl(impl_error(error)?));
+    let mut from_types: Vec<&str> = Vec::new();
+    for variant in &error.variants {
+        if let Some(field) = variant.fields.iter().find(|field| field.from) {
+            if from_types.contains(&&*field.r#type) {
+                return Err(ErrorEnumError::DuplicateFrom(field.r#type.to_string()));
+            }
+            from_types.push(&field.r#type);
+            module.add_node(ModuleNode::Impl(from(error, variant, field)?));
+        }
+    }
+    Ok(module)
+}
+
+fn add_variants(r#enum: &mut Enum, error: &ErrorEnum) {
+    for variant in &error.variants {
+        let node = r#enum.add_variant(variant.name.clone());
+        if let Some(doc) = &variant.doc {
+            node.set_doc(doc.clone());
+        }
+        for field in &variant.fields {
+            match &field.name {
+                Some(name) => _ = node.add_field(name.clone(), field.r#type.clone()),
+                None => _ = node.add_tuple_field(field.r#type.clone()),
+            }
+        }
+    }
+}
+
+/// Implements `Display` with a `write!` of each template
+fn display(error: &ErrorEnum) -> Result<Impl, ErrorEnumError> {
+    let mut r#impl = Impl::new(error.name.clone());
+    r#impl.set_trait("std::fmt::Display");
+    let function = r#impl.add_function("fmt");
+    function.add_self_param(true, false);
+    // The formatter must not be shadowed by a field binding
+    let body = function.add_body();
+    for variant in &error.variants {
+        for binding in variant.bindings() {
+            body.reserve(binding);
+        }
+    }
+    let f = body.fresh("f");
+    let mut arms = expr::r#match(expr::binding("self"));
+    for variant in &error.variants {
+        let (template, used) = template(variant)?;
+        arms = arms.arm(
+            variant.pattern(&used),
+            expr::macro_call("write")
+                .arg(expr::binding(f.clone()))
+                .arg(expr::str(template)),
+        );
+    }
+    function.body_mut().unwrap().add_tail(arms);
+    function
+        .add_param(f, "&mut std::fmt::Formatter<'_>")
+        .set_return_type("std::fmt::Result");
+    Ok(r#impl)
+}
+
+/// Rewrites the placeholders of a template to the field bindings and returns the used bindings
+fn template(variant: &ErrorVariant) -> Result<(String, Vec<String>), ErrorEnumError> {
+    let bindings = variant.bindings();
+    let mut template = String::new();
+    let mut used = Vec::new();
+    let mut rest = &*variant.message;
+    while let Some(index) = rest.find(['{', '}']) {
+        template.push_str(&rest[..index]);
+        rest = &rest[index..];
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            template.push_str(&rest[..2]);
+            rest = &rest[2..];
+            continue;
+        }
+        let end = match rest.starts_with('{') {
+            true => rest.find('}'),
+            false => None,
+        };
+        let Some(end) = end else {
+            return Err(ErrorEnumError::UnbalancedBraces {
+                variant: variant.name.to_string(),
+                message: variant.message.to_string(),
+            });
+        };
+        let (name, spec) = rest[1..end].split_at(rest[1..end].find(':').unwrap_or(end - 1));
+        let binding = match variant.is_tuple() {
+            true => format!("_{name}"),
+            false => name.to_owned(),
+        };
+        if !bindings.contains(&binding) {
+            return Err(ErrorEnumError::UnknownField {
+                variant: variant.name.to_string(),
+                placeholder: name.to_owned(),
+            });
+        }
+        template.push('{');
+        template.push_str(&binding);
+        template.push_str(spec);
+        template.push('}');
+        if !used.contains(&binding) {
+            used.push(binding);
+        }
+        rest = &rest[end + 1..];
+    }
+    template.push_str(rest);
+    Ok((template, used))
+}
+
+/// Implements `Error`, `source` is only generated if a variant has a source
+fn impl_error(error: &ErrorEnum) -> Result<Impl, ErrorEnumError> {
+    let mut r#impl = Impl::new(error.name.clone());
+    r#impl.set_trait("std::error::Error");
+    let mut arms = expr::r#match(expr::binding("self"));
+    let mut all = true;
+    for variant in &error.variants {
+        let mut sources = variant
+            .fields
+            .iter()
+            .zip(variant.bindings())
+            .filter(|(field, _)| field.source);
+        let Some((field, binding)) = sources.next() else {
+            all = false;
+            continue;
+        };
+        if sources.next().is_some() {
+            return Err(ErrorEnumError::MultipleSources(variant.name.to_string()));
+        }
+        // Boxed trait objects do not implement `Error` themselves
+        let source = match field.r#type.starts_with("Box<dyn ") {
+            true => expr::binding(binding.clone())
+                .dereference()
+                .dereference()
+                .reference(),
+            false => expr::binding(binding.clone()),
+        };
+        arms = arms.arm(variant.pattern(&[binding]), expr::call("Some").arg(source));
+    }
+    if arms.arms().is_empty() {
+        return Ok(r#impl);
+    }
+    if !all {
+        arms = arms.arm("_", expr::binding("None"));
+    }
+    r#impl
+        .add_function("source")
+        .add_self_param(true, false)
+        .set_return_type("Option<&(dyn std::error::Error + 'static)>")
+        .add_body()
+        .add_tail(arms);
+    Ok(r#impl)
+}
+
+/// Implements `From` for the type of a `from` field
+fn from(
+    error: &ErrorEnum,
+    variant: &ErrorVariant,
+    field: &ErrorField,
+) -> Result<Impl, ErrorEnumError> {
+    if variant.fields.len() != 1 {
+        return Err(ErrorEnumError::FromWithOtherFields(
+            variant.name.to_string(),
+        ));
+    }
+    let path = format!("Self::{}", variant.name);
+    let value: Expr = match &field.name {
+        Some(name) if name == "source" => expr::init_struct(path).field_auto("source").into(),
+        Some(name) => expr::init_struct(path)
+            .field(name.clone(), expr::binding("source"))
+            .into(),
+        None => expr::call(path).arg(expr::binding("source")).into(),
+    };
+    let mut r#impl = Impl::new(error.name.clone());
+    r#impl
+        .set_trait(format!("From<{}>", field.r#type))
+        .add_function("from")
+        .add_param("source", field.r#type.clone())
+        .set_return_type("Self")
+        .add_body()
+        .add_tail(value);
+    Ok(r#impl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::derive::tests::assert_compiles;
+
+    fn error(add: impl FnOnce(&mut ErrorEnum)) -> ErrorEnumError {
+        let mut error = ErrorEnum::new("Error");
+        add(&mut error);
+        error_enum(&error).unwrap_err()
+    }
+
+    #[test]
+    fn generated() {
+        let mut error = ErrorEnum::new("Error");
+        error
+            .add_variant("Parse", "line {0}: {1:?}")
+            .add_tuple_field("usize");
+        error
+            .variant_mut("Parse")
+            .unwrap()
+            .add_tuple_field("String");
+        error.add_variant("Eof", "unexpected end of input, {{}}");
+        let io = error.add_variant("Io", "failed to read {path}");
+        io.add_field("path", "String");
+        io.add_field("f", "std::io::Error").set_source(true);
+        error
+            .add_variant("Utf8", "invalid utf-8")
+            .add_tuple_field("std::str::Utf8Error")
+            .set_from(true);
+
+        let code = error_enum(&error).unwrap().to_string();
+        assert!(code.contains("fn fmt(&self, f_1: &mut std::fmt::Formatter<'_>)"));
+        assert!(code.contains(r#"Self::Parse(_0, _1) => write!(f_1, "line {_0}: {_1:?}"),"#));
+        assert!(code.contains(r#"Self::Eof => write!(f_1, "unexpected end of input, {{}}"),"#));
+        assert!(code.contains(r#"Self::Io { path, .. } => write!(f_1, "failed to read {path}"),"#));
+        assert!(code.contains("Self::Io { f, .. } => Some(f),"));
+        assert!(code.contains("Self::Utf8(_0) => Some(_0),"));
+        assert!(code.contains("impl From<std::str::Utf8Error> for Error {"));
+        assert_compiles("error_enum", &code);
+    }
+
+    #[test]
+    fn errors() {
+        let mixed = error(|error| {
+            let variant = error.add_variant("Mixed", "mixed");
+            variant.add_field("a", "u8");
+            variant.add_tuple_field("u8");
+        });
+        assert_eq!(mixed, ErrorEnumError::MixedFields("Mixed".to_owned()));
+
+        for message in ["open {", "close }", "{0"] {
+            let unbalanced = error(|error| {
+                error.add_variant("Braces", message).add_tuple_field("u8");
+            });
+            assert_eq!(
+                unbalanced,
+                ErrorEnumError::UnbalancedBraces {
+                    variant: "Braces".to_owned(),
+                    message: message.to_owned()
+                }
+            );
+        }
+
+        let unknown = error(|error| {
+            error.add_variant("Tuple", "{0} {1}").add_tuple_field("u8");
+        });
+        assert_eq!(
+            unknown,
+            ErrorEnumError::UnknownField {
+                variant: "Tuple".to_owned(),
+                placeholder: "1".to_owned()
+            }
+        );
+        assert_eq!(unknown.to_string(), "`{1}` is not a field of `Tuple`");
+
+        let sources = error(|error| {
+            let variant = error.add_variant("Sources", "two sources");
+            variant.add_tuple_field("std::io::Error").set_source(true);
+            variant.add_tuple_field("std::fmt::Error").set_from(true);
+        });
+        assert_eq!(
+            sources,
+            ErrorEnumError::MultipleSources("Sources".to_owned())
+        );
+
+        let from = error(|error| {
+            let variant = error.add_variant("From", "from {path}");
+            variant.add_field("path", "String");
+            variant.add_field("io", "std::io::Error").set_from(true);
+        });
+        assert_eq!(from, ErrorEnumError::FromWithOtherFields("From".to_owned()));
+
+        let duplicate = error(|error| {
+            for name in ["Read", "Write"] {
+                error
+                    .add_variant(name, "io")
+                    .add_tuple_field("std::io::Error")
+                    .set_from(true);
+            }
+        });
+        assert_eq!(
+            duplicate,
+            ErrorEnumError::DuplicateFrom("std::io::Error".to_owned())
+        );
+    }
+}
@@ -0,0 +1,468 @@
+//! Tuple structs wrapping a single value
+//!
+//! [`newtype`] generates `struct UserId(u64);` and impls that delegate to the inner value:
+//! conversions from and to the inner type, `Deref`, `Display`, `FromStr` and the operator traits
+//! listed in [`NewtypeOptions`]. Binary operators take `Self` as the right-hand side.
+//!
+//! ```
+//! use codegen_rs::derive::newtype::{newtype, NewtypeOptions, OperatorTrait};
+//!
+//! let mut options = NewtypeOptions::new();
+//! options
+//!     .set_public(true)
+//!     .add_derive("Clone")
+//!     .add_derive("Copy")
+//!     .set_display(true)
+//!     .add_operator(OperatorTrait::Add);
+//! let code = newtype("Meters", "u64", &options).to_string();
+//! assert!(code.starts_with("#[derive(Clone, Copy)]\npub struct Meters(u64);\n"));
+//! assert!(code.contains("Self(self.0 + rhs.0)"));
+//! assert!(code.contains("self.0 += rhs.0;"));
+//! ```
+
+use crate::{
+    derive::self_field,
+    expr::{self, Expr, Operator},
+    nodes::{r#impl::Impl, Module, ModuleNode},
+    Str,
+};
+
+/// Operator traits of `std::ops` that delegate to the inner value
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OperatorTrait {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    Neg,
+    Not,
+}
+
+impl OperatorTrait {
+    /// Returns the trait name, e.g. `Add`
+    pub fn name(self) -> &'static str {
+        match self {
+            OperatorTrait::Add => "Add",
+            OperatorTrait::Sub => "Sub",
+            OperatorTrait::Mul => "Mul",
+            OperatorTrait::Div => "Div",
+            OperatorTrait::Rem => "Rem",
+            OperatorTrait::BitAnd => "BitAnd",
+            OperatorTrait::BitOr => "BitOr",
+            OperatorTrait::BitXor => "BitXor",
+            OperatorTrait::Shl => "Shl",
+            OperatorTrait::Shr => "Shr",
+            OperatorTrait::Neg => "Neg",
+            OperatorTrait::Not => "Not",
+        }
+    }
+
+    /// Returns the method name, e.g. `add`
+    pub fn method(self) -> &'static str {
+        match self {
+            OperatorTrait::Add => "add",
+            OperatorTrait::Sub => "sub",
+            OperatorTrait::Mul => "mul",
+            OperatorTrait::Div => "div",
+            OperatorTrait::Rem => "rem",
+            OperatorTrait::BitAnd => "bitand",
+            OperatorTrait::BitOr => "bitor",
+            OperatorTrait::BitXor => "bitxor",
+            OperatorTrait::Shl => "shl",
+            OperatorTrait::Shr => "shr",
+            OperatorTrait::Neg => "neg",
+            OperatorTrait::Not => "not",
+        }
+    }
+
+    /// `Neg` and `Not` have no right-hand side and no assigning variant
+    pub fn is_unary(self) -> bool {
+        matches!(self, OperatorTrait::Neg | OperatorTrait::Not)
+    }
+
+    /// Returns the operator of the expression, e.g. `Operator::Plus` for `Add`
+    fn operator(self) -> Operator {
+        match self {
+            OperatorTrait::Add => Operator::Plus,
+            OperatorTrait::Sub | OperatorTrait::Neg => Operator::Minus,
+            OperatorTrait::Mul => Operator::Star,
+            OperatorTrait::Div => Operator::Slash,
+            OperatorTrait::Rem => Operator::Percent,
+            OperatorTrait::BitAnd => Operator::And,
+            OperatorTrait::BitOr => Operator::Or,
+            OperatorTrait::BitXor => Operator::Xor,
+            OperatorTrait::Shl => Operator::ShiftLeft,
+            OperatorTrait::Shr => Operator::ShiftRight,
+            OperatorTrait::Not => Operator::Not,
+        }
+    }
+
+    /// Applies the operator to expressions, `right` is ignored for unary operators
+    fn apply(self, left: Expr, right: Expr) -> Expr {
+        match self {
+            OperatorTrait::Add => left.add(right),
+            OperatorTrait::Sub => left.sub(right),
+            OperatorTrait::Mul => left.mul(right),
+            OperatorTrait::Div => left.div(right),
+            OperatorTrait::Rem => left.rem(right),
+            OperatorTrait::BitAnd => left.bit_and(right),
+            OperatorTrait::BitOr => left.bit_or(right),
+            OperatorTrait::BitXor => left.bit_xor(right),
+            OperatorTrait::Shl => left.bit_shl(right),
+            OperatorTrait::Shr => left.bit_shr(right),
+            OperatorTrait::Neg => left.negate(),
+            OperatorTrait::Not => left.not(),
+        }
+    }
+}
+
+/// Options for [`newtype`]
+#[derive(Clone, Debug)]
+pub struct NewtypeOptions {
+    public: bool,
+    public_field: bool,
+    derives: Vec<Str>,
+    from: bool,
+    into: bool,
+    deref: bool,
+    display: bool,
+    from_str: bool,
+    operators: Vec<OperatorTrait>,
+    assign: bool,
+}
+
+impl NewtypeOptions {
+    pub fn new() -> Self {
+        Self {
+            public: false,
+            public_field: false,
+            derives: Vec::new(),
+            from: true,
+            into: true,
+            deref: false,
+            display: false,
+            from_str: false,
+            operators: Vec::new(),
+            assign: true,
+        }
+    }
+
+    pub fn set_public(&mut self, public: bool) -> &mut Self {
+        self.public = public;
+        self
+    }
+
+    /// Makes the inner value public, e.g. `struct UserId(pub u64);`
+    pub fn set_public_field(&mut self, public_field: bool) -> &mut Self {
+        self.public_field = public_field;
+        self
+    }
+
+    /// Adds a trait to the `#[derive]` attribute of the struct
+    pub fn add_derive(&mut self, r#trait: impl Into<Str>) -> &mut Self {
+        self.derives.push(r#trait.into());
+        self
+    }
+
+    /// Implements `From<inner>` for the newtype, enabled by default
+    pub fn set_from(&mut self, from: bool) -> &mut Self {
+        self.from = from;
+        self
+    }
+
+    /// Implements `From<newtype>` for the inner type, enabled by default
+    pub fn set_into(&mut self, into: bool) -> &mut Self {
+        self.into = into;
+        self
+    }
+
+    /// Implements `Deref` with the inner type as target
+    pub fn set_deref(&mut self, deref: bool) -> &mut Self {
+        self.deref = deref;
+        self
+    }
+
+    /// Implements `Display` by formatting the inner value
+    pub fn set_display(&mut self, display: bool) -> &mut Self {
+        self.display = display;
+        self
+    }
+
+    /// Implements `FromStr` by parsing the inner type
+    pub fn set_from_str(&mut self, from_str: bool) -> &mut Self {
+        self.from_str = from_str;
+        self
+    }
+
+    /// Implements an operator trait
+    pub fn add_operator(&mut self, operator: OperatorTrait) -> &mut Self {
+        if !self.operators.contains(&operator) {
+            self.operators.push(operator);
+        }
+        self
+    }
+
+    /// Also implements `AddAssign` etc. for binary operators, enabled by default
+    pub fn set_assign(&mut self, assign: bool) -> &mut Self {
+        self.assign = assign;
+        self
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.public
+    }
+
+    pub fn is_public_field(&self) -> bool {
+        self.public_field
+    }
+
+    pub fn derives(&self) -> &[Str] {
+        &self.derives
+    }
+
+    pub fn from(&self) -> bool {
+        self.from
+    }
+
+    pub fn into(&self) -> bool {
+        self.into
+    }
+
+    pub fn deref(&self) -> bool {
+        self.deref
+    }
+
+    pub fn display(&self) -> bool {
+        self.display
+    }
+
+    pub fn from_str(&self) -> bool {
+        self.from_str
+    }
+
+    pub fn operators(&self) -> &[OperatorTrait] {
+        &self.operators
+    }
+
+    pub fn assign(&self) -> bool {
+        self.assign
+    }
+}
+
+impl Default for NewtypeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Generates the tuple struct `name` wrapping `inner` and the impls enabled in `options`
+pub fn newtype(name: impl Into<Str>, inner: impl Into<Str>, options: &NewtypeOptions) -> Module {
+    let name: Str = name.into();
+    let inner: Str = inner.into();
+    let mut module = Module::new();
+    let r#struct = module.add_struct(name.clone());
+    r#struct.set_public(options.public);
+    if !options.derives.is_empty() {
+        r#struct.add_attribute(format!("derive({})", options.derives.join(", ")));
+    }
+    r#struct
+        .add_tuple_field(inner.clone())
+        .set_public(options.public_field);
+
+    let value = || self_field("0");
+    if options.from {
+        let mut from = Impl::new(name.clone());
+        from.set_trait(format!("From<{inner}>"))
+            .add_function("from")
+            .add_param("value", inner.clone())
+            .set_return_type("Self")
+            .add_body()
+            .add_tail(expr::call("Self").arg(expr::binding("value")));
+        module.add_node(ModuleNode::Impl(from));
+    }
+    if options.into {
+        let mut into = Impl::new(inner.clone());
+        into.set_trait(format!("From<{name}>"))
+            .add_function("from")
+            .add_param("value", name.clone())
+            .set_return_type("Self")
+            .add_body()
+            .add_tail(expr::binding("value").field("0"));
+        module.add_node(ModuleNode::Impl(into));
+    }
+    if options.deref {
+        let mut deref = Impl::new(name.clone());
+        deref
+            .set_trait("std::ops::Deref")
+            .add_type_alias("Target", inner.clone());
+        deref
+            .add_function("deref")
+            .add_self_param(true, false)
+            .set_return_type("&Self::Target")
+            .add_body()
+            .add_tail(value().reference());
+        module.add_node(ModuleNode::Impl(deref));
+    }
+    if options.display {
+        let mut display = Impl::new(name.clone());
+        display
+            .set_trait("std::fmt::Display")
+            .add_function("fmt")
+            .add_self_param(true, false)
+            .add_param("f", "&mut std::fmt::Formatter<'_>")
+            .set_return_type("std::fmt::Result")
+            .add_body()
+            .add_tail(
+                expr::call("std::fmt::Display::fmt")
+                    .arg(value().reference())
+                    .arg(expr::binding("f")),
+            );
+        module.add_node(ModuleNode::Impl(display));
+    }
+    if options.from_str {
+        let mut from_str = Impl::new(name.clone());
+        from_str
+            .set_trait("std::str::FromStr")
+            .add_type_alias("Err", format!("<{inner} as std::str::FromStr>::Err"));
+        from_str
+            .add_function("from_str")
+            .add_param("s", "&str")
+            .set_return_type("Result<Self, Self::Err>")
+            .add_body()
+            .add_tail(
+                Expr::from(expr::binding("s").method("parse"))
+                    .method("map")
+                    .arg(expr::binding("Self")),
+            );
+        module.add_node(ModuleNode::Impl(from_str));
+    }
+    for &operator in &options.operators {
+        for r#impl in operator_impls(&name, operator, options.assign) {
+            module.add_node(ModuleNode::Impl(r#impl));
+        }
+    }
+    module
+}
+
+/// Implements an operator and, if `assign` is set and the operator is binary, its assigning
+/// variant
+fn operator_impls(name: &Str, operator: OperatorTrait, assign: bool) -> Vec<Impl> {
+    let value = || self_field("0");
+    let rhs = || expr::binding("rhs").field("0");
+    let mut r#impl = Impl::new(name.clone());
+    r#impl
+        .set_trait(format!("std::ops::{}", operator.name()))
+        .add_type_alias("Output", "Self");
+    let function = r#impl.add_function(operator.method());
+    function.add_self_param(false, false);
+    if !operator.is_unary() {
+        function.add_param("rhs", "Self");
+    }
+    function
+        .set_return_type("Self::Output")
+        .add_body()
+        .add_tail(expr::call("Self").arg(operator.apply(value(), rhs())));
+    if operator.is_unary() || !assign {
+        return vec![r#impl];
+    }
+
+    let mut assign = Impl::new(name.clone());
+    assign
+        .set_trait(format!("std::ops::{}Assign", operator.name()))
+        .add_function(format!("{}_assign", operator.method()))
+        .add_self_param(true, true)
+        .add_param("rhs", "Self")
+        .add_body()
+        .add_assign(value(), rhs())
+        .set_operator(operator.operator());
+    vec![r#impl, assign]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::derive::tests::assert_compiles;
+
+    #[test]
+    fn defaults() {
+        let code = newtype("UserId", "u64", &NewtypeOptions::new()).to_string();
+        assert_eq!(
+            code,
+            "struct UserId(u64);\n\
+             \n\
+             impl From<u64> for UserId {\n    \
+                 fn from(value: u64) -> Self {\n        \
+                     Self(value)\n    \
+                 }\n\
+             }\n\
+             \n\
+             impl From<UserId> for u64 {\n    \
+                 fn from(value: UserId) -> Self {\n        \
+                     value.0\n    \
+                 }\n\
+             }\n"
+        );
+        assert_compiles("newtype_defaults", &code);
+    }
+
+    #[test]
+    fn delegation() {
+        let mut options = NewtypeOptions::new();
+        options
+            .set_public(true)
+            .set_public_field(true)
+            .add_derive("Clone")
+            .add_derive("Copy")
+            .set_deref(true)
+            .set_display(true)
+            .set_from_str(true)
+            .add_operator(OperatorTrait::Shl)
+            .add_operator(OperatorTrait::Neg)
+            .add_operator(OperatorTrait::Shl);
+        let code = newtype("Meters", "i64", &options).to_string();
+        assert!(code.starts_with("#[derive(Clone, Copy)]\npub struct Meters(pub i64);\n"));
+        assert!(code.contains("type Target = i64;"));
+        assert!(code.contains("fn deref(&self) -> &Self::Target {\n        &self.0\n"));
+        assert!(code.contains("std::fmt::Display::fmt(&self.0, f)"));
+        assert!(code.contains("type Err = <i64 as std::str::FromStr>::Err;"));
+        assert!(code.contains("s.parse().map(Self)"));
+        assert_eq!(code.matches("impl std::ops::Shl for Meters {").count(), 1);
+        assert!(code.contains("Self(self.0 << rhs.0)"));
+        assert!(code.contains("fn shl_assign(&mut self, rhs: Self) {\n        self.0 <<= rhs.0;\n"));
+        assert!(code.contains("fn neg(self) -> Self::Output {\n        Self(-self.0)\n"));
+        assert!(!code.contains("NegAssign"));
+        assert_compiles("newtype_delegation", &code);
+    }
+
+    #[test]
+    fn raw_names_and_skipped_impls() {
+        let mut options = NewtypeOptions::new();
+        options
+            .set_from(false)
+            .set_into(false)
+            .set_assign(false)
+            .add_operator(OperatorTrait::BitXor);
+        let code = newtype("r#match", "r#type", &options).to_string();
+        assert!(code.starts_with("struct r#match(r#type);\n"));
+        assert!(!code.contains("From<"));
+        assert!(code.contains("impl std::ops::BitXor for r#match {"));
+        assert!(code.contains("Self(self.0 ^ rhs.0)"));
+        assert!(!code.contains("BitXorAssign"));
+        assert_compiles(
+            "newtype_raw",
+            &format!("#[allow(non_camel_case_types)]\ntype r#type = u8;\n{code}"),
+        );
+
+        options.set_into(true);
+        let code = newtype("r#match", "r#type", &options).to_string();
+        assert!(code.contains("impl From<r#match> for r#type {"));
+        assert!(code.contains("fn from(value: r#match) -> Self {\n        value.0\n"));
+    }
+}
@@ -0,0 +1,550 @@
+//! Structural differences between two modules
+//!
+//! Unlike a text diff, [`diff`] matches items by kind and name, so it reports which items,
+//! fields, variants and function signatures were added, removed or changed regardless of where
+//! they are in the file. Raw code is matched by the kind and name of the item it contains and
+//! compared as a whole.
+//!
+//! ```
+//! use codegen_rs::{diff::diff, nodes::Module};
+//!
+//! let mut old = Module::new();
+//! old.add_struct("Vec2").add_field("x", "f32");
+//! let mut new = Module::new();
+//! new.add_struct("Vec2").add_field("x", "f64");
+//! new.add_function("length").set_public(true);
+//!
+//! assert_eq!(
+//!     diff(&old, &new).to_string(),
+//!     "~ field Vec2::x: `x: f32` -> `x: f64`\n+ fn length\n",
+//! );
+//! ```
+
+use std::fmt::{Display, Write};
+
+use crate::{
+    generator::{Formatter, GenerateCode},
+    nodes::{
+        extern_block::ExternNode,
+        function::Function,
+        r#enum::Enum,
+        r#impl::{Impl, ImplNode},
+        r#struct::{Field, Struct},
+        r#trait::{Trait, TraitNode},
+        raw::Raw,
+        Module, ModuleNode, Submodule,
+    },
+    parse, Str,
+};
+
+/// Compares two modules item by item
+pub fn diff(old: &Module, new: &Module) -> Diff {
+    let mut changes = Vec::new();
+    compare("", &module_entries(old), &module_entries(new), &mut changes);
+    Diff { changes }
+}
+
+/// The changes between two modules, see [`diff`]
+///
+/// Displays one change per line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diff {
+    changes: Vec<Change>,
+}
+
+impl Diff {
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl Display for Diff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    item: ItemKind,
+    path: String,
+    kind: ChangeKind,
+}
+
+impl Change {
+    pub fn item(&self) -> ItemKind {
+        self.item
+    }
+
+    /// The path of the item, e.g. `shapes::Vec2::x`
+    ///
+    /// Methods of inherent impls are written as `Type::method`, trait impls as
+    /// `<Type as Trait>`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn kind(&self) -> &ChangeKind {
+        &self.kind
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { item, path, kind } = self;
+        match kind {
+            ChangeKind::Added => write!(f, "+ {item} {path}"),
+            ChangeKind::Removed => write!(f, "- {item} {path}"),
+            ChangeKind::Changed { old, new } => write!(f, "~ {item} {path}: `{old}` -> `{new}`"),
+            ChangeKind::BodyChanged => write!(f, "~ {item} {path}: body changed"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    /// The signature of the item changed, e.g. its visibility or type
+    Changed {
+        old: String,
+        new: String,
+    },
+    /// Only the body of a function changed
+    BodyChanged,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ItemKind {
+    Use,
+    Module,
+    Const,
+    Static,
+    Struct,
+    Field,
+    Enum,
+    Variant,
+    Trait,
+    Impl,
+    Function,
+    TypeAlias,
+    ExternCrate,
+    MacroRules,
+    /// Raw code that is not an item, e.g. a macro invocation
+    Raw,
+}
+
+impl ItemKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ItemKind::Use => "use",
+            ItemKind::Module => "mod",
+            ItemKind::Const => "const",
+            ItemKind::Static => "static",
+            ItemKind::Struct => "struct",
+            ItemKind::Field => "field",
+            ItemKind::Enum => "enum",
+            ItemKind::Variant => "variant",
+            ItemKind::Trait => "trait",
+            ItemKind::Impl => "impl",
+            ItemKind::Function => "fn",
+            ItemKind::TypeAlias => "type",
+            ItemKind::ExternCrate => "extern crate",
+            ItemKind::MacroRules => "macro_rules!",
+            ItemKind::Raw => "raw",
+        }
+    }
+}
+
+impl Display for ItemKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An item that is matched by kind and name
+struct Entry<'a> {
+    item: ItemKind,
+    name: String,
+    node: EntryNode<'a>,
+}
+
+enum EntryNode<'a> {
+    /// Compared by its normalized code
+    Leaf(String),
+    Submodule(&'a Submodule),
+    Struct(&'a Struct),
+    Enum(&'a Enum),
+    Trait(&'a Trait),
+    Impl(&'a Impl),
+    Function(&'a Function),
+}
+
+impl<'a> Entry<'a> {
+    fn new(item: ItemKind, name: impl Into<String>, node: EntryNode<'a>) -> Self {
+        Self {
+            item,
+            name: name.into(),
+            node,
+        }
+    }
+
+    fn leaf(item: ItemKind, name: &str, node: &impl GenerateCode) -> Self {
+        Self::new(
+            item,
+            name,
+            EntryNode::Leaf(normalize(&node.to_code_string())),
+        )
+    }
+
+    /// Returns the signature, it is compared before the children
+    fn signature(&self) -> String {
+        match self.node {
+            EntryNode::Leaf(ref code) => code.clone(),
+            EntryNode::Submodule(submodule) => {
+                format!(
+                    "{}mod {}",
+                    visibility(submodule.is_public()),
+                    submodule.name()
+                )
+            }
+            EntryNode::Struct(r#struct) => declaration(
+                r#struct.attributes(),
+                (r#struct.is_public(), r#struct.public_in()),
+                "struct",
+                r#struct.name(),
+                r#struct.generics(),
+            ),
+            EntryNode::Enum(r#enum) => declaration(
+                r#enum.attributes(),
+                (r#enum.is_public(), r#enum.public_in()),
+                "enum",
+                r#enum.name(),
+                r#enum.generics(),
+            ),
+            EntryNode::Trait(r#trait) => declaration(
+                &[],
+                (r#trait.is_public(), None),
+                "trait",
+                r#trait.name(),
+                &[],
+            ),
+            EntryNode::Impl(r#impl) => {
+                let mut code = String::new();
+                Formatter::new(&mut code).write_generics(r#impl.generics());
+                code
+            }
+            EntryNode::Function(function) => {
+                // Docs do not change the signature
+                let mut declaration = function.clone();
+                declaration.doc = None;
+                declaration.remove_body();
+                normalize(&declaration.to_code_string())
+            }
+        }
+    }
+
+    /// Returns the entries of the children and the prefix of their paths
+    fn children(&self, path: &str) -> (String, Vec<Entry<'a>>) {
+        let prefix = format!("{path}::");
+        let entries = match self.node {
+            EntryNode::Leaf(_) | EntryNode::Function(_) => Vec::new(),
+            EntryNode::Submodule(submodule) => module_entries(submodule.module()),
+            EntryNode::Struct(r#struct) => r#struct.fields().iter().map(field_entry).collect(),
+            EntryNode::Enum(r#enum) => r#enum
+                .variants()
+                .iter()
+                .map(|variant| Entry::leaf(ItemKind::Variant, variant.name(), variant))
+                .collect(),
+            EntryNode::Trait(r#trait) => r#trait
+                .nodes()
+                .iter()
+                .filter_map(|node| match node {
+                    TraitNode::Function(function) => Some(function_entry(function)),
+                    _ => None,
+                })
+                .collect(),
+            EntryNode::Impl(r#impl) => r#impl
+                .nodes()
+                .iter()
+                .filter_map(|node| match node {
+                    ImplNode::Function(function) => Some(function_entry(function)),
+                    ImplNode::Const(r#const) => {
+                        Some(Entry::leaf(ItemKind::Const, r#const.name(), r#const))
+                    }
+                    ImplNode::TypeAlias(alias) => {
+                        Some(Entry::leaf(ItemKind::TypeAlias, alias.name(), alias))
+                    }
+                    _ => None,
+                })
+                .collect(),
+        };
+        (prefix, entries)
+    }
+}
+
+fn module_entries(module: &Module) -> Vec<Entry<'_>> {
+    let mut entries = Vec::new();
+    for node in module.nodes() {
+        match node {
+            ModuleNode::Use(r#use) => entries.push(Entry::leaf(ItemKind::Use, r#use.path(), r#use)),
+            ModuleNode::Submodule(submodule) => entries.push(Entry::new(
+                ItemKind::Module,
+                submodule.name(),
+                EntryNode::Submodule(submodule),
+            )),
+            ModuleNode::Const(r#const) => {
+                entries.push(Entry::leaf(ItemKind::Const, r#const.name(), r#const));
+            }
+            ModuleNode::Static(r#static) => {
+                entries.push(Entry::leaf(ItemKind::Static, r#static.name(), r#static));
+            }
+            ModuleNode::Struct(r#struct) => entries.push(Entry::new(
+                ItemKind::Struct,
+                r#struct.name(),
+                EntryNode::Struct(r#struct),
+            )),
+            ModuleNode::Enum(r#enum) => entries.push(Entry::new(
+                ItemKind::Enum,
+                r#enum.name(),
+                EntryNode::Enum(r#enum),
+            )),
+            ModuleNode::Trait(r#trait) => entries.push(Entry::new(
+                ItemKind::Trait,
+                r#trait.name(),
+                EntryNode::Trait(r#trait),
+            )),
+            ModuleNode::Impl(r#impl) => {
+                let name = match r#impl.r#trait() {
+                    Some(r#trait) => format!("<{} as {trait}>", r#impl.target()),
+                    None => r#impl.target().to_owned(),
+                };
+                entries.push(Entry::new(ItemKind::Impl, name, EntryNode::Impl(r#impl)));
+            }
+            ModuleNode::Function(function) => entries.push(function_entry(function)),
+            ModuleNode::TypeAlias(alias) => {
+                entries.push(Entry::leaf(ItemKind::TypeAlias, alias.name(), alias));
+            }
+            ModuleNode::ExternCrate(extern_crate) => entries.push(Entry::leaf(
+                ItemKind::ExternCrate,
+                extern_crate.name(),
+                extern_crate,
+            )),
+            // Foreign items and thread locals are part of the module's namespace
+            ModuleNode::ExternBlock(extern_block) => {
+                for node in extern_block.nodes() {
+                    entries.push(match node {
+                        ExternNode::Function(function) => function_entry(function),
+                        ExternNode::Static(r#static) => {
+                            Entry::leaf(ItemKind::Static, r#static.name(), r#static)
+                        }
+                    });
+                }
+            }
+            ModuleNode::MacroRules(macro_rules) => entries.push(Entry::leaf(
+                ItemKind::MacroRules,
+                macro_rules.name(),
+                macro_rules,
+            )),
+            ModuleNode::ThreadLocal(thread_local) => {
+                for r#static in thread_local.statics() {
+                    entries.push(Entry::leaf(ItemKind::Static, r#static.name(), r#static));
+                }
+            }
+            ModuleNode::Raw(raw) => entries.push(raw_entry(raw)),
+        }
+    }
+    entries
+}
+
+fn function_entry(function: &Function) -> Entry<'_> {
+    Entry::new(
+        ItemKind::Function,
+        function.name(),
+        EntryNode::Function(function),
+    )
+}
+
+/// Matches raw code by the kind and name of its item, or by its first line if it is not an item
+fn raw_entry(raw: &Raw) -> Entry<'_> {
+    let code = EntryNode::Leaf(normalize(raw.code()));
+    let Some(header) = parse::item_header(raw.code()) else {
+        let line = raw
+            .code()
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty());
+        return Entry::new(ItemKind::Raw, line.unwrap_or_default(), code);
+    };
+    let item = match &*header.keyword {
+        "use" => ItemKind::Use,
+        "mod" => ItemKind::Module,
+        "const" => ItemKind::Const,
+        "static" => ItemKind::Static,
+        "struct" => ItemKind::Struct,
+        "enum" => ItemKind::Enum,
+        "trait" => ItemKind::Trait,
+        "fn" => ItemKind::Function,
+        "type" => ItemKind::TypeAlias,
+        "extern" => ItemKind::ExternCrate,
+        "macro_rules" => ItemKind::MacroRules,
+        "impl" => {
+            let name = match header.r#trait {
+                Some(r#trait) => format!("<{} as {trait}>", header.name),
+                None => header.name,
+            };
+            return Entry::new(ItemKind::Impl, name, code);
+        }
+        _ => ItemKind::Raw,
+    };
+    Entry::new(item, header.name, code)
+}
+
+fn field_entry(field: &Field) -> Entry<'_> {
+    Entry::leaf(ItemKind::Field, field.name(), field)
+}
+
+/// Compares entries by kind and name, items with the same key are matched in order
+fn compare(prefix: &str, old: &[Entry], new: &[Entry], changes: &mut Vec<Change>) {
+    let mut matched = vec![false; new.len()];
+    for (i, entry) in old.iter().enumerate() {
+        let path = format!("{prefix}{}", entry.name);
+        let nth = occurrence(old, i);
+        let counterpart = (0..new.len()).find(|&j| {
+            new[j].item == entry.item && new[j].name == entry.name && nth == occurrence(new, j)
+        });
+        let Some(j) = counterpart else {
+            changes.push(change(entry.item, path, ChangeKind::Removed));
+            continue;
+        };
+        matched[j] = true;
+        let (old_signature, new_signature) = (entry.signature(), new[j].signature());
+        if old_signature != new_signature {
+            changes.push(change(
+                entry.item,
+                path.clone(),
+                ChangeKind::Changed {
+                    old: old_signature,
+                    new: new_signature,
+                },
+            ));
+        } else if let (EntryNode::Function(old), EntryNode::Function(new)) =
+            (&entry.node, &new[j].node)
+        {
+            if old.body() != new.body() {
+                changes.push(change(entry.item, path.clone(), ChangeKind::BodyChanged));
+            }
+        }
+        let (child_prefix, old_children) = entry.children(&path);
+        let (_, new_children) = new[j].children(&path);
+        compare(&child_prefix, &old_children, &new_children, changes);
+    }
+    for (entry, _) in new.iter().zip(matched).filter(|(_, matched)| !matched) {
+        let path = format!("{prefix}{}", entry.name);
+        changes.push(change(entry.item, path, ChangeKind::Added));
+    }
+}
+
+/// Counts the entries before `index` with the same key
+fn occurrence(entries: &[Entry], index: usize) -> usize {
+    let entry = &entries[index];
+    entries[..index]
+        .iter()
+        .filter(|other| other.item == entry.item && other.name == entry.name)
+        .count()
+}
+
+fn change(item: ItemKind, path: String, kind: ChangeKind) -> Change {
+    Change { item, path, kind }
+}
+
+/// Returns the declaration of an item without its body, e.g. `#[derive(Debug)] pub struct A<T>`
+fn declaration(
+    attributes: &[Str],
+    (public, public_in): (bool, Option<&str>),
+    keyword: &str,
+    name: &str,
+    generics: &[Str],
+) -> String {
+    let mut code = String::new();
+    let mut fmt = Formatter::new(&mut code);
+    for attribute in attributes {
+        _ = write!(fmt, "#[{attribute}] ");
+    }
+    fmt.write_visibility(public, public_in);
+    _ = write!(fmt, "{keyword} {name}");
+    fmt.write_generics(generics);
+    code
+}
+
+fn visibility(public: bool) -> &'static str {
+    if public {
+        "pub "
+    } else {
+        ""
+    }
+}
+
+/// Collapses whitespace and removes the trailing `;` or `,` of generated code
+fn normalize(code: &str) -> String {
+    let code = code.split_whitespace().collect::<Vec<_>>().join(" ");
+    code.trim_end_matches([';', ',']).to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed_diff(old: &str, new: &str) -> String {
+        let old = crate::parse::parse_module(old).unwrap();
+        let new = crate::parse::parse_module(new).unwrap();
+        diff(&old, &new).to_string()
+    }
+
+    #[test]
+    fn attributes() {
+        assert_eq!(
+            parsed_diff(
+                "#[derive(Debug)]\npub struct A {\n    x: u8,\n}\n",
+                "#[derive(Debug, Clone)]\npub(crate) struct A<T> {\n    x: u8,\n}\n",
+            ),
+            "~ struct A: `#[derive(Debug)] pub struct A` -> \
+             `#[derive(Debug, Clone)] pub(crate) struct A<T>`\n"
+        );
+    }
+
+    #[test]
+    fn function_docs() {
+        assert_eq!(
+            parsed_diff("/// Old\nfn f() {}\n", "/// New\nfn f() {}\n"),
+            ""
+        );
+        assert_eq!(
+            parsed_diff("/// Old\nfn f() {}\n", "/// New\n#[inline]\nfn f() {}\n"),
+            "~ fn f: `fn f()` -> `#[inline] fn f()`\n"
+        );
+    }
+
+    #[test]
+    fn raw_items() {
+        assert_eq!(
+            parsed_diff(
+                "impl<T> Foo for Bar<T> where T: Copy {}\n\nunion U { a: u8 }\n\nfoo!();\n",
+                "union U { a: u16 }\n\nbar!();\n",
+            ),
+            "- impl <Bar<T> as Foo>\n\
+             ~ raw U: `union U { a: u8 }` -> `union U { a: u16 }`\n\
+             - raw foo!();\n\
+             + raw bar!();\n"
+        );
+    }
+}
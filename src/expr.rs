@@ -1,8 +1,12 @@
 #![allow(unused_must_use)]
 
-use std::fmt::Write;
+use std::{
+    fmt::Write,
+    hash::{Hash, Hasher},
+    mem,
+};
 
-use crate::{generator::GenerateCode, Str};
+use crate::{generator::GenerateCode, parse::ParseError, Str};
 
 pub use self::make::*;
 
@@ -28,12 +32,39 @@ macro_rules! make {
     };
 }
 
+/// Parses an expression such as `a + b * 2i32`
+///
+/// ```
+/// use codegen_rs::{expr, generator::GenerateCode};
+///
+/// let expr = expr::parse("a + b * 2i32").unwrap();
+/// assert_eq!(expr.to_code_string(), "(a + (b * 2i32))");
+/// ```
+pub fn parse(source: &str) -> Result<Expr, ParseError> {
+    crate::parse::parse_expr(source, &[])
+}
+
+/// Parses an expression like [`parse`], replacing `$name` placeholders with the bound expressions
+///
+/// ```
+/// use codegen_rs::{expr, generator::GenerateCode};
+///
+/// let expr = expr::parse_with("$value as u64", &[("value", expr::binding("x"))]).unwrap();
+/// assert_eq!(expr.to_code_string(), "(x as u64)");
+/// ```
+pub fn parse_with(source: &str, placeholders: &[(&str, Expr)]) -> Result<Expr, ParseError> {
+    crate::parse::parse_expr(source, placeholders)
+}
+
 #[non_exhaustive]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Expr {
     Binding(Box<Binding>),
     Literal(Box<Literal>),
     Call(Box<Call>),
+    MacroCall(Box<MacroCall>),
+    MethodCall(Box<MethodCall>),
+    FieldAccess(Box<FieldAccess>),
     UnaryOperation(Box<UnaryOperation>),
     BinaryOperation(Box<BinaryOperation>),
     Cast(Box<Cast>),
@@ -41,12 +72,14 @@ pub enum Expr {
     Array(Box<Array>),
     StructInitializer(Box<StructInitializer>),
     TupleStructInitializer(Box<TupleStructInitializer>),
+    Match(Box<Match>),
 }
 
 impl Expr {
     make!(unary, negate, Minus);
     make!(unary, not, Not);
     make!(unary, reference, And);
+    make!(unary, reference_mut, AndMut);
     make!(unary, dereference, Star);
     make!(binary, add, Plus);
     make!(binary, sub, Minus);
@@ -61,6 +94,7 @@ impl Expr {
     make!(binary, bit_shl, ShiftLeft);
     make!(binary, bit_shr, ShiftRight);
     make!(binary, equals, Equals);
+    make!(binary, not_equals, NotEquals);
     make!(binary, less, Less);
     make!(binary, less_equal, LessEqual);
     make!(binary, greater, Greater);
@@ -69,6 +103,16 @@ impl Expr {
     pub fn cast(self, r#type: impl Into<Str>) -> Self {
         Cast::new(self, r#type.into()).into()
     }
+
+    /// Accesses a field of this expression, e.g. `self.name`
+    pub fn field(self, name: impl Into<Str>) -> Self {
+        FieldAccess::new(self, name).into()
+    }
+
+    /// Calls a method on this expression, arguments are added to the returned [`MethodCall`]
+    pub fn method(self, name: impl Into<Str>) -> MethodCall {
+        MethodCall::new(self, name)
+    }
 }
 
 impl GenerateCode for Expr {
@@ -77,6 +121,9 @@ impl GenerateCode for Expr {
             Expr::Binding(value) => value.generate(fmt),
             Expr::Literal(value) => value.generate(fmt),
             Expr::Call(value) => value.generate(fmt),
+            Expr::MacroCall(value) => value.generate(fmt),
+            Expr::MethodCall(value) => value.generate(fmt),
+            Expr::FieldAccess(value) => value.generate(fmt),
             Expr::UnaryOperation(value) => value.generate(fmt),
             Expr::BinaryOperation(value) => value.generate(fmt),
             Expr::Cast(value) => value.generate(fmt),
@@ -84,19 +131,28 @@ impl GenerateCode for Expr {
             Expr::Array(value) => value.generate(fmt),
             Expr::StructInitializer(value) => value.generate(fmt),
             Expr::TupleStructInitializer(value) => value.generate(fmt),
+            Expr::Match(value) => value.generate(fmt),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Binding {
-    name: Str,
+    pub(crate) name: Str,
 }
 
 impl Binding {
     pub fn new(name: impl Into<Str>) -> Self {
         Self { name: name.into() }
     }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut Str {
+        &mut self.name
+    }
 }
 
 impl GenerateCode for Binding {
@@ -125,6 +181,12 @@ pub enum Literal {
     F32(f32),
     F64(f64),
     Str(Str),
+    /// An integer without a type suffix
+    Integer(u128),
+    /// A float without a type suffix
+    Float(f64),
+    /// An unsigned integer in another base with an optional type suffix, e.g. `0xFFu8`
+    Radix(u128, Radix, Str),
 }
 
 impl GenerateCode for Literal {
@@ -145,16 +207,90 @@ impl GenerateCode for Literal {
             Literal::F32(value) => write!(fmt, "{value:?}f32"),
             Literal::F64(value) => write!(fmt, "{value:?}f64"),
             Literal::Str(value) => write!(fmt, "{value:?}"),
+            Literal::Integer(value) => write!(fmt, "{value}"),
+            Literal::Float(value) => write!(fmt, "{value:?}"),
+            Literal::Radix(value, Radix::Binary, suffix) => write!(fmt, "{value:#b}{suffix}"),
+            Literal::Radix(value, Radix::Octal, suffix) => write!(fmt, "{value:#o}{suffix}"),
+            Literal::Radix(value, Radix::Hexadecimal, suffix) => write!(fmt, "{value:#X}{suffix}"),
         };
     }
 }
 
+/// Floats are compared by their bits, which keeps equality consistent with [`Hash`]
+impl PartialEq for Literal {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Literal::Bool(a), Literal::Bool(b)) => a == b,
+            (Literal::Char(a), Literal::Char(b)) => a == b,
+            (Literal::I8(a), Literal::I8(b)) => a == b,
+            (Literal::U8(a), Literal::U8(b)) => a == b,
+            (Literal::I16(a), Literal::I16(b)) => a == b,
+            (Literal::U16(a), Literal::U16(b)) => a == b,
+            (Literal::I32(a), Literal::I32(b)) => a == b,
+            (Literal::U32(a), Literal::U32(b)) => a == b,
+            (Literal::I64(a), Literal::I64(b)) => a == b,
+            (Literal::U64(a), Literal::U64(b)) => a == b,
+            (Literal::I128(a), Literal::I128(b)) => a == b,
+            (Literal::U128(a), Literal::U128(b)) => a == b,
+            (Literal::F32(a), Literal::F32(b)) => a.to_bits() == b.to_bits(),
+            (Literal::F64(a), Literal::F64(b)) => a.to_bits() == b.to_bits(),
+            (Literal::Str(a), Literal::Str(b)) => a == b,
+            (Literal::Integer(a), Literal::Integer(b)) => a == b,
+            (Literal::Float(a), Literal::Float(b)) => a.to_bits() == b.to_bits(),
+            (Literal::Radix(a, a_radix, a_suffix), Literal::Radix(b, b_radix, b_suffix)) => {
+                a == b && a_radix == b_radix && a_suffix == b_suffix
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Literal {}
+
+impl Hash for Literal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Literal::Bool(value) => value.hash(state),
+            Literal::Char(value) => value.hash(state),
+            Literal::I8(value) => value.hash(state),
+            Literal::U8(value) => value.hash(state),
+            Literal::I16(value) => value.hash(state),
+            Literal::U16(value) => value.hash(state),
+            Literal::I32(value) => value.hash(state),
+            Literal::U32(value) => value.hash(state),
+            Literal::I64(value) => value.hash(state),
+            Literal::U64(value) => value.hash(state),
+            Literal::I128(value) => value.hash(state),
+            Literal::U128(value) => value.hash(state),
+            Literal::F32(value) => value.to_bits().hash(state),
+            Literal::F64(value) => value.to_bits().hash(state),
+            Literal::Str(value) => value.hash(state),
+            Literal::Integer(value) => value.hash(state),
+            Literal::Float(value) => value.to_bits().hash(state),
+            Literal::Radix(value, radix, suffix) => (value, radix, suffix).hash(state),
+        }
+    }
+}
+
 make!(into, Literal);
 
-#[derive(Clone, Debug)]
+/// The base of a [`Literal::Radix`]
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Radix {
+    /// `0b`
+    Binary,
+    /// `0o`
+    Octal,
+    /// `0x`
+    Hexadecimal,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Call {
-    name: Str,
-    args: Vec<Expr>,
+    pub(crate) name: Str,
+    pub(crate) args: Vec<Expr>,
 }
 
 impl Call {
@@ -169,6 +305,22 @@ impl Call {
         self.args.push(value.into());
         self
     }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut Str {
+        &mut self.name
+    }
+
+    pub fn args(&self) -> &[Expr] {
+        &self.args
+    }
+
+    pub fn args_mut(&mut self) -> &mut Vec<Expr> {
+        &mut self.args
+    }
 }
 
 impl GenerateCode for Call {
@@ -178,7 +330,7 @@ impl GenerateCode for Call {
             if i > 0 {
                 write!(fmt, ", ");
             }
-            arg.generate(fmt);
+            generate_unparenthesized(arg, fmt);
         }
         write!(fmt, ")");
     }
@@ -186,8 +338,176 @@ impl GenerateCode for Call {
 
 make!(into, Call);
 
+/// A function-like macro invocation with expression arguments, e.g. `write!(f, "{x}")`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MacroCall {
+    pub(crate) name: Str,
+    pub(crate) args: Vec<Expr>,
+}
+
+impl MacroCall {
+    /// Creates an invocation of `name`, without the `!`
+    pub fn new(name: impl Into<Str>) -> Self {
+        Self {
+            name: name.into(),
+            args: Vec::new(),
+        }
+    }
+
+    pub fn arg(mut self, value: impl Into<Expr>) -> Self {
+        self.args.push(value.into());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut Str {
+        &mut self.name
+    }
+
+    pub fn args(&self) -> &[Expr] {
+        &self.args
+    }
+
+    pub fn args_mut(&mut self) -> &mut Vec<Expr> {
+        &mut self.args
+    }
+}
+
+impl GenerateCode for MacroCall {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        write!(fmt, "{}!(", self.name);
+        for (i, arg) in self.args.iter().enumerate() {
+            if i > 0 {
+                write!(fmt, ", ");
+            }
+            generate_unparenthesized(arg, fmt);
+        }
+        write!(fmt, ")");
+    }
+}
+
+make!(into, MacroCall);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MethodCall {
+    pub(crate) receiver: Expr,
+    pub(crate) name: Str,
+    pub(crate) args: Vec<Expr>,
+}
+
+impl MethodCall {
+    pub fn new(receiver: impl Into<Expr>, name: impl Into<Str>) -> Self {
+        Self {
+            receiver: receiver.into(),
+            name: name.into(),
+            args: Vec::new(),
+        }
+    }
+
+    pub fn arg(mut self, value: impl Into<Expr>) -> Self {
+        self.args.push(value.into());
+        self
+    }
+
+    pub fn receiver(&self) -> &Expr {
+        &self.receiver
+    }
+
+    pub fn receiver_mut(&mut self) -> &mut Expr {
+        &mut self.receiver
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut Str {
+        &mut self.name
+    }
+
+    pub fn args(&self) -> &[Expr] {
+        &self.args
+    }
+
+    pub fn args_mut(&mut self) -> &mut Vec<Expr> {
+        &mut self.args
+    }
+}
+
+impl GenerateCode for MethodCall {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        generate_receiver(&self.receiver, fmt);
+        write!(fmt, ".{}(", self.name);
+        for (i, arg) in self.args.iter().enumerate() {
+            if i > 0 {
+                write!(fmt, ", ");
+            }
+            generate_unparenthesized(arg, fmt);
+        }
+        write!(fmt, ")");
+    }
+}
+
+make!(into, MethodCall);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FieldAccess {
+    pub(crate) expr: Expr,
+    pub(crate) name: Str,
+}
+
+impl FieldAccess {
+    /// Accesses the field `name`, which can also be the index of a tuple field
+    pub fn new(expr: impl Into<Expr>, name: impl Into<Str>) -> Self {
+        Self {
+            expr: expr.into(),
+            name: name.into(),
+        }
+    }
+
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    pub fn expr_mut(&mut self) -> &mut Expr {
+        &mut self.expr
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut Str {
+        &mut self.name
+    }
+}
+
+impl GenerateCode for FieldAccess {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        generate_receiver(&self.expr, fmt);
+        write!(fmt, ".{}", self.name);
+    }
+}
+
+make!(into, FieldAccess);
+
+/// Generates the left side of a field access or method call, unary operations bind less tightly
+fn generate_receiver(expr: &Expr, fmt: &mut crate::generator::Formatter) {
+    match expr {
+        Expr::UnaryOperation(_) => {
+            write!(fmt, "(");
+            expr.generate(fmt);
+            write!(fmt, ")");
+        }
+        _ => expr.generate(fmt),
+    }
+}
+
 #[non_exhaustive]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Operator {
     /// `+`
     Plus,
@@ -205,6 +525,8 @@ pub enum Operator {
     Or,
     /// `&`
     And,
+    /// `&mut `, only used as a unary operator
+    AndMut,
     /// `^`
     Xor,
     /// `||`
@@ -217,6 +539,8 @@ pub enum Operator {
     ShiftRight,
     /// `==`
     Equals,
+    /// `!=`
+    NotEquals,
     /// `<`
     Less,
     /// `<=`
@@ -238,12 +562,14 @@ impl Operator {
             Operator::Not => "!",
             Operator::Or => "|",
             Operator::And => "&",
+            Operator::AndMut => "&mut ",
             Operator::Xor => "^",
             Operator::DoubleOr => "||",
             Operator::DoubleAnd => "&&",
             Operator::ShiftLeft => "<<",
             Operator::ShiftRight => ">>",
             Operator::Equals => "==",
+            Operator::NotEquals => "!=",
             Operator::Less => "<",
             Operator::LessEqual => "<=",
             Operator::Greater => ">",
@@ -252,10 +578,10 @@ impl Operator {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct UnaryOperation {
-    operator: Operator,
-    expr: Expr,
+    pub(crate) operator: Operator,
+    pub(crate) expr: Expr,
 }
 
 impl UnaryOperation {
@@ -265,6 +591,18 @@ impl UnaryOperation {
             expr: expr.into(),
         }
     }
+
+    pub fn operator(&self) -> Operator {
+        self.operator
+    }
+
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    pub fn expr_mut(&mut self) -> &mut Expr {
+        &mut self.expr
+    }
 }
 
 impl GenerateCode for UnaryOperation {
@@ -276,11 +614,11 @@ impl GenerateCode for UnaryOperation {
 
 make!(into, UnaryOperation);
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct BinaryOperation {
-    operator: Operator,
-    left: Expr,
-    right: Expr,
+    pub(crate) operator: Operator,
+    pub(crate) left: Expr,
+    pub(crate) right: Expr,
 }
 
 impl BinaryOperation {
@@ -291,6 +629,26 @@ impl BinaryOperation {
             right: right.into(),
         }
     }
+
+    pub fn operator(&self) -> Operator {
+        self.operator
+    }
+
+    pub fn left(&self) -> &Expr {
+        &self.left
+    }
+
+    pub fn left_mut(&mut self) -> &mut Expr {
+        &mut self.left
+    }
+
+    pub fn right(&self) -> &Expr {
+        &self.right
+    }
+
+    pub fn right_mut(&mut self) -> &mut Expr {
+        &mut self.right
+    }
 }
 
 impl GenerateCode for BinaryOperation {
@@ -305,10 +663,10 @@ impl GenerateCode for BinaryOperation {
 
 make!(into, BinaryOperation);
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Cast {
-    expr: Expr,
-    r#type: Str,
+    pub(crate) expr: Expr,
+    pub(crate) r#type: Str,
 }
 
 impl Cast {
@@ -318,6 +676,22 @@ impl Cast {
             r#type: r#type.into(),
         }
     }
+
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    pub fn expr_mut(&mut self) -> &mut Expr {
+        &mut self.expr
+    }
+
+    pub fn r#type(&self) -> &str {
+        &self.r#type
+    }
+
+    pub fn type_mut(&mut self) -> &mut Str {
+        &mut self.r#type
+    }
 }
 
 impl GenerateCode for Cast {
@@ -332,8 +706,8 @@ make!(into, Cast);
 
 #[derive(Clone, Debug)]
 pub struct Tuple {
-    multiline: bool,
-    values: Vec<Expr>,
+    pub(crate) multiline: bool,
+    pub(crate) values: Vec<Expr>,
 }
 
 impl Tuple {
@@ -353,6 +727,18 @@ impl Tuple {
         self.values.push(value.into());
         self
     }
+
+    pub fn is_multiline(&self) -> bool {
+        self.multiline
+    }
+
+    pub fn values(&self) -> &[Expr] {
+        &self.values
+    }
+
+    pub fn values_mut(&mut self) -> &mut Vec<Expr> {
+        &mut self.values
+    }
 }
 
 impl GenerateCode for Tuple {
@@ -366,7 +752,7 @@ impl GenerateCode for Tuple {
             writeln!(fmt);
             fmt.indent(|fmt| {
                 for value in &self.values {
-                    value.generate(fmt);
+                    generate_unparenthesized(value, fmt);
                     writeln!(fmt, ",");
                 }
             });
@@ -375,7 +761,7 @@ impl GenerateCode for Tuple {
                 if i > 0 {
                     write!(fmt, ", ");
                 }
-                value.generate(fmt);
+                generate_unparenthesized(value, fmt);
             }
             if self.values.len() == 1 {
                 write!(fmt, ",");
@@ -385,12 +771,27 @@ impl GenerateCode for Tuple {
     }
 }
 
+/// Only compares the values, `multiline` only affects formatting
+impl PartialEq for Tuple {
+    fn eq(&self, other: &Self) -> bool {
+        self.values == other.values
+    }
+}
+
+impl Eq for Tuple {}
+
+impl Hash for Tuple {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.values.hash(state);
+    }
+}
+
 make!(into, Tuple);
 
 #[derive(Clone, Debug)]
 pub struct Array {
-    multiline: bool,
-    values: Vec<Expr>,
+    pub(crate) multiline: bool,
+    pub(crate) values: Vec<Expr>,
 }
 
 impl Array {
@@ -410,6 +811,18 @@ impl Array {
         self.values.push(value.into());
         self
     }
+
+    pub fn is_multiline(&self) -> bool {
+        self.multiline
+    }
+
+    pub fn values(&self) -> &[Expr] {
+        &self.values
+    }
+
+    pub fn values_mut(&mut self) -> &mut Vec<Expr> {
+        &mut self.values
+    }
 }
 
 impl GenerateCode for Array {
@@ -423,7 +836,7 @@ impl GenerateCode for Array {
             writeln!(fmt);
             fmt.indent(|fmt| {
                 for value in &self.values {
-                    value.generate(fmt);
+                    generate_unparenthesized(value, fmt);
                     writeln!(fmt, ",");
                 }
             });
@@ -432,20 +845,35 @@ impl GenerateCode for Array {
                 if i > 0 {
                     write!(fmt, ", ");
                 }
-                value.generate(fmt);
+                generate_unparenthesized(value, fmt);
             }
         }
         write!(fmt, "]");
     }
 }
 
+/// Only compares the values, `multiline` only affects formatting
+impl PartialEq for Array {
+    fn eq(&self, other: &Self) -> bool {
+        self.values == other.values
+    }
+}
+
+impl Eq for Array {}
+
+impl Hash for Array {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.values.hash(state);
+    }
+}
+
 make!(into, Array);
 
 #[derive(Clone, Debug)]
 pub struct StructInitializer {
-    multiline: bool,
-    name: Str,
-    fields: Vec<(Str, Option<Expr>)>,
+    pub(crate) multiline: bool,
+    pub(crate) name: Str,
+    pub(crate) fields: Vec<(Str, Option<Expr>)>,
 }
 
 impl StructInitializer {
@@ -472,6 +900,27 @@ impl StructInitializer {
         self.fields.push((name.into(), None));
         self
     }
+
+    pub fn is_multiline(&self) -> bool {
+        self.multiline
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut Str {
+        &mut self.name
+    }
+
+    /// Returns the fields, a value of `None` means the value is inferred by name
+    pub fn fields(&self) -> &[(Str, Option<Expr>)] {
+        &self.fields
+    }
+
+    pub fn fields_mut(&mut self) -> &mut Vec<(Str, Option<Expr>)> {
+        &mut self.fields
+    }
 }
 
 impl GenerateCode for StructInitializer {
@@ -511,12 +960,28 @@ impl GenerateCode for StructInitializer {
     }
 }
 
+/// Only compares the name and fields, `multiline` only affects formatting
+impl PartialEq for StructInitializer {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.fields == other.fields
+    }
+}
+
+impl Eq for StructInitializer {}
+
+impl Hash for StructInitializer {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.fields.hash(state);
+    }
+}
+
 make!(into, StructInitializer);
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct TupleStructInitializer {
-    name: Str,
-    tuple: Tuple,
+    pub(crate) name: Str,
+    pub(crate) tuple: Tuple,
 }
 
 impl TupleStructInitializer {
@@ -536,6 +1001,26 @@ impl TupleStructInitializer {
         self.tuple.values.push(value.into());
         self
     }
+
+    pub fn is_multiline(&self) -> bool {
+        self.tuple.multiline
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut Str {
+        &mut self.name
+    }
+
+    pub fn args(&self) -> &[Expr] {
+        &self.tuple.values
+    }
+
+    pub fn args_mut(&mut self) -> &mut Vec<Expr> {
+        &mut self.tuple.values
+    }
 }
 
 impl GenerateCode for TupleStructInitializer {
@@ -546,3 +1031,136 @@ impl GenerateCode for TupleStructInitializer {
 }
 
 make!(into, TupleStructInitializer);
+
+/// A `match` expression, patterns are kept as strings
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Match {
+    pub(crate) expr: Expr,
+    pub(crate) arms: Vec<MatchArm>,
+}
+
+impl Match {
+    pub fn new(expr: impl Into<Expr>) -> Self {
+        Self {
+            expr: expr.into(),
+            arms: Vec::new(),
+        }
+    }
+
+    /// Adds an arm, e.g. `Self::A => "a"`
+    pub fn arm(mut self, pattern: impl Into<Str>, value: impl Into<Expr>) -> Self {
+        self.arms.push(MatchArm::new(pattern, value));
+        self
+    }
+
+    /// Adds an arm with a guard, e.g. `n if n > 0 => 1`
+    pub fn arm_if(
+        mut self,
+        pattern: impl Into<Str>,
+        guard: impl Into<Expr>,
+        value: impl Into<Expr>,
+    ) -> Self {
+        let mut arm = MatchArm::new(pattern, value);
+        arm.guard = Some(guard.into());
+        self.arms.push(arm);
+        self
+    }
+
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    pub fn expr_mut(&mut self) -> &mut Expr {
+        &mut self.expr
+    }
+
+    pub fn arms(&self) -> &[MatchArm] {
+        &self.arms
+    }
+
+    pub fn arms_mut(&mut self) -> &mut Vec<MatchArm> {
+        &mut self.arms
+    }
+}
+
+impl GenerateCode for Match {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        write!(fmt, "match ");
+        generate_unparenthesized(&self.expr, fmt);
+        write!(fmt, " ");
+        fmt.write_block(|fmt| {
+            for arm in &self.arms {
+                arm.generate(fmt);
+            }
+        });
+        fmt.pop_newline();
+    }
+}
+
+make!(into, Match);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MatchArm {
+    pub(crate) pattern: Str,
+    pub(crate) guard: Option<Expr>,
+    pub(crate) value: Expr,
+}
+
+impl MatchArm {
+    pub fn new(pattern: impl Into<Str>, value: impl Into<Expr>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            guard: None,
+            value: value.into(),
+        }
+    }
+
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn pattern_mut(&mut self) -> &mut Str {
+        &mut self.pattern
+    }
+
+    pub fn guard(&self) -> Option<&Expr> {
+        self.guard.as_ref()
+    }
+
+    pub fn guard_mut(&mut self) -> Option<&mut Expr> {
+        self.guard.as_mut()
+    }
+
+    pub fn value(&self) -> &Expr {
+        &self.value
+    }
+
+    pub fn value_mut(&mut self) -> &mut Expr {
+        &mut self.value
+    }
+}
+
+impl GenerateCode for MatchArm {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        fmt.write_str(&self.pattern);
+        if let Some(guard) = &self.guard {
+            write!(fmt, " if ");
+            generate_unparenthesized(guard, fmt);
+        }
+        write!(fmt, " => ");
+        generate_unparenthesized(&self.value, fmt);
+        writeln!(fmt, ",");
+    }
+}
+
+/// Generates an expression without the parentheses of binary operations and casts, for positions
+/// that are already delimited like conditions, `match` arms and statements
+pub(crate) fn generate_unparenthesized(expr: &Expr, fmt: &mut crate::generator::Formatter) {
+    match expr {
+        Expr::BinaryOperation(_) | Expr::Cast(_) => {
+            let code = expr.to_code_string();
+            fmt.write_str(&code[1..code.len() - 1]);
+        }
+        _ => expr.generate(fmt),
+    }
+}
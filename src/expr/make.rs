@@ -9,8 +9,8 @@
 use crate::Str;
 
 use super::{
-    Array, BinaryOperation, Binding, Call, Cast, Expr, Literal, Operator, StructInitializer, Tuple,
-    TupleStructInitializer, UnaryOperation,
+    Array, BinaryOperation, Binding, Call, Cast, Expr, FieldAccess, Literal, MacroCall, Match,
+    MethodCall, Operator, Radix, StructInitializer, Tuple, TupleStructInitializer, UnaryOperation,
 };
 
 macro_rules! make {
@@ -34,6 +34,7 @@ macro_rules! make {
 make!(unary, negate, Minus);
 make!(unary, not, Not);
 make!(unary, reference, And);
+make!(unary, reference_mut, AndMut);
 make!(unary, dereference, Star);
 make!(binary, add, Plus);
 make!(binary, sub, Minus);
@@ -48,6 +49,7 @@ make!(binary, bit_xor, Xor);
 make!(binary, bit_shl, ShiftLeft);
 make!(binary, bit_shr, ShiftRight);
 make!(binary, equals, Equals);
+make!(binary, not_equals, NotEquals);
 make!(binary, less, Less);
 make!(binary, less_equal, LessEqual);
 make!(binary, greater, Greater);
@@ -73,6 +75,28 @@ make!(literal, I64, i64);
 make!(literal, U64, u64);
 make!(literal, I128, i128);
 make!(literal, U128, u128);
+make!(literal, F32, f32);
+make!(literal, F64, f64);
+
+/// An integer literal without a type suffix
+pub fn integer(value: u128) -> Expr {
+    Expr::Literal(Box::new(Literal::Integer(value)))
+}
+
+/// A hexadecimal integer literal without a type suffix, e.g. `0xFF`
+pub fn hex(value: u128) -> Expr {
+    radix(value, Radix::Hexadecimal, "")
+}
+
+/// An integer literal in `radix` with an optional type suffix, e.g. `0b101u8`
+pub fn radix(value: u128, radix: Radix, suffix: impl Into<Str>) -> Expr {
+    Expr::Literal(Box::new(Literal::Radix(value, radix, suffix.into())))
+}
+
+/// A float literal without a type suffix
+pub fn float(value: f64) -> Expr {
+    Expr::Literal(Box::new(Literal::Float(value)))
+}
 
 pub fn str(value: impl Into<Str>) -> Expr {
     Expr::Literal(Box::new(Literal::Str(value.into())))
@@ -82,6 +106,19 @@ pub fn call(name: impl Into<Str>) -> Call {
     Call::new(name.into())
 }
 
+/// Invokes the macro `name`, e.g. `macro_call("vec")` for `vec!(..)`
+pub fn macro_call(name: impl Into<Str>) -> MacroCall {
+    MacroCall::new(name)
+}
+
+pub fn method_call(receiver: impl Into<Expr>, name: impl Into<Str>) -> MethodCall {
+    MethodCall::new(receiver, name)
+}
+
+pub fn field_access(expr: impl Into<Expr>, name: impl Into<Str>) -> Expr {
+    FieldAccess::new(expr, name).into()
+}
+
 pub fn tuple() -> Tuple {
     Tuple::new()
 }
@@ -111,3 +148,8 @@ pub fn init_struct(name: impl Into<Str>) -> StructInitializer {
 pub fn init_tuple_struct(name: impl Into<Str>) -> TupleStructInitializer {
     TupleStructInitializer::new(name.into())
 }
+
+/// A `match` on `expr`, arms are added to the returned [`Match`]
+pub fn r#match(expr: impl Into<Expr>) -> Match {
+    Match::new(expr)
+}
@@ -0,0 +1,88 @@
+//! Helpers to write generated code to the file system
+
+use std::{fs, io, path::Path};
+
+use crate::Str;
+
+/// Writes `contents` to `path` unless the file already contains exactly `contents`
+///
+/// Leaving unchanged files alone keeps their timestamps, so cargo does not rebuild them.
+/// Returns whether the file was written.
+pub fn write_if_changed(path: impl AsRef<Path>, contents: &str) -> io::Result<bool> {
+    let path = path.as_ref();
+    match fs::read(path) {
+        Ok(existing) if existing == contents.as_bytes() => return Ok(false),
+        Ok(_) => {}
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+    fs::write(path, contents)?;
+    Ok(true)
+}
+
+/// The file layout used for submodules
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ModuleStyle {
+    /// `x.rs` with its submodules in `x/`
+    #[default]
+    File,
+    /// `x/mod.rs` with its submodules next to it
+    ModRs,
+}
+
+/// Options for [`Module::write_to_dir_with`](crate::nodes::Module::write_to_dir_with)
+#[derive(Clone, Debug)]
+pub struct DirOptions {
+    root_file: Str,
+    style: ModuleStyle,
+}
+
+impl DirOptions {
+    pub fn new() -> Self {
+        Self {
+            root_file: "lib.rs".into(),
+            style: ModuleStyle::File,
+        }
+    }
+
+    /// Set the name of the file the root module is written to, `lib.rs` by default
+    pub fn set_root_file(&mut self, root_file: impl Into<Str>) -> &mut Self {
+        self.root_file = root_file.into();
+        self
+    }
+
+    pub fn set_style(&mut self, style: ModuleStyle) -> &mut Self {
+        self.style = style;
+        self
+    }
+
+    pub fn root_file(&self) -> &str {
+        &self.root_file
+    }
+
+    pub fn style(&self) -> ModuleStyle {
+        self.style
+    }
+}
+
+impl Default for DirOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unchanged_contents() {
+        let path = std::env::temp_dir().join(format!("codegen-rs-fs-{}.rs", std::process::id()));
+        _ = fs::remove_file(&path);
+        assert!(write_if_changed(&path, "struct A;\n").unwrap());
+        assert!(!write_if_changed(&path, "struct A;\n").unwrap());
+        assert!(write_if_changed(&path, "struct B;\n").unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "struct B;\n");
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -1,5 +1,7 @@
 use std::fmt::Write;
 
+use crate::Str;
+
 pub trait GenerateCode {
     fn generate(&self, fmt: &mut Formatter);
 
@@ -42,6 +44,58 @@ impl<'a> Formatter<'a> {
         self.dst.is_empty() || self.dst.ends_with('\n')
     }
 
+    /// Removes a trailing newline, used to continue a line after a block
+    pub fn pop_newline(&mut self) {
+        if self.dst.ends_with('\n') {
+            self.dst.pop();
+        }
+    }
+
+    /// Writes a doc comment followed by outer attributes, e.g. `/// Docs` and `#[inline]`
+    pub fn write_doc_and_attributes(&mut self, doc: Option<&str>, attributes: &[Str]) {
+        if let Some(doc) = doc {
+            for line in doc.lines() {
+                match line.is_empty() {
+                    true => _ = writeln!(self, "///"),
+                    false => _ = writeln!(self, "/// {line}"),
+                }
+            }
+        }
+        for attribute in attributes {
+            _ = writeln!(self, "#[{attribute}]");
+        }
+    }
+
+    /// Writes text without indenting it, e.g. the continuation of a multi-line string literal
+    pub fn write_unindented(&mut self, s: &str) {
+        self.dst.push_str(s);
+    }
+
+    /// Writes `pub `, restricted to `path` if it is set, e.g. `pub(crate) ` or `pub(in a::b) `
+    pub fn write_visibility(&mut self, public: bool, path: Option<&str>) {
+        match (public, path) {
+            (false, _) => {}
+            (true, None) => _ = write!(self, "pub "),
+            (true, Some(path @ ("crate" | "super" | "self"))) => _ = write!(self, "pub({path}) "),
+            (true, Some(path)) => _ = write!(self, "pub(in {path}) "),
+        }
+    }
+
+    /// Writes generic parameters like `<T, U>`, nothing if there are none
+    pub fn write_generics(&mut self, generics: &[Str]) {
+        if generics.is_empty() {
+            return;
+        }
+        _ = write!(self, "<");
+        for (i, generic) in generics.iter().enumerate() {
+            if i > 0 {
+                _ = write!(self, ", ");
+            }
+            _ = write!(self, "{generic}");
+        }
+        _ = write!(self, ">");
+    }
+
     pub fn push_spaces(&mut self) {
         for _ in 0..self.indent {
             self.dst.push(' ');
@@ -0,0 +1,716 @@
+//! Dependencies between the types defined in a module
+//!
+//! A [`TypeGraph`] contains the structs, enums and type aliases of a module and the references
+//! of their fields to each other. References behind a pointer such as `Box`, `Vec` or `&` are
+//! indirect, all others, including `Option`, tuples and arrays, store the type inline. A cycle
+//! of inline references is a type of infinite size which does not compile, neither does a type
+//! alias that refers to itself:
+//!
+//! ```
+//! # use codegen_rs::nodes::Module;
+//! let mut module = Module::new();
+//! module.add_struct("A").add_field("b", "B");
+//! module.add_struct("B").add_field("a", "Option<A>");
+//! assert_eq!(
+//!     module.check_recursive_types().unwrap_err().to_string(),
+//!     "infinitely sized recursive types: A.b -> B.a -> A"
+//! );
+//! module.box_recursive_fields().unwrap();
+//! assert_eq!(module.get_struct("B").unwrap().fields()[0].r#type(), "Option<Box<A>>");
+//! ```
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    nodes::{r#enum::VariantFields, Module, ModuleNode},
+    parse::lexer::{Lexer, TokenKind},
+};
+
+/// Generic types that store their arguments behind a pointer
+const INDIRECT: &[&str] = &[
+    "Box",
+    "Vec",
+    "VecDeque",
+    "LinkedList",
+    "BinaryHeap",
+    "HashMap",
+    "HashSet",
+    "BTreeMap",
+    "BTreeSet",
+    "Rc",
+    "Arc",
+    "Weak",
+    "PhantomData",
+];
+
+/// A field of a type in the graph
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FieldRef {
+    r#type: String,
+    variant: Option<String>,
+    field: String,
+}
+
+impl FieldRef {
+    /// The struct, enum or type alias containing the field, prefixed with the submodule path
+    pub fn r#type(&self) -> &str {
+        &self.r#type
+    }
+
+    pub fn variant(&self) -> Option<&str> {
+        self.variant.as_deref()
+    }
+
+    /// The name or the index of the field, empty for type aliases
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+}
+
+impl Display for FieldRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.r#type)?;
+        if let Some(variant) = &self.variant {
+            write!(f, "::{variant}")?;
+        }
+        if !self.field.is_empty() {
+            write!(f, ".{}", self.field)?;
+        }
+        Ok(())
+    }
+}
+
+/// A reference of a field to a type of the module
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dependency {
+    field: FieldRef,
+    target: String,
+    indirect: bool,
+}
+
+impl Dependency {
+    pub fn field(&self) -> &FieldRef {
+        &self.field
+    }
+
+    /// The name of the referenced type
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Whether the type is stored behind a pointer
+    pub fn is_indirect(&self) -> bool {
+        self.indirect
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TypeNode {
+    name: String,
+    alias: bool,
+    dependencies: Vec<Dependency>,
+}
+
+/// The dependencies followed when searching for cycles
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Edges {
+    All,
+    /// References that store the type inline
+    Inline,
+    /// References of type aliases to type aliases, even behind a pointer
+    Aliases,
+}
+
+/// The types of a module and their dependencies
+#[derive(Clone, Debug)]
+pub struct TypeGraph {
+    types: Vec<TypeNode>,
+    indices: HashMap<String, usize>,
+}
+
+impl TypeGraph {
+    /// Builds the graph of the structs, enums and type aliases directly in `module`
+    pub fn new(module: &Module) -> Self {
+        let mut fields = Vec::new();
+        for node in module.nodes() {
+            let (name, types) = match node {
+                ModuleNode::Struct(struct_) => (
+                    struct_.name(),
+                    struct_
+                        .fields()
+                        .iter()
+                        .map(|field| (None, field.name().to_owned(), field.r#type()))
+                        .collect(),
+                ),
+                ModuleNode::Enum(enum_) => (
+                    enum_.name(),
+                    enum_
+                        .variants()
+                        .iter()
+                        .flat_map(|variant| {
+                            let name = Some(variant.name());
+                            let fields: Vec<_> = match variant.fields() {
+                                VariantFields::Unit => Vec::new(),
+                                VariantFields::Unnamed(types) => types
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(i, r#type)| (name, i.to_string(), &**r#type))
+                                    .collect(),
+                                VariantFields::Named(fields) => fields
+                                    .iter()
+                                    .map(|field| (name, field.name().to_owned(), field.r#type()))
+                                    .collect(),
+                            };
+                            fields
+                        })
+                        .collect(),
+                ),
+                ModuleNode::TypeAlias(alias) => {
+                    (alias.name(), vec![(None, String::new(), alias.r#type())])
+                }
+                _ => continue,
+            };
+            fields.push((name, matches!(node, ModuleNode::TypeAlias(_)), types));
+        }
+        let mut indices = HashMap::new();
+        for (i, (name, _, _)) in fields.iter().enumerate() {
+            indices.entry(name.to_string()).or_insert(i);
+        }
+        let types = fields
+            .iter()
+            .map(|(name, alias, fields)| {
+                let mut dependencies: Vec<Dependency> = Vec::new();
+                for (variant, field, r#type) in fields {
+                    let field = FieldRef {
+                        r#type: name.to_string(),
+                        variant: variant.map(str::to_owned),
+                        field: field.clone(),
+                    };
+                    for (target, indirect) in references(r#type) {
+                        if !indices.contains_key(target) {
+                            continue;
+                        }
+                        // A field that refers to a type multiple times stores it inline if any
+                        // of the references does
+                        match dependencies
+                            .iter_mut()
+                            .find(|dep| dep.field == field && dep.target == target)
+                        {
+                            Some(dep) => dep.indirect &= indirect,
+                            None => dependencies.push(Dependency {
+                                field: field.clone(),
+                                target: target.into(),
+                                indirect,
+                            }),
+                        }
+                    }
+                }
+                TypeNode {
+                    name: name.to_string(),
+                    alias: *alias,
+                    dependencies,
+                }
+            })
+            .collect();
+        Self { types, indices }
+    }
+
+    /// The names of the types in definition order
+    pub fn types(&self) -> impl Iterator<Item = &str> {
+        self.types.iter().map(|node| node.name.as_str())
+    }
+
+    /// The references of the fields of `name` to other types of the module
+    pub fn dependencies(&self, name: &str) -> &[Dependency] {
+        match self.indices.get(name) {
+            Some(&i) => &self.types[i].dependencies,
+            None => &[],
+        }
+    }
+
+    /// Returns the types so that each type comes after the types it refers to
+    ///
+    /// Mutually recursive types are kept in definition order, unrelated types stay in definition
+    /// order as far as possible.
+    pub fn topological_order(&self) -> Vec<&str> {
+        self.order()
+            .into_iter()
+            .map(|i| self.types[i].name.as_str())
+            .collect()
+    }
+
+    /// Returns one cycle for each group of types with infinite size
+    ///
+    /// These are cycles of inline references and cycles of type aliases, which cannot refer to
+    /// themselves even behind a pointer.
+    pub fn infinite_cycles(&self) -> Vec<Cycle> {
+        let mut cycles = self.alias_cycles();
+        cycles.extend(
+            self.cycles(Edges::Inline)
+                .into_iter()
+                .filter(|cycle| !self.is_alias_cycle(cycle)),
+        );
+        cycles
+    }
+
+    /// Returns the cycles of type aliases, they cannot be fixed by boxing a field
+    fn alias_cycles(&self) -> Vec<Cycle> {
+        self.cycles(Edges::Aliases)
+    }
+
+    fn is_alias_cycle(&self, cycle: &Cycle) -> bool {
+        cycle.fields.iter().all(|field| self.is_alias(field))
+    }
+
+    fn is_alias(&self, field: &FieldRef) -> bool {
+        self.types[self.indices[&field.r#type]].alias
+    }
+
+    /// Returns one cycle for each strongly connected component with a cycle
+    fn cycles(&self, edges: Edges) -> Vec<Cycle> {
+        let mut cycles = Vec::new();
+        for component in self.components(edges) {
+            let root = component[0];
+            let recursive = component.len() > 1
+                || self
+                    .successors(root, edges)
+                    .any(|(target, _)| target == root);
+            if !recursive {
+                continue;
+            }
+            // Breadth-first search for the shortest way back to the root
+            let mut previous: HashMap<usize, (usize, &Dependency)> = HashMap::new();
+            let mut queue = std::collections::VecDeque::from([root]);
+            'search: while let Some(i) = queue.pop_front() {
+                for (target, dependency) in self.successors(i, edges) {
+                    if target == root {
+                        let mut fields = vec![dependency.field.clone()];
+                        let mut current = i;
+                        while current != root {
+                            let (before, dependency) = previous[&current];
+                            fields.push(dependency.field.clone());
+                            current = before;
+                        }
+                        fields.reverse();
+                        cycles.push(Cycle { fields });
+                        break 'search;
+                    }
+                    if component.contains(&target) && !previous.contains_key(&target) {
+                        previous.insert(target, (i, dependency));
+                        queue.push_back(target);
+                    }
+                }
+            }
+        }
+        cycles
+    }
+
+    /// Indices of the types in topological order
+    fn order(&self) -> Vec<usize> {
+        self.components(Edges::All).into_iter().flatten().collect()
+    }
+
+    fn successors(&self, i: usize, edges: Edges) -> impl Iterator<Item = (usize, &Dependency)> {
+        self.types[i]
+            .dependencies
+            .iter()
+            .map(|dependency| (self.indices[&dependency.target], dependency))
+            .filter(move |&(target, dependency)| match edges {
+                Edges::All => true,
+                Edges::Inline => !dependency.indirect,
+                Edges::Aliases => self.types[i].alias && self.types[target].alias,
+            })
+    }
+
+    /// Strongly connected components, dependencies first
+    fn components(&self, edges: Edges) -> Vec<Vec<usize>> {
+        let mut tarjan = Tarjan {
+            graph: self,
+            edges,
+            index: vec![None; self.types.len()],
+            low: vec![0; self.types.len()],
+            stack: Vec::new(),
+            on_stack: vec![false; self.types.len()],
+            next: 0,
+            components: Vec::new(),
+        };
+        for i in 0..self.types.len() {
+            if tarjan.index[i].is_none() {
+                tarjan.visit(i);
+            }
+        }
+        tarjan.components
+    }
+}
+
+struct Tarjan<'g> {
+    graph: &'g TypeGraph,
+    edges: Edges,
+    index: Vec<Option<usize>>,
+    low: Vec<usize>,
+    stack: Vec<usize>,
+    on_stack: Vec<bool>,
+    next: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    fn visit(&mut self, i: usize) {
+        self.index[i] = Some(self.next);
+        self.low[i] = self.next;
+        self.next += 1;
+        self.stack.push(i);
+        self.on_stack[i] = true;
+        let successors: Vec<usize> = self
+            .graph
+            .successors(i, self.edges)
+            .map(|(target, _)| target)
+            .collect();
+        for target in successors {
+            match self.index[target] {
+                None => {
+                    self.visit(target);
+                    self.low[i] = self.low[i].min(self.low[target]);
+                }
+                Some(index) if self.on_stack[target] => self.low[i] = self.low[i].min(index),
+                Some(_) => {}
+            }
+        }
+        if Some(self.low[i]) == self.index[i] {
+            let mut component = Vec::new();
+            while let Some(j) = self.stack.pop() {
+                self.on_stack[j] = false;
+                component.push(j);
+                if j == i {
+                    break;
+                }
+            }
+            component.sort_unstable();
+            self.components.push(component);
+        }
+    }
+}
+
+/// Fields that contain each other inline, the last field refers to the type of the first
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cycle {
+    fields: Vec<FieldRef>,
+}
+
+impl Cycle {
+    pub fn fields(&self) -> &[FieldRef] {
+        &self.fields
+    }
+}
+
+impl Display for Cycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for field in &self.fields {
+            write!(f, "{field} -> ")?;
+        }
+        f.write_str(&self.fields[0].r#type)
+    }
+}
+
+/// Types of infinite size, see [`Module::check_recursive_types`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecursionError {
+    cycles: Vec<Cycle>,
+}
+
+impl RecursionError {
+    pub fn cycles(&self) -> &[Cycle] {
+        &self.cycles
+    }
+}
+
+impl Display for RecursionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("infinitely sized recursive types: ")?;
+        for (i, cycle) in self.cycles.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{cycle}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RecursionError {}
+
+impl Module {
+    /// Reorders the struct, enum and type alias definitions so that each type comes after the
+    /// types it refers to, see [`TypeGraph::topological_order`]
+    ///
+    /// Other nodes keep their position. Submodules are sorted separately.
+    pub fn sort_types(&mut self) {
+        let order = TypeGraph::new(self).order();
+        let slots: Vec<usize> = (0..self.nodes.len())
+            .filter(|&i| is_type(&self.nodes[i]))
+            .collect();
+        let mut nodes: Vec<Option<ModuleNode>> = std::mem::take(&mut self.nodes)
+            .into_iter()
+            .map(Some)
+            .collect();
+        let sorted: Vec<ModuleNode> = order
+            .iter()
+            .filter_map(|&i| nodes[slots[i]].take())
+            .collect();
+        for (&slot, node) in slots.iter().zip(sorted) {
+            nodes[slot] = Some(node);
+        }
+        self.nodes = nodes.into_iter().flatten().collect();
+        for node in &mut self.nodes {
+            if let ModuleNode::Submodule(submodule) = node {
+                submodule.module_mut().sort_types();
+            }
+        }
+    }
+
+    /// Checks this module and all submodules for types that contain themselves inline
+    pub fn check_recursive_types(&self) -> Result<(), RecursionError> {
+        let mut cycles = Vec::new();
+        self.collect_cycles("", &mut cycles);
+        match cycles.is_empty() {
+            true => Ok(()),
+            false => Err(RecursionError { cycles }),
+        }
+    }
+
+    /// Wraps fields in `Box` until no type contains itself inline
+    ///
+    /// For each cycle the last field of a struct or enum in the cycle is boxed, usually the one
+    /// referring back to the first type, `Option<T>` becomes `Option<Box<T>>`. Returns the fields
+    /// that were changed.
+    ///
+    /// Cycles of type aliases cannot be fixed by boxing, if there are any an error is returned
+    /// and nothing is changed.
+    pub fn box_recursive_fields(&mut self) -> Result<Vec<FieldRef>, RecursionError> {
+        let mut cycles = Vec::new();
+        self.collect_alias_cycles("", &mut cycles);
+        if !cycles.is_empty() {
+            return Err(RecursionError { cycles });
+        }
+        let mut boxed = Vec::new();
+        self.box_fields("", &mut boxed);
+        Ok(boxed)
+    }
+
+    fn collect_cycles(&self, prefix: &str, cycles: &mut Vec<Cycle>) {
+        for mut cycle in TypeGraph::new(self).infinite_cycles() {
+            for field in &mut cycle.fields {
+                field.r#type.insert_str(0, prefix);
+            }
+            cycles.push(cycle);
+        }
+        for node in &self.nodes {
+            if let ModuleNode::Submodule(submodule) = node {
+                let prefix = format!("{prefix}{}::", submodule.name());
+                submodule.module().collect_cycles(&prefix, cycles);
+            }
+        }
+    }
+
+    fn collect_alias_cycles(&self, prefix: &str, cycles: &mut Vec<Cycle>) {
+        for mut cycle in TypeGraph::new(self).alias_cycles() {
+            for field in &mut cycle.fields {
+                field.r#type.insert_str(0, prefix);
+            }
+            cycles.push(cycle);
+        }
+        for node in &self.nodes {
+            if let ModuleNode::Submodule(submodule) = node {
+                let prefix = format!("{prefix}{}::", submodule.name());
+                submodule.module().collect_alias_cycles(&prefix, cycles);
+            }
+        }
+    }
+
+    fn box_fields(&mut self, prefix: &str, boxed: &mut Vec<FieldRef>) {
+        loop {
+            let graph = TypeGraph::new(self);
+            let fields: Vec<FieldRef> = graph
+                .infinite_cycles()
+                .iter()
+                .filter_map(|cycle| {
+                    let mut fields = cycle.fields.iter().rev();
+                    fields.find(|field| !graph.is_alias(field)).cloned()
+                })
+                .collect();
+            if fields.is_empty() {
+                break;
+            }
+            for mut field in fields {
+                self.box_field(&field);
+                field.r#type.insert_str(0, prefix);
+                boxed.push(field);
+            }
+        }
+        for node in &mut self.nodes {
+            if let ModuleNode::Submodule(submodule) = node {
+                let prefix = format!("{prefix}{}::", submodule.name());
+                submodule.module_mut().box_fields(&prefix, boxed);
+            }
+        }
+    }
+
+    fn box_field(&mut self, field: &FieldRef) {
+        let Some(node) = self
+            .nodes
+            .iter_mut()
+            .find(|node| is_type(node) && node.name() == Some(field.r#type.as_str()))
+        else {
+            return;
+        };
+        match node {
+            ModuleNode::Struct(struct_) => {
+                if let Some(f) = struct_.field_mut(&field.field) {
+                    let r#type = boxed(f.r#type());
+                    f.set_type(r#type);
+                }
+            }
+            ModuleNode::Enum(enum_) => {
+                let Some(variant) = field
+                    .variant
+                    .as_deref()
+                    .and_then(|variant| enum_.variant_mut(variant))
+                else {
+                    return;
+                };
+                match variant.fields_mut() {
+                    VariantFields::Unit => {}
+                    VariantFields::Unnamed(types) => {
+                        if let Some(r#type) = field
+                            .field
+                            .parse::<usize>()
+                            .ok()
+                            .and_then(|i| types.get_mut(i))
+                        {
+                            *r#type = boxed(r#type).into();
+                        }
+                    }
+                    VariantFields::Named(fields) => {
+                        if let Some(f) = fields.iter_mut().find(|f| f.name() == field.field) {
+                            let r#type = boxed(f.r#type());
+                            f.set_type(r#type);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn is_type(node: &ModuleNode) -> bool {
+    matches!(
+        node,
+        ModuleNode::Struct(_) | ModuleNode::Enum(_) | ModuleNode::TypeAlias(_)
+    )
+}
+
+fn boxed(r#type: &str) -> String {
+    match r#type
+        .strip_prefix("Option<")
+        .and_then(|inner| inner.strip_suffix('>'))
+    {
+        Some(inner) => format!("Option<Box<{inner}>>"),
+        None => format!("Box<{type}>"),
+    }
+}
+
+/// Returns the last path segment of each type named in `r#type` and whether it is indirect
+fn references(r#type: &str) -> Vec<(&str, bool)> {
+    let Ok((tokens, _)) = Lexer::new(r#type).tokenize() else {
+        return Vec::new();
+    };
+    let text = |i: usize| &r#type[tokens[i].start..tokens[i].end];
+    // For each nesting level: whether it is indirect, whether the current type is behind a
+    // reference or pointer, and whether the level is the parameter list of a function pointer
+    let mut levels = vec![(false, false, false)];
+    let mut references = Vec::new();
+    let mut previous = "";
+    for i in 0..tokens.len() {
+        let token = text(i);
+        let &(indirect, pointer, _) = levels.last().unwrap();
+        match tokens[i].kind {
+            TokenKind::Ident => match token {
+                "dyn" | "impl" | "fn" | "Fn" | "FnMut" | "FnOnce" => {
+                    levels.last_mut().unwrap().1 = true
+                }
+                "mut" | "const" | "as" => {}
+                _ if i + 1 < tokens.len() && text(i + 1) == "::" => {}
+                _ => references.push((token.trim_start_matches("r#"), indirect || pointer)),
+            },
+            TokenKind::Open => {
+                levels.push((indirect || pointer, false, previous == "fn"));
+            }
+            TokenKind::Close => {
+                if let Some((_, _, true)) = levels.pop() {
+                    // The return type of a function pointer
+                    levels.last_mut().unwrap().1 = true;
+                }
+            }
+            TokenKind::Punct => match token {
+                "&" | "&&" | "*" => levels.last_mut().unwrap().1 = true,
+                "<" => {
+                    let wrapper = INDIRECT.contains(&previous);
+                    levels.push((indirect || pointer || wrapper, false, false));
+                }
+                ">" => _ = levels.pop(),
+                ">>" => levels.truncate(levels.len().saturating_sub(2)),
+                "," | ";" => levels.last_mut().unwrap().1 = false,
+                _ => {}
+            },
+            _ => {}
+        }
+        if levels.is_empty() {
+            return references;
+        }
+        previous = token;
+    }
+    references
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recursive_aliases() {
+        let mut module = Module::new();
+        module.add_type_alias("L", "(L, u8)");
+        module.add_type_alias("M", "Box<N>");
+        module.add_type_alias("N", "Vec<M>");
+        let error = module.box_recursive_fields().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "infinitely sized recursive types: L -> L, M -> N -> M"
+        );
+        assert_eq!(module.check_recursive_types().unwrap_err(), error);
+        assert_eq!(
+            module.to_string(),
+            "type L = (L, u8);\ntype M = Box<N>;\ntype N = Vec<M>;\n"
+        );
+    }
+
+    #[test]
+    fn cycles_through_aliases() {
+        let mut module = Module::new();
+        module.add_struct("A").add_field("b", "B");
+        module.add_type_alias("B", "(A, u8)");
+        assert_eq!(
+            module.check_recursive_types().unwrap_err().to_string(),
+            "infinitely sized recursive types: A.b -> B -> A"
+        );
+        let boxed = module.box_recursive_fields().unwrap();
+        assert_eq!(boxed.len(), 1);
+        assert_eq!(boxed[0].to_string(), "A.b");
+        assert_eq!(
+            module.get_struct("A").unwrap().fields()[0].r#type(),
+            "Box<B>"
+        );
+        assert!(module.check_recursive_types().is_ok());
+    }
+}
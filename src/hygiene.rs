@@ -0,0 +1,236 @@
+//! Fresh names for generated bindings
+//!
+//! Temporaries in generated bodies must not collide with parameters, with bindings written by the
+//! user or with each other. [`Block::fresh`] returns `tmp`, `tmp_1`, `tmp_2` and so on, skipping
+//! every name that appears in the block. [`Function::fresh`] additionally avoids the parameters.
+//! A nested block, e.g. the `then` block of an `if`, does not know its enclosing scopes, use
+//! [`Block::fresh_in`] with the [`used_names`](Function::used_names) of the function for it.
+//!
+//! ```
+//! use codegen_rs::{expr, nodes::function::Function};
+//!
+//! let mut function = Function::new("scale".into());
+//! function.add_param("tmp", "u32");
+//! let first = function.fresh("tmp");
+//! let second = function.fresh("tmp");
+//! assert_eq!((&*first, &*second), ("tmp_1", "tmp_2"));
+//!
+//! let body = function.body_mut().unwrap();
+//! body.add_let(first.clone(), expr::binding("tmp").mul(expr::u32(2)));
+//! body.add_tail(expr::binding(first));
+//!
+//! let names = function.used_names();
+//! let body = function.body_mut().unwrap();
+//! let then = body.add_if(expr::binding("tmp").equals(expr::u32(0))).then_mut();
+//! assert_eq!(&*then.fresh_in("tmp", &names), "tmp_3");
+//! ```
+//!
+//! Names are collected conservatively: every identifier in raw code, `match` patterns and string
+//! arguments of macros counts as used, as do names bound in nested blocks.
+
+use std::collections::HashSet;
+
+use crate::{
+    expr::{Binding, Expr, Literal, MacroCall, MatchArm, StructInitializer},
+    naming,
+    nodes::{
+        function::Function,
+        raw::Raw,
+        statement::{Block, LetStatement},
+    },
+    parse::lexer::{Lexer, TokenKind},
+    visit::{self, Visit},
+    Str,
+};
+
+impl Block {
+    /// Returns a name based on `base` that is not used in this block
+    ///
+    /// The name is reserved, so later calls return different names even before it is bound.
+    /// Names visible from outer scopes can be excluded with [`reserve`](Self::reserve) or
+    /// [`fresh_in`](Self::fresh_in).
+    pub fn fresh(&mut self, base: &str) -> Str {
+        self.fresh_in(base, &[])
+    }
+
+    /// Returns a name based on `base` that is neither used in this block nor in `enclosing`
+    ///
+    /// `enclosing` are the names of the scopes around a nested block, usually
+    /// [`Function::used_names`].
+    pub fn fresh_in(&mut self, base: &str, enclosing: &[Str]) -> Str {
+        let mut names = Names::default();
+        names.visit_block(self);
+        for name in enclosing {
+            names.add(name);
+        }
+        let base = base.trim_start_matches("r#");
+        let name = (0..)
+            .map(|i| match i {
+                0 => naming::escape_ident(base),
+                i => naming::escape_ident(&format!("{base}_{i}")),
+            })
+            .find(|name| !names.contains(name))
+            .unwrap();
+        self.reserved.push(name.clone().into());
+        name.into()
+    }
+
+    /// Marks a name as used, e.g. a binding of an enclosing scope
+    pub fn reserve(&mut self, name: impl Into<Str>) -> &mut Self {
+        self.reserved.push(name.into());
+        self
+    }
+
+    /// Returns the names returned by [`fresh`](Self::fresh) and passed to
+    /// [`reserve`](Self::reserve)
+    pub fn reserved(&self) -> &[Str] {
+        &self.reserved
+    }
+}
+
+impl Function {
+    /// Returns a name based on `base` that is neither a parameter nor used in the body
+    ///
+    /// See [`Block::fresh`], an empty body is added if the function has none. Names bound in
+    /// nested blocks are avoided too, so the name can be used anywhere in the function.
+    pub fn fresh(&mut self, base: &str) -> Str {
+        let params: Vec<Str> = self
+            .params()
+            .iter()
+            .flat_map(|param| idents(param.name()))
+            .map(Str::from)
+            .collect();
+        self.add_body().fresh_in(base, &params)
+    }
+
+    /// Returns the parameters and every name used in the body, in nested blocks included
+    ///
+    /// These are the names a nested block has to avoid, see [`Block::fresh_in`].
+    pub fn used_names(&self) -> Vec<Str> {
+        let mut names = Names::default();
+        for param in self.params() {
+            for name in idents(param.name()) {
+                names.add(&name);
+            }
+        }
+        if let Some(body) = self.body() {
+            names.visit_block(body);
+        }
+        let mut names: Vec<Str> = names.0.into_iter().map(Str::from).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+/// Collects the names bound or referenced in a block
+#[derive(Default)]
+struct Names(HashSet<String>);
+
+impl Names {
+    fn add(&mut self, name: &str) {
+        self.0.insert(name.trim_start_matches("r#").to_owned());
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.0.contains(name.trim_start_matches("r#"))
+    }
+}
+
+impl Visit for Names {
+    fn visit_block(&mut self, node: &Block) {
+        for name in &node.reserved {
+            self.add(name);
+        }
+        visit::visit_block(self, node);
+    }
+
+    fn visit_let_statement(&mut self, node: &LetStatement) {
+        self.add(node.name());
+        visit::visit_let_statement(self, node);
+    }
+
+    fn visit_binding(&mut self, node: &Binding) {
+        if let Some(name) = node.name().split("::").next() {
+            self.add(name);
+        }
+    }
+
+    fn visit_struct_initializer(&mut self, node: &StructInitializer) {
+        // Fields without a value refer to a binding of the same name
+        for (name, _) in node.fields().iter().filter(|(_, value)| value.is_none()) {
+            self.add(name);
+        }
+        visit::visit_struct_initializer(self, node);
+    }
+
+    fn visit_match_arm(&mut self, node: &MatchArm) {
+        for name in idents(node.pattern()) {
+            self.add(&name);
+        }
+        visit::visit_match_arm(self, node);
+    }
+
+    fn visit_macro_call(&mut self, node: &MacroCall) {
+        // Format strings can capture bindings, e.g. `{id}`
+        for arg in node.args() {
+            if let Expr::Literal(literal) = arg {
+                if let Literal::Str(string) = &**literal {
+                    for name in idents(string) {
+                        self.add(&name);
+                    }
+                }
+            }
+        }
+        visit::visit_macro_call(self, node);
+    }
+
+    fn visit_raw(&mut self, node: &Raw) {
+        for name in idents(node.code()) {
+            self.add(&name);
+        }
+    }
+}
+
+/// Returns the identifiers in a piece of code
+fn idents(code: &str) -> Vec<String> {
+    let Ok((tokens, _)) = Lexer::new(code).tokenize() else {
+        return Vec::new();
+    };
+    tokens
+        .iter()
+        .filter(|token| token.kind == TokenKind::Ident)
+        .map(|token| code[token.start..token.end].to_owned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr;
+
+    #[test]
+    fn nested_blocks() {
+        let mut function = Function::new("f".into());
+        function.add_param("value", "u32");
+        function
+            .add_body()
+            .add_let("value_1", expr::u32(1))
+            .set_mut(true);
+        let names = function.used_names();
+        let body = function.body_mut().unwrap();
+        let then = body.add_if(expr::bool(true)).then_mut();
+        assert_eq!(&*then.fresh("value"), "value");
+        assert_eq!(&*then.fresh_in("value", &names), "value_2");
+        // The name reserved in the nested block is seen from the function
+        assert_eq!(&*function.fresh("value"), "value_3");
+    }
+
+    #[test]
+    fn add_body_keeps_reservations() {
+        let mut function = Function::new("f".into());
+        assert_eq!(&*function.fresh("tmp"), "tmp");
+        function.add_body().add_expr(expr::call("g"));
+        assert_eq!(function.body().unwrap().reserved(), ["tmp"]);
+        assert_eq!(&*function.fresh("tmp"), "tmp_1");
+    }
+}
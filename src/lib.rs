@@ -1,7 +1,21 @@
+pub mod build;
+pub mod check;
+pub mod derive;
+pub mod diff;
 pub mod expr;
+pub mod fs;
 pub mod generator;
+pub mod graph;
+pub mod hygiene;
+pub mod merge;
+pub mod naming;
 pub mod nodes;
+pub mod order;
+pub mod parse;
+pub mod quote;
+pub mod regions;
 pub mod visibility;
+pub mod visit;
 
 /// A copy-on-write immutable string slice
 pub type Str = std::borrow::Cow<'static, str>;
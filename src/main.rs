@@ -1,12 +1,64 @@
+use std::process::ExitCode;
+
 use codegen_rs::{
     expr,
     nodes::{
         statement::{ReturnStatement, Statement},
         Module,
     },
+    parse::parse_module,
 };
 
-fn main() {
+const USAGE: &str = "usage: codegen-rs [check <module.rs | -> <file> [--update]]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let update = args.iter().any(|arg| arg == "--update");
+    let args: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|&arg| arg != "--update")
+        .collect();
+    match args[..] {
+        [] if !update => {
+            let code = example().to_string();
+            println!("~~~\n{code}\n~~~");
+            ExitCode::SUCCESS
+        }
+        // Renders the module read from a file or stdin and compares it with `file`, or updates
+        // `file` with `--update`
+        ["check", source, file] => {
+            let result = read_module(source).and_then(|module| {
+                module
+                    .check_or_update(file, update)
+                    .map_err(|err| err.to_string())
+            });
+            match result {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(err) => {
+                    eprintln!("{err}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Parses the module at `path`, `-` reads it from stdin, e.g. the output of a generator
+fn read_module(path: &str) -> Result<Module, String> {
+    let source = match path {
+        "-" => std::io::read_to_string(std::io::stdin()),
+        path => std::fs::read_to_string(path),
+    }
+    .map_err(|err| format!("{path}: {err}"))?;
+    parse_module(&source).map_err(|err| format!("{path}: {err}"))
+}
+
+fn example() -> Module {
     let mut module = Module::new(); // Create a new module
 
     let s_vec2 = module.add_struct("Vec2").set_public(true); // Create a struct
@@ -31,6 +83,5 @@ fn main() {
                 .into(),
         )))); // Add a body to the function
 
-    let code = module.to_string();
-    println!("~~~\n{code}\n~~~");
+    module
 }
@@ -0,0 +1,360 @@
+//! Combining modules produced by separate generator passes
+
+use std::fmt::Display;
+
+use crate::nodes::{
+    r#impl::{Impl, ImplNode},
+    Module, ModuleNode,
+};
+
+/// Options for [`Module::merge_with`]
+#[derive(Clone, Debug, Default)]
+pub struct MergeOptions {
+    fold_impls: bool,
+}
+
+impl MergeOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Folds all inherent impl blocks of a type into the first one with the same generics,
+    /// attributes and where clause
+    pub fn set_fold_impls(&mut self, fold_impls: bool) -> &mut Self {
+        self.fold_impls = fold_impls;
+        self
+    }
+
+    pub fn fold_impls(&self) -> bool {
+        self.fold_impls
+    }
+}
+
+impl Module {
+    /// Merges `other` into this module, see [`Module::merge_with`]
+    pub fn merge(&mut self, other: Module) -> Result<(), MergeError> {
+        self.merge_with(other, &MergeOptions::new())
+    }
+
+    /// Merges `other` into this module
+    ///
+    /// Submodules with the same name are merged recursively, `use` declarations are added unless
+    /// they already exist and all other nodes are appended. Items that are equal to an existing
+    /// item are skipped. If an item has the same name as a different item, nothing is merged and
+    /// all conflicts are returned.
+    pub fn merge_with(&mut self, other: Module, options: &MergeOptions) -> Result<(), MergeError> {
+        let mut conflicts = Vec::new();
+        find_conflicts(self, &other, "", &mut conflicts);
+        if !conflicts.is_empty() {
+            return Err(MergeError { conflicts });
+        }
+        merge_nodes(self, other);
+        if options.fold_impls {
+            fold_impls(self);
+        }
+        Ok(())
+    }
+}
+
+/// Items with the same name that are different
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MergeError {
+    conflicts: Vec<String>,
+}
+
+impl MergeError {
+    /// The paths of the conflicting items, e.g. `shapes::Vec2` or `Vec2::new` for methods
+    pub fn conflicts(&self) -> &[String] {
+        &self.conflicts
+    }
+}
+
+impl Display for MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "conflicting items: {}", self.conflicts.join(", "))
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Namespace {
+    Type,
+    Value,
+    Macro,
+}
+
+fn namespace(node: &ModuleNode) -> Option<Namespace> {
+    Some(match node {
+        ModuleNode::Submodule(_)
+        | ModuleNode::Struct(_)
+        | ModuleNode::Enum(_)
+        | ModuleNode::Trait(_)
+        | ModuleNode::TypeAlias(_)
+        | ModuleNode::ExternCrate(_) => Namespace::Type,
+        ModuleNode::Const(_) | ModuleNode::Static(_) | ModuleNode::Function(_) => Namespace::Value,
+        ModuleNode::MacroRules(_) => Namespace::Macro,
+        _ => return None,
+    })
+}
+
+/// Returns the existing item that `node` would clash with
+fn find_named<'a>(module: &'a Module, node: &ModuleNode) -> Option<&'a ModuleNode> {
+    let namespace = namespace(node)?;
+    module
+        .nodes()
+        .iter()
+        .find(|other| self::namespace(other) == Some(namespace) && other.name() == node.name())
+}
+
+fn find_conflicts(module: &Module, other: &Module, prefix: &str, conflicts: &mut Vec<String>) {
+    for node in other.nodes() {
+        match node {
+            ModuleNode::Impl(r#impl) => {
+                let mut existing = module.impls().filter(|existing| {
+                    existing.target() == r#impl.target() && existing.r#trait() == r#impl.r#trait()
+                });
+                match r#impl.r#trait() {
+                    Some(r#trait) => {
+                        if existing.any(|existing| existing != r#impl) {
+                            conflicts.push(format!("{prefix}<{} as {trait}>", r#impl.target()));
+                        }
+                    }
+                    None => {
+                        let existing: Vec<_> = existing.collect();
+                        if existing.contains(&r#impl) {
+                            continue;
+                        }
+                        for name in r#impl.nodes().iter().filter_map(ImplNode::name) {
+                            if existing.iter().any(|existing| {
+                                existing
+                                    .nodes()
+                                    .iter()
+                                    .any(|node| node.name() == Some(name))
+                            }) {
+                                conflicts.push(format!("{prefix}{}::{name}", r#impl.target()));
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {
+                let Some(existing) = find_named(module, node) else {
+                    continue;
+                };
+                match (existing, node) {
+                    (ModuleNode::Submodule(existing), ModuleNode::Submodule(submodule)) => {
+                        find_conflicts(
+                            existing.module(),
+                            submodule.module(),
+                            &format!("{prefix}{}::", submodule.name()),
+                            conflicts,
+                        );
+                    }
+                    _ if existing == node => {}
+                    _ => conflicts.push(format!("{prefix}{}", node.name().unwrap_or_default())),
+                }
+            }
+        }
+    }
+}
+
+/// Merges the nodes, conflicts must have been checked before
+fn merge_nodes(module: &mut Module, other: Module) {
+    for node in other.into_nodes() {
+        match node {
+            ModuleNode::Use(_) => {
+                if module.nodes().contains(&node) {
+                    continue;
+                }
+                // Keeps the declarations together, after any leading inner attributes
+                let index = match module
+                    .nodes()
+                    .iter()
+                    .rposition(|node| matches!(node, ModuleNode::Use(_)))
+                {
+                    Some(last) => last + 1,
+                    None => module
+                        .nodes()
+                        .iter()
+                        .position(|node| !matches!(node, ModuleNode::Raw(_)))
+                        .unwrap_or(module.nodes().len()),
+                };
+                module.insert_at(index, node);
+            }
+            ModuleNode::Submodule(submodule) => match module.get_submodule_mut(submodule.name()) {
+                Some(existing) => merge_nodes(existing.module_mut(), submodule.module),
+                None => _ = module.add_node(ModuleNode::Submodule(submodule)),
+            },
+            ModuleNode::Impl(ref r#impl) => {
+                if !module.impls().any(|existing| existing == r#impl) {
+                    module.add_node(node);
+                }
+            }
+            _ if find_named(module, &node).is_some() => {}
+            _ => _ = module.add_node(node),
+        }
+    }
+}
+
+/// Moves the nodes of all inherent impls into the first impl of their target with the same
+/// generics, attributes and where clause
+fn fold_impls(module: &mut Module) {
+    let mut i = 0;
+    while i < module.nodes().len() {
+        match &mut module.nodes_mut()[i] {
+            ModuleNode::Submodule(submodule) => fold_impls(submodule.module_mut()),
+            ModuleNode::Impl(r#impl) if r#impl.r#trait().is_none() => {
+                let mut nodes: Vec<ImplNode> = Vec::new();
+                let mut j = i + 1;
+                while j < module.nodes().len() {
+                    match (&module.nodes()[i], &module.nodes()[j]) {
+                        (ModuleNode::Impl(r#impl), ModuleNode::Impl(other))
+                            if same_header(r#impl, other) =>
+                        {
+                            let ModuleNode::Impl(other) = module.remove(j) else {
+                                unreachable!();
+                            };
+                            nodes.extend(other.nodes);
+                        }
+                        _ => j += 1,
+                    }
+                }
+                let ModuleNode::Impl(r#impl) = &mut module.nodes_mut()[i] else {
+                    unreachable!();
+                };
+                for node in nodes {
+                    r#impl.add_node(node);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+/// Whether two impls only differ in their nodes
+fn same_header(r#impl: &Impl, other: &Impl) -> bool {
+    r#impl.r#trait() == other.r#trait()
+        && r#impl.target() == other.target()
+        && r#impl.generics() == other.generics()
+        && r#impl.attributes() == other.attributes()
+        && r#impl.where_predicates() == other.where_predicates()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(code: &str) -> Module {
+        code.parse().unwrap()
+    }
+
+    #[test]
+    fn submodules() {
+        let mut module = Module::new();
+        let shapes = module.add_submodule("shapes");
+        shapes.add_struct("A");
+        shapes.add_submodule("round").add_struct("Circle");
+
+        let mut other = Module::new();
+        let shapes = other.add_submodule("shapes");
+        shapes.add_struct("A");
+        shapes.add_struct("B");
+        shapes.add_submodule("round").add_struct("Ellipse");
+        module.merge(other).unwrap();
+        assert_eq!(
+            module.to_string(),
+            concat!(
+                "mod shapes {\n    struct A {\n    }\n\n",
+                "    mod round {\n        struct Circle {\n        }\n\n",
+                "        struct Ellipse {\n        }\n    }\n\n",
+                "    struct B {\n    }\n}\n",
+            )
+        );
+
+        let mut other = Module::new();
+        let round = other.add_submodule("shapes").add_submodule("round");
+        round.add_struct("Circle").add_field("r", "f32");
+        let error = module.merge(other).unwrap_err();
+        assert_eq!(error.conflicts(), ["shapes::round::Circle"]);
+    }
+
+    #[test]
+    fn uses() {
+        let mut module = Module::new();
+        module.add_raw("#![allow(dead_code)]");
+        module.add_struct("A");
+        module
+            .merge(parse("use std::fmt;\nuse std::io;\n"))
+            .unwrap();
+        module
+            .merge(parse("use std::io;\nuse std::rc::Rc;\n"))
+            .unwrap();
+        assert_eq!(
+            module.to_string(),
+            "#![allow(dead_code)]\n\nuse std::fmt;\nuse std::io;\nuse std::rc::Rc;\n\nstruct A {\n}\n"
+        );
+    }
+
+    #[test]
+    fn trait_impls() {
+        let code = "impl Default for A {\n    fn default() -> Self {\n        Self\n    }\n}\n";
+        let mut module = parse(code);
+        module.merge(parse(code)).unwrap();
+        assert_eq!(module.to_string(), code);
+
+        let error = module
+            .merge(parse(
+                "impl Default for A {\n    fn default() -> Self {\n        A\n    }\n}\n",
+            ))
+            .unwrap_err();
+        assert_eq!(error.conflicts(), ["<A as Default>"]);
+        assert_eq!(module.to_string(), code);
+    }
+
+    #[test]
+    fn inherent_methods() {
+        let code = "impl A {\n    fn new() -> Self {\n        Self\n    }\n}\n";
+        let mut module = parse(code);
+        let error = module
+            .merge(parse("impl A {\n    fn new() -> A {\n        A\n    }\n\n    fn get(&self) {\n    }\n}\n"))
+            .unwrap_err();
+        assert_eq!(error.conflicts(), ["A::new"]);
+        assert_eq!(error.to_string(), "conflicting items: A::new");
+        assert_eq!(module.to_string(), code);
+
+        module
+            .merge(parse("impl A {\n    fn get(&self) {\n    }\n}\n"))
+            .unwrap();
+        assert_eq!(module.impls().count(), 2);
+    }
+
+    #[test]
+    fn fold_impls() {
+        let mut module = parse("impl A {\n    fn a() {\n    }\n}\n");
+        module
+            .merge_with(
+                parse(concat!(
+                    "impl Clone for A {\n}\n\n",
+                    "impl A {\n    fn b() {\n    }\n}\n\n",
+                    "#[cfg(test)]\nimpl A {\n    fn c() {\n    }\n}\n\n",
+                    "impl<T> A<T>\nwhere\n    T: Copy,\n{\n    fn d() {\n    }\n}\n\n",
+                    "impl<T> A<T> {\n    fn e() {\n    }\n}\n\n",
+                    "impl<T> A<T> {\n    fn f() {\n    }\n}\n",
+                )),
+                MergeOptions::new().set_fold_impls(true),
+            )
+            .unwrap();
+        assert_eq!(
+            module.to_string(),
+            concat!(
+                "impl A {\n    fn a() {\n    }\n\n    fn b() {\n    }\n}\n\n",
+                "impl Clone for A {\n}\n\n",
+                "#[cfg(test)]\nimpl A {\n    fn c() {\n    }\n}\n\n",
+                "impl<T> A<T>\nwhere\n    T: Copy,\n{\n    fn d() {\n    }\n}\n\n",
+                "impl<T> A<T> {\n    fn e() {\n    }\n\n    fn f() {\n    }\n}\n",
+            )
+        );
+    }
+}
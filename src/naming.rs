@@ -0,0 +1,205 @@
+//! Case conversions and escaping for generated identifiers
+//!
+//! Names are split into words at non-alphanumeric characters and case changes, so `user_id`,
+//! `UserID` and `user-id` all consist of the words `user` and `id`. A run of uppercase letters is
+//! a single word, except that its last letter starts the next word when a lowercase letter
+//! follows, e.g. `HTTPServer` is `HTTP` and `Server`. Digits belong to the word before them.
+//!
+//! The `*_ident` functions convert and then [escape](escape_ident) the result, so it is always a
+//! valid identifier.
+//!
+//! ```
+//! use codegen_rs::naming;
+//!
+//! assert_eq!(naming::to_snake_case("UserID"), "user_id");
+//! assert_eq!(naming::to_pascal_case("user-id"), "UserId");
+//! assert_eq!(naming::to_screaming_snake_case("userId"), "USER_ID");
+//! assert_eq!(naming::snake_ident("Type"), "r#type");
+//! assert_eq!(naming::pascal_ident("2d point"), "_2dPoint");
+//! ```
+
+/// Strict and reserved keywords of the 2021 and 2024 editions
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Keywords that cannot be raw identifiers
+const NOT_RAW: &[&str] = &["crate", "self", "Self", "super"];
+
+/// Splits a name into words, see the [module docs](self)
+pub fn words(name: &str) -> Vec<&str> {
+    let name = name.strip_prefix("r#").unwrap_or(name);
+    let mut words = Vec::new();
+    for part in name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+    {
+        let chars: Vec<(usize, char)> = part.char_indices().collect();
+        let mut start = 0;
+        for i in 1..chars.len() {
+            let (index, c) = chars[i];
+            let previous = chars[i - 1].1;
+            let next = chars.get(i + 1).map(|&(_, c)| c);
+            let boundary = c.is_uppercase()
+                && (previous.is_lowercase()
+                    || previous.is_numeric()
+                    || (previous.is_uppercase() && next.is_some_and(char::is_lowercase)));
+            if boundary {
+                words.push(&part[start..index]);
+                start = index;
+            }
+        }
+        words.push(&part[start..]);
+    }
+    words
+}
+
+/// `user_id`
+pub fn to_snake_case(name: &str) -> String {
+    words(name).join("_").to_lowercase()
+}
+
+/// `USER_ID`
+pub fn to_screaming_snake_case(name: &str) -> String {
+    words(name).join("_").to_uppercase()
+}
+
+/// `user-id`
+pub fn to_kebab_case(name: &str) -> String {
+    words(name).join("-").to_lowercase()
+}
+
+/// `userId`
+pub fn to_camel_case(name: &str) -> String {
+    let words = words(name);
+    let Some((first, rest)) = words.split_first() else {
+        return String::new();
+    };
+    let mut camel = first.to_lowercase();
+    for word in rest {
+        camel.push_str(&capitalize(word));
+    }
+    camel
+}
+
+/// `UserId`
+pub fn to_pascal_case(name: &str) -> String {
+    words(name).into_iter().map(capitalize).collect()
+}
+
+/// Converts to snake case and escapes the result, for functions, fields and modules
+pub fn snake_ident(name: &str) -> String {
+    escape_ident(&to_snake_case(name))
+}
+
+/// Converts to screaming snake case and escapes the result, for consts and statics
+pub fn screaming_snake_ident(name: &str) -> String {
+    escape_ident(&to_screaming_snake_case(name))
+}
+
+/// Converts to camel case and escapes the result
+pub fn camel_ident(name: &str) -> String {
+    escape_ident(&to_camel_case(name))
+}
+
+/// Converts to pascal case and escapes the result, for types, traits and variants
+pub fn pascal_ident(name: &str) -> String {
+    escape_ident(&to_pascal_case(name))
+}
+
+/// Returns whether `name` is a strict or reserved keyword
+pub fn is_keyword(name: &str) -> bool {
+    KEYWORDS.contains(&name)
+}
+
+/// Makes `name` a valid identifier
+///
+/// Invalid characters are replaced with `_`, a leading digit is prefixed with `_` and keywords
+/// become raw identifiers like `r#type`. Keywords that cannot be raw, such as `self`, get a
+/// trailing `_` instead. Raw identifiers are escaped again, so `r#type` stays the same. An empty
+/// name and `_`, which is not an identifier, become `__`.
+pub fn escape_ident(name: &str) -> String {
+    let name = name.strip_prefix("r#").unwrap_or(name);
+    let mut ident: String = name
+        .chars()
+        .map(|c| match c.is_alphanumeric() || c == '_' {
+            true => c,
+            false => '_',
+        })
+        .collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_numeric()) {
+        ident.insert(0, '_');
+    }
+    if ident == "_" {
+        ident.push('_');
+    }
+    match is_keyword(&ident) {
+        true if NOT_RAW.contains(&ident.as_str()) => ident + "_",
+        true => format!("r#{ident}"),
+        false => ident,
+    }
+}
+
+/// Uppercases the first character and lowercases the rest
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn acronyms_and_digits() {
+        assert_eq!(words("HTTPServer"), ["HTTP", "Server"]);
+        assert_eq!(words("parseURL"), ["parse", "URL"]);
+        assert_eq!(words("IOError2"), ["IO", "Error2"]);
+        assert_eq!(words("vec2D"), ["vec2", "D"]);
+        assert_eq!(words("r#type_id"), ["type", "id"]);
+        assert_eq!(to_snake_case("HTTPServer"), "http_server");
+        assert_eq!(to_camel_case("HTTP_SERVER"), "httpServer");
+        assert_eq!(to_pascal_case("utf8 string"), "Utf8String");
+        assert_eq!(to_kebab_case("Sha256Hash"), "sha256-hash");
+        assert_eq!(to_camel_case(""), "");
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape_ident("2d"), "_2d");
+        assert_eq!(escape_ident("a-b.c"), "a_b_c");
+        assert_eq!(escape_ident("match"), "r#match");
+        assert_eq!(escape_ident("gen"), "r#gen");
+        assert_eq!(escape_ident(""), "__");
+        assert_eq!(escape_ident("_"), "__");
+        assert_eq!(escape_ident("-"), "__");
+        assert_eq!(escape_ident("__"), "__");
+        for keyword in NOT_RAW {
+            assert_eq!(escape_ident(keyword), format!("{keyword}_"));
+            assert_eq!(escape_ident(&format!("r#{keyword}")), format!("{keyword}_"));
+        }
+        assert_eq!(snake_ident("Self"), "self_");
+        assert_eq!(pascal_ident("self"), "Self_");
+    }
+
+    #[test]
+    fn raw_round_trip() {
+        for name in ["type", "r#type", "async", "r#async"] {
+            let ident = escape_ident(name);
+            assert!(ident.starts_with("r#"), "{ident}");
+            assert_eq!(escape_ident(&ident), ident);
+        }
+        assert_eq!(snake_ident("r#Type"), "r#type");
+        assert_eq!(pascal_ident("r#type"), "Type");
+    }
+}
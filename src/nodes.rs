@@ -1,3 +1,24 @@
+/// Generates lookups of the first node of a kind with a given name
+macro_rules! get {
+    ($($variant: ident, $type: ty, $get: ident, $get_mut: ident;)*) => {
+        $(
+            pub fn $get(&self, name: &str) -> Option<&$type> {
+                self.nodes.iter().find_map(|node| match node {
+                    ModuleNode::$variant(it) if it.name() == name => Some(it),
+                    _ => None,
+                })
+            }
+
+            pub fn $get_mut(&mut self, name: &str) -> Option<&mut $type> {
+                self.nodes.iter_mut().find_map(|node| match node {
+                    ModuleNode::$variant(it) if it.name() == name => Some(it),
+                    _ => None,
+                })
+            }
+        )*
+    };
+}
+
 macro_rules! push_mut {
     ($vec: expr, $value: expr) => {{
         $vec.push($value);
@@ -13,24 +34,45 @@ macro_rules! push_mut {
 
 pub mod r#const;
 pub mod r#enum;
+pub mod extern_block;
+pub mod extern_crate;
 pub mod function;
 pub mod r#impl;
+pub mod macro_rules;
+pub mod raw;
 pub mod statement;
 pub mod r#static;
 pub mod r#struct;
+pub mod thread_local;
 pub mod r#trait;
+pub mod r#type;
+pub mod r#use;
+pub mod user_region;
 
-use std::fmt::{Display, Write};
+use std::{
+    cmp::Ordering,
+    fmt::{Display, Write},
+    io,
+    path::Path,
+};
 
-use crate::{expr::Expr, generator::GenerateCode, Str};
+use crate::{
+    expr::Expr,
+    fs::{write_if_changed, DirOptions, ModuleStyle},
+    generator::{Formatter, GenerateCode},
+    Str,
+};
 
 use self::{
-    function::Function, r#const::Const, r#enum::Enum, r#impl::Impl, r#static::Static,
-    r#struct::Struct, r#trait::Trait,
+    extern_block::ExternBlock, extern_crate::ExternCrate, function::Function,
+    macro_rules::MacroRules, r#const::Const, r#enum::Enum, r#impl::Impl, r#static::Static,
+    r#struct::Struct, r#trait::Trait, r#type::TypeAlias, r#use::Use, raw::Raw,
+    thread_local::ThreadLocal,
 };
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Module {
-    nodes: Vec<ModuleNode>,
+    pub(crate) nodes: Vec<ModuleNode>,
 }
 
 impl Module {
@@ -38,10 +80,18 @@ impl Module {
         Self { nodes: Vec::new() }
     }
 
+    pub(crate) fn into_nodes(self) -> Vec<ModuleNode> {
+        self.nodes
+    }
+
     pub fn add_submodule(&mut self, name: impl Into<Str>) -> &mut Submodule {
         push_mut!(@[ModuleNode::Submodule] self.nodes, Submodule::new(name.into()))
     }
 
+    pub fn add_use(&mut self, path: impl Into<Str>) -> &mut Use {
+        push_mut!(@[ModuleNode::Use] self.nodes, Use::new(path.into()))
+    }
+
     pub fn add_const(
         &mut self,
         name: impl Into<Str>,
@@ -79,6 +129,113 @@ impl Module {
     pub fn add_function(&mut self, name: impl Into<Str>) -> &mut Function {
         push_mut!(@[ModuleNode::Function] self.nodes, Function::new(name.into()))
     }
+
+    pub fn add_type_alias(
+        &mut self,
+        name: impl Into<Str>,
+        r#type: impl Into<Str>,
+    ) -> &mut TypeAlias {
+        push_mut!(@[ModuleNode::TypeAlias] self.nodes, TypeAlias::new(name.into(), r#type.into()))
+    }
+
+    pub fn add_extern_crate(&mut self, name: impl Into<Str>) -> &mut ExternCrate {
+        push_mut!(@[ModuleNode::ExternCrate] self.nodes, ExternCrate::new(name.into()))
+    }
+
+    /// Adds an `extern "abi" { ... }` block
+    pub fn add_extern_block(&mut self, abi: impl Into<Str>) -> &mut ExternBlock {
+        push_mut!(@[ModuleNode::ExternBlock] self.nodes, ExternBlock::new(Some(abi.into())))
+    }
+
+    /// Adds an `extern { ... }` block without an ABI, which defaults to `"C"`
+    pub fn add_extern_block_without_abi(&mut self) -> &mut ExternBlock {
+        push_mut!(@[ModuleNode::ExternBlock] self.nodes, ExternBlock::new(None))
+    }
+
+    pub fn add_macro_rules(&mut self, name: impl Into<Str>) -> &mut MacroRules {
+        push_mut!(@[ModuleNode::MacroRules] self.nodes, MacroRules::new(name.into()))
+    }
+
+    pub fn add_thread_local(&mut self) -> &mut ThreadLocal {
+        push_mut!(@[ModuleNode::ThreadLocal] self.nodes, ThreadLocal::new())
+    }
+
+    /// Adds verbatim code, see [`Raw`]
+    pub fn add_raw(&mut self, code: impl Into<Str>) -> &mut Raw {
+        push_mut!(@[ModuleNode::Raw] self.nodes, Raw::new(code.into()))
+    }
+
+    /// Adds an already constructed node
+    pub fn add_node(&mut self, node: ModuleNode) -> &mut ModuleNode {
+        push_mut!(self.nodes, node)
+    }
+}
+
+impl Module {
+    pub fn nodes(&self) -> &[ModuleNode] {
+        &self.nodes
+    }
+
+    pub fn nodes_mut(&mut self) -> &mut [ModuleNode] {
+        &mut self.nodes
+    }
+
+    /// Returns the index of the first node called `name`, see [`ModuleNode::name`]
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().position(|node| node.name() == Some(name))
+    }
+
+    /// Inserts a node at `index`, shifting all nodes after it
+    pub fn insert_at(&mut self, index: usize, node: ModuleNode) -> &mut ModuleNode {
+        self.nodes.insert(index, node);
+        &mut self.nodes[index]
+    }
+
+    pub fn remove(&mut self, index: usize) -> ModuleNode {
+        self.nodes.remove(index)
+    }
+
+    pub fn sort_by(&mut self, compare: impl FnMut(&ModuleNode, &ModuleNode) -> Ordering) {
+        self.nodes.sort_by(compare);
+    }
+
+    pub fn retain(&mut self, keep: impl FnMut(&ModuleNode) -> bool) {
+        self.nodes.retain(keep);
+    }
+
+    get! {
+        Submodule, Submodule, get_submodule, get_submodule_mut;
+        Const, Const, get_const, get_const_mut;
+        Static, Static, get_static, get_static_mut;
+        Struct, Struct, get_struct, get_struct_mut;
+        Enum, Enum, get_enum, get_enum_mut;
+        Trait, Trait, get_trait, get_trait_mut;
+        Function, Function, get_function, get_function_mut;
+        TypeAlias, TypeAlias, get_type_alias, get_type_alias_mut;
+        MacroRules, MacroRules, get_macro_rules, get_macro_rules_mut;
+    }
+
+    pub fn impls(&self) -> impl Iterator<Item = &Impl> {
+        self.nodes.iter().filter_map(|node| match node {
+            ModuleNode::Impl(impl_) => Some(impl_),
+            _ => None,
+        })
+    }
+
+    /// Returns the first impl block for `target`, either inherent or of the trait `r#trait`
+    pub fn get_impl(&self, target: &str, r#trait: Option<&str>) -> Option<&Impl> {
+        self.impls()
+            .find(|impl_| impl_.target() == target && impl_.r#trait() == r#trait)
+    }
+
+    pub fn get_impl_mut(&mut self, target: &str, r#trait: Option<&str>) -> Option<&mut Impl> {
+        self.nodes.iter_mut().find_map(|node| match node {
+            ModuleNode::Impl(impl_) if impl_.target() == target && impl_.r#trait() == r#trait => {
+                Some(impl_)
+            }
+            _ => None,
+        })
+    }
 }
 
 impl Default for Module {
@@ -87,25 +244,93 @@ impl Default for Module {
     }
 }
 
-impl GenerateCode for Module {
-    fn generate(&self, formatter: &mut crate::generator::Formatter) {
+impl Module {
+    /// Writes the module as a directory tree with one file per submodule
+    ///
+    /// The module itself is written to `lib.rs` and submodules use the `x.rs` + `x/` layout,
+    /// see [`Module::write_to_dir_with`] for other layouts.
+    pub fn write_to_dir(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_to_dir_with(path, &DirOptions::new())
+    }
+
+    /// Writes the module as a directory tree with one file per submodule
+    ///
+    /// Files whose contents have not changed are not rewritten.
+    pub fn write_to_dir_with(
+        &self,
+        path: impl AsRef<Path>,
+        options: &DirOptions,
+    ) -> io::Result<()> {
+        let path = path.as_ref();
+        std::fs::create_dir_all(path)?;
+        self.write_files(&path.join(options.root_file()), path, options.style())
+    }
+
+    fn write_files(&self, file: &Path, dir: &Path, style: ModuleStyle) -> io::Result<()> {
+        let mut code = String::new();
+        self.generate_nodes(&mut Formatter::new(&mut code), true);
+        write_if_changed(file, &code)?;
+        for node in &self.nodes {
+            let ModuleNode::Submodule(submodule) = node else {
+                continue;
+            };
+            let name = submodule.name.trim_start_matches("r#");
+            let child_dir = dir.join(name);
+            let child_file = match style {
+                ModuleStyle::File => dir.join(format!("{name}.rs")),
+                ModuleStyle::ModRs => child_dir.join("mod.rs"),
+            };
+            let has_children = submodule
+                .module
+                .nodes
+                .iter()
+                .any(|node| matches!(node, ModuleNode::Submodule(_)));
+            if style == ModuleStyle::ModRs || has_children {
+                std::fs::create_dir_all(&child_dir)?;
+            }
+            submodule
+                .module
+                .write_files(&child_file, &child_dir, style)?;
+        }
+        Ok(())
+    }
+
+    /// Generates all nodes, submodules are declared as `mod x;` if `outline` is set
+    fn generate_nodes(&self, fmt: &mut Formatter, outline: bool) {
         let len = self.nodes.len();
         for (i, node) in self.nodes.iter().enumerate() {
-            node.generate(formatter);
+            match node {
+                ModuleNode::Submodule(submodule) if outline => submodule.generate_declaration(fmt),
+                _ => node.generate(fmt),
+            }
             let j = i + 1;
             if j < len
                 && !matches!(
                     (node, &self.nodes[j]),
-                    (ModuleNode::Const(_), ModuleNode::Const(_))
+                    (ModuleNode::Use(_), ModuleNode::Use(_))
+                        | (ModuleNode::Const(_), ModuleNode::Const(_))
                         | (ModuleNode::Static(_), ModuleNode::Static(_))
+                        | (ModuleNode::TypeAlias(_), ModuleNode::TypeAlias(_))
+                        | (ModuleNode::ExternCrate(_), ModuleNode::ExternCrate(_))
                 )
+                && !(outline
+                    && matches!(
+                        (node, &self.nodes[j]),
+                        (ModuleNode::Submodule(_), ModuleNode::Submodule(_))
+                    ))
             {
-                _ = writeln!(formatter);
+                _ = writeln!(fmt);
             }
         }
     }
 }
 
+impl GenerateCode for Module {
+    fn generate(&self, formatter: &mut crate::generator::Formatter) {
+        self.generate_nodes(formatter, false);
+    }
+}
+
 impl Display for Module {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(&self.to_code_string())
@@ -113,7 +338,9 @@ impl Display for Module {
 }
 
 #[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ModuleNode {
+    Use(Use),
     Submodule(Submodule),
     Const(Const),
     Static(Static),
@@ -122,11 +349,42 @@ pub enum ModuleNode {
     Trait(Trait),
     Impl(Impl),
     Function(Function),
+    TypeAlias(TypeAlias),
+    ExternCrate(ExternCrate),
+    ExternBlock(ExternBlock),
+    MacroRules(MacroRules),
+    ThreadLocal(ThreadLocal),
+    Raw(Raw),
+}
+
+impl ModuleNode {
+    /// Returns the name of the item, `use` declarations, impls, extern blocks, `thread_local!` and
+    /// raw code have none
+    pub fn name(&self) -> Option<&str> {
+        Some(match self {
+            ModuleNode::Submodule(submodule) => submodule.name(),
+            ModuleNode::Const(const_) => const_.name(),
+            ModuleNode::Static(static_) => static_.name(),
+            ModuleNode::Struct(struct_) => struct_.name(),
+            ModuleNode::Enum(enum_) => enum_.name(),
+            ModuleNode::Trait(trait_) => trait_.name(),
+            ModuleNode::Function(function) => function.name(),
+            ModuleNode::TypeAlias(alias) => alias.name(),
+            ModuleNode::ExternCrate(extern_crate) => extern_crate.name(),
+            ModuleNode::MacroRules(macro_rules) => macro_rules.name(),
+            ModuleNode::Use(_)
+            | ModuleNode::Impl(_)
+            | ModuleNode::ExternBlock(_)
+            | ModuleNode::ThreadLocal(_)
+            | ModuleNode::Raw(_) => return None,
+        })
+    }
 }
 
 impl GenerateCode for ModuleNode {
     fn generate(&self, fmt: &mut crate::generator::Formatter) {
         match self {
+            ModuleNode::Use(r#use) => r#use.generate(fmt),
             ModuleNode::Submodule(submodule) => submodule.generate(fmt),
             ModuleNode::Const(const_) => const_.generate(fmt),
             ModuleNode::Static(static_) => static_.generate(fmt),
@@ -135,14 +393,21 @@ impl GenerateCode for ModuleNode {
             ModuleNode::Trait(trait_) => trait_.generate(fmt),
             ModuleNode::Impl(impl_) => impl_.generate(fmt),
             ModuleNode::Function(function) => function.generate(fmt),
+            ModuleNode::TypeAlias(alias) => alias.generate(fmt),
+            ModuleNode::ExternCrate(extern_crate) => extern_crate.generate(fmt),
+            ModuleNode::ExternBlock(extern_block) => extern_block.generate(fmt),
+            ModuleNode::MacroRules(macro_rules) => macro_rules.generate(fmt),
+            ModuleNode::ThreadLocal(thread_local) => thread_local.generate(fmt),
+            ModuleNode::Raw(raw) => raw.generate(fmt),
         }
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Submodule {
-    public: bool,
-    name: Str,
-    module: Module,
+    pub(crate) public: bool,
+    pub(crate) name: Str,
+    pub(crate) module: Module,
 }
 
 impl Submodule {
@@ -159,10 +424,18 @@ impl Submodule {
         self
     }
 
+    pub fn module_mut(&mut self) -> &mut Module {
+        &mut self.module
+    }
+
     pub fn add_submodule(&mut self, name: impl Into<Str>) -> &mut Submodule {
         self.module.add_submodule(name)
     }
 
+    pub fn add_use(&mut self, path: impl Into<Str>) -> &mut Use {
+        self.module.add_use(path)
+    }
+
     pub fn add_const(
         &mut self,
         name: impl Into<Str>,
@@ -200,6 +473,65 @@ impl Submodule {
     pub fn add_function(&mut self, name: impl Into<Str>) -> &mut Function {
         self.module.add_function(name)
     }
+
+    pub fn add_type_alias(
+        &mut self,
+        name: impl Into<Str>,
+        r#type: impl Into<Str>,
+    ) -> &mut TypeAlias {
+        self.module.add_type_alias(name, r#type)
+    }
+
+    pub fn add_extern_crate(&mut self, name: impl Into<Str>) -> &mut ExternCrate {
+        self.module.add_extern_crate(name)
+    }
+
+    pub fn add_extern_block(&mut self, abi: impl Into<Str>) -> &mut ExternBlock {
+        self.module.add_extern_block(abi)
+    }
+
+    pub fn add_extern_block_without_abi(&mut self) -> &mut ExternBlock {
+        self.module.add_extern_block_without_abi()
+    }
+
+    pub fn add_macro_rules(&mut self, name: impl Into<Str>) -> &mut MacroRules {
+        self.module.add_macro_rules(name)
+    }
+
+    pub fn add_thread_local(&mut self) -> &mut ThreadLocal {
+        self.module.add_thread_local()
+    }
+
+    pub fn add_raw(&mut self, code: impl Into<Str>) -> &mut Raw {
+        self.module.add_raw(code)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: impl Into<Str>) -> &mut Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.public
+    }
+
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+}
+
+impl Submodule {
+    /// Generates `mod name;` for a submodule that lives in its own file
+    fn generate_declaration(&self, fmt: &mut Formatter) {
+        if self.public {
+            _ = write!(fmt, "pub ");
+        }
+        _ = writeln!(fmt, "mod {};", self.name);
+    }
 }
 
 impl GenerateCode for Submodule {
@@ -213,3 +545,155 @@ impl GenerateCode for Submodule {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("codegen-rs-nodes-{}-{name}", std::process::id()));
+        _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// `lib.rs` with the submodules `a`, containing `b`, and `r#type`
+    fn tree() -> Module {
+        let mut module = Module::new();
+        let a = module.add_submodule("a");
+        a.set_public(true);
+        a.add_struct("A");
+        a.add_submodule("b").add_function("b").add_body();
+        module.add_submodule("r#type").add_struct("T");
+        module.add_function("root").add_body();
+        module
+    }
+
+    #[test]
+    fn lookups() {
+        let mut module = Module::new();
+        module.add_struct("r#type");
+        module.add_function("f");
+        module.add_impl("r#type").add_function("new");
+        module.add_impl("r#type").set_trait("Default");
+        module.add_use("std::fmt");
+
+        assert_eq!(module.position("r#type"), Some(0));
+        assert_eq!(module.position("f"), Some(1));
+        assert_eq!(module.position("std::fmt"), None);
+        assert_eq!(module.position("missing"), None);
+        assert!(module.get_struct("r#type").is_some());
+        assert!(module.get_struct("f").is_none());
+        assert!(module.get_enum("r#type").is_none());
+        module.get_function_mut("f").unwrap().set_return_type("u8");
+        assert_eq!(
+            module.get_function("f").unwrap().to_code_string(),
+            "fn f() -> u8;\n"
+        );
+
+        assert_eq!(module.impls().count(), 2);
+        let inherent = module.get_impl("r#type", None).unwrap();
+        assert!(inherent.r#trait().is_none());
+        assert!(inherent.function("new").is_some());
+        assert!(module.get_impl("r#type", Some("Clone")).is_none());
+        assert!(module.get_impl("f", None).is_none());
+        module
+            .get_impl_mut("r#type", Some("Default"))
+            .unwrap()
+            .add_function("default");
+        let default = module.get_impl("r#type", Some("Default")).unwrap();
+        assert!(default.function("default").is_some());
+    }
+
+    #[test]
+    fn editing() {
+        let mut module = Module::new();
+        module.add_struct("C");
+        module.add_struct("A");
+        module.add_function("f");
+        module.add_struct("B");
+
+        module.sort_by(|a, b| a.name().cmp(&b.name()));
+        assert_eq!(
+            module.to_string(),
+            "struct A {\n}\n\nstruct B {\n}\n\nstruct C {\n}\n\nfn f();\n"
+        );
+
+        let ModuleNode::Function(function) =
+            module.insert_at(1, ModuleNode::Function(Function::new("g".into())))
+        else {
+            panic!("expected the inserted function");
+        };
+        function.set_return_type("u8");
+        assert_eq!(module.position("g"), Some(1));
+        assert_eq!(module.position("B"), Some(2));
+
+        let removed = module.remove(0);
+        assert_eq!(removed.name(), Some("A"));
+        module.retain(|node| !matches!(node, ModuleNode::Struct(_)));
+        assert_eq!(module.to_string(), "fn g() -> u8;\n\nfn f();\n");
+    }
+
+    fn read(dir: &Path, file: &str) -> String {
+        fs::read_to_string(dir.join(file)).unwrap()
+    }
+
+    #[test]
+    fn file_style() {
+        let dir = temp_dir("file");
+        tree().write_to_dir(&dir).unwrap();
+        assert_eq!(
+            read(&dir, "lib.rs"),
+            "pub mod a;\nmod r#type;\n\nfn root() {\n}\n"
+        );
+        assert_eq!(read(&dir, "a.rs"), "struct A {\n}\n\nmod b;\n");
+        assert_eq!(read(&dir, "a/b.rs"), "fn b() {\n}\n");
+        assert_eq!(read(&dir, "type.rs"), "struct T {\n}\n");
+        // Only modules with submodules get a directory
+        assert!(!dir.join("type").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn mod_rs_style() {
+        let dir = temp_dir("mod-rs");
+        let mut options = DirOptions::new();
+        options
+            .set_root_file("main.rs")
+            .set_style(ModuleStyle::ModRs);
+        tree().write_to_dir_with(&dir, &options).unwrap();
+        assert!(!dir.join("lib.rs").exists());
+        assert_eq!(
+            read(&dir, "main.rs"),
+            "pub mod a;\nmod r#type;\n\nfn root() {\n}\n"
+        );
+        assert_eq!(read(&dir, "a/mod.rs"), "struct A {\n}\n\nmod b;\n");
+        assert_eq!(read(&dir, "a/b/mod.rs"), "fn b() {\n}\n");
+        assert_eq!(read(&dir, "type/mod.rs"), "struct T {\n}\n");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unchanged_files_are_kept() {
+        let dir = temp_dir("unchanged");
+        let mut module = tree();
+        module.write_to_dir(&dir).unwrap();
+        let old = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+        for file in ["lib.rs", "a.rs"] {
+            fs::File::options()
+                .write(true)
+                .open(dir.join(file))
+                .unwrap()
+                .set_modified(old)
+                .unwrap();
+        }
+        module.add_function("other");
+        module.write_to_dir(&dir).unwrap();
+        let modified = |file: &str| fs::metadata(dir.join(file)).unwrap().modified().unwrap();
+        assert_ne!(modified("lib.rs"), old);
+        assert_eq!(modified("a.rs"), old);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -2,11 +2,12 @@ use std::fmt::Write;
 
 use crate::{expr::Expr, generator::GenerateCode, Str};
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Const {
-    public: bool,
-    name: Str,
-    r#type: Str,
-    value: Expr,
+    pub(crate) public: bool,
+    pub(crate) name: Str,
+    pub(crate) r#type: Str,
+    pub(crate) value: Expr,
 }
 
 impl Const {
@@ -23,6 +24,36 @@ impl Const {
         self.public = public;
         self
     }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: impl Into<Str>) -> &mut Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn r#type(&self) -> &str {
+        &self.r#type
+    }
+
+    pub fn set_type(&mut self, r#type: impl Into<Str>) -> &mut Self {
+        self.r#type = r#type.into();
+        self
+    }
+
+    pub fn value(&self) -> &Expr {
+        &self.value
+    }
+
+    pub fn value_mut(&mut self) -> &mut Expr {
+        &mut self.value
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.public
+    }
 }
 
 impl GenerateCode for Const {
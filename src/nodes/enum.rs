@@ -1,47 +1,295 @@
-use std::fmt::Write;
+use std::{cmp::Ordering, fmt::Write};
 
-use crate::{generator::GenerateCode, Str};
+use crate::{expr::Expr, generator::GenerateCode, Str};
 
+use super::r#struct::Field;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Enum {
-    public: bool,
-    name: Str,
-    variants: Vec<EnumVariant>,
+    pub(crate) doc: Option<Str>,
+    pub(crate) attributes: Vec<Str>,
+    pub(crate) public: bool,
+    pub(crate) public_in: Option<Str>,
+    pub(crate) name: Str,
+    pub(crate) generics: Vec<Str>,
+    pub(crate) variants: Vec<EnumVariant>,
 }
 
 impl Enum {
     pub fn new(name: Str) -> Self {
         Self {
+            doc: None,
+            attributes: Vec::new(),
             public: false,
+            public_in: None,
             name,
+            generics: Vec::new(),
             variants: Vec::new(),
         }
     }
 
     pub fn set_public(&mut self, public: bool) -> &mut Self {
         self.public = public;
+        self.public_in = None;
+        self
+    }
+
+    /// Restricts the visibility to `path`, e.g. `crate` for `pub(crate)`
+    pub fn set_public_in(&mut self, path: impl Into<Str>) -> &mut Self {
+        self.public = true;
+        self.public_in = Some(path.into());
         self
     }
+
+    pub fn add_variant(&mut self, name: impl Into<Str>) -> &mut EnumVariant {
+        push_mut!(self.variants, EnumVariant::new(name.into()))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: impl Into<Str>) -> &mut Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.public
+    }
+
+    /// The path the visibility is restricted to, e.g. `crate` for `pub(crate)`
+    pub fn public_in(&self) -> Option<&str> {
+        self.public_in.as_deref()
+    }
+
+    /// Adds a generic parameter with optional bounds, e.g. `T` or `T: Clone`
+    pub fn add_generic(&mut self, generic: impl Into<Str>) -> &mut Self {
+        self.generics.push(generic.into());
+        self
+    }
+
+    pub fn generics(&self) -> &[Str] {
+        &self.generics
+    }
+
+    /// Adds an outer attribute without `#[]`, e.g. `derive(Debug)` or `repr(u8)`
+    pub fn add_attribute(&mut self, attribute: impl Into<Str>) -> &mut Self {
+        self.attributes.push(attribute.into());
+        self
+    }
+
+    pub fn attributes(&self) -> &[Str] {
+        &self.attributes
+    }
+
+    pub fn attributes_mut(&mut self) -> &mut Vec<Str> {
+        &mut self.attributes
+    }
+
+    /// Sets the doc comment, each line becomes a `///` comment
+    pub fn set_doc(&mut self, doc: impl Into<Str>) -> &mut Self {
+        self.doc = Some(doc.into());
+        self
+    }
+
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
+    pub fn variants(&self) -> &[EnumVariant] {
+        &self.variants
+    }
+
+    pub fn variants_mut(&mut self) -> &mut [EnumVariant] {
+        &mut self.variants
+    }
+
+    pub fn variant(&self, name: &str) -> Option<&EnumVariant> {
+        self.variants.iter().find(|variant| variant.name == name)
+    }
+
+    pub fn variant_mut(&mut self, name: &str) -> Option<&mut EnumVariant> {
+        self.variants
+            .iter_mut()
+            .find(|variant| variant.name == name)
+    }
+
+    /// Inserts a variant at `index`, shifting all variants after it
+    pub fn insert_variant_at(&mut self, index: usize, name: impl Into<Str>) -> &mut EnumVariant {
+        self.variants.insert(index, EnumVariant::new(name.into()));
+        &mut self.variants[index]
+    }
+
+    pub fn remove_variant(&mut self, name: &str) -> Option<EnumVariant> {
+        let index = self
+            .variants
+            .iter()
+            .position(|variant| variant.name == name)?;
+        Some(self.variants.remove(index))
+    }
+
+    pub fn sort_variants_by(
+        &mut self,
+        compare: impl FnMut(&EnumVariant, &EnumVariant) -> Ordering,
+    ) {
+        self.variants.sort_by(compare);
+    }
 }
 
 impl GenerateCode for Enum {
     fn generate(&self, fmt: &mut crate::generator::Formatter) {
-        if self.public {
-            _ = write!(fmt, "pub ");
-        }
-        _ = writeln!(fmt, "enum {} ", self.name);
+        fmt.write_doc_and_attributes(self.doc.as_deref(), &self.attributes);
+        fmt.write_visibility(self.public, self.public_in.as_deref());
+        _ = write!(fmt, "enum {}", self.name);
+        fmt.write_generics(&self.generics);
+        _ = write!(fmt, " ");
         fmt.write_block(|fmt| {
             for variant in &self.variants {
                 variant.generate(fmt);
-                todo!("Implement enum variant separation")
             }
         });
     }
 }
 
-pub struct EnumVariant {}
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EnumVariant {
+    pub(crate) doc: Option<Str>,
+    pub(crate) attributes: Vec<Str>,
+    pub(crate) name: Str,
+    pub(crate) fields: VariantFields,
+    pub(crate) discriminant: Option<Expr>,
+}
+
+impl EnumVariant {
+    pub fn new(name: Str) -> Self {
+        Self {
+            doc: None,
+            attributes: Vec::new(),
+            name,
+            fields: VariantFields::Unit,
+            discriminant: None,
+        }
+    }
+
+    /// Adds a named field, turning the variant into a struct variant
+    pub fn add_field(&mut self, name: impl Into<Str>, r#type: impl Into<Str>) -> &mut Field {
+        if !matches!(self.fields, VariantFields::Named(_)) {
+            self.fields = VariantFields::Named(Vec::new());
+        }
+        let VariantFields::Named(fields) = &mut self.fields else {
+            unreachable!();
+        };
+        push_mut!(fields, Field::new(name.into(), r#type.into()))
+    }
+
+    /// Adds an unnamed field, turning the variant into a tuple variant
+    pub fn add_tuple_field(&mut self, r#type: impl Into<Str>) -> &mut Self {
+        if !matches!(self.fields, VariantFields::Unnamed(_)) {
+            self.fields = VariantFields::Unnamed(Vec::new());
+        }
+        let VariantFields::Unnamed(fields) = &mut self.fields else {
+            unreachable!();
+        };
+        fields.push(r#type.into());
+        self
+    }
+
+    /// Set the explicit discriminant, e.g. `A = 1`
+    pub fn set_discriminant(&mut self, discriminant: impl Into<Expr>) -> &mut Self {
+        self.discriminant = Some(discriminant.into());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: impl Into<Str>) -> &mut Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn fields(&self) -> &VariantFields {
+        &self.fields
+    }
+
+    pub fn fields_mut(&mut self) -> &mut VariantFields {
+        &mut self.fields
+    }
+
+    pub fn discriminant(&self) -> Option<&Expr> {
+        self.discriminant.as_ref()
+    }
+
+    pub fn discriminant_mut(&mut self) -> Option<&mut Expr> {
+        self.discriminant.as_mut()
+    }
+
+    /// Adds an outer attribute without `#[]`, e.g. `default`
+    pub fn add_attribute(&mut self, attribute: impl Into<Str>) -> &mut Self {
+        self.attributes.push(attribute.into());
+        self
+    }
+
+    pub fn attributes(&self) -> &[Str] {
+        &self.attributes
+    }
+
+    pub fn attributes_mut(&mut self) -> &mut Vec<Str> {
+        &mut self.attributes
+    }
+
+    /// Sets the doc comment, each line becomes a `///` comment
+    pub fn set_doc(&mut self, doc: impl Into<Str>) -> &mut Self {
+        self.doc = Some(doc.into());
+        self
+    }
+
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+}
 
 impl GenerateCode for EnumVariant {
-    fn generate(&self, _fmt: &mut crate::generator::Formatter) {
-        todo!("Implement enum variant")
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        fmt.write_doc_and_attributes(self.doc.as_deref(), &self.attributes);
+        _ = write!(fmt, "{}", self.name);
+        match &self.fields {
+            VariantFields::Unit => {}
+            VariantFields::Unnamed(types) => {
+                _ = write!(fmt, "(");
+                for (i, r#type) in types.iter().enumerate() {
+                    if i > 0 {
+                        _ = write!(fmt, ", ");
+                    }
+                    _ = write!(fmt, "{type}");
+                }
+                _ = write!(fmt, ")");
+            }
+            VariantFields::Named(fields) => {
+                _ = write!(fmt, " ");
+                fmt.write_block(|fmt| {
+                    for field in fields {
+                        field.generate(fmt);
+                    }
+                });
+                // Remove the newline written by the block
+                fmt.pop_newline();
+            }
+        }
+        if let Some(discriminant) = &self.discriminant {
+            _ = write!(fmt, " = ");
+            discriminant.generate(fmt);
+        }
+        _ = writeln!(fmt, ",");
     }
 }
+
+/// The fields of an enum variant
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum VariantFields {
+    Unit,
+    Unnamed(Vec<Str>),
+    Named(Vec<Field>),
+}
@@ -0,0 +1,166 @@
+use std::fmt::Write;
+
+use crate::{generator::GenerateCode, Str};
+
+use super::function::Function;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ExternBlock {
+    pub(crate) abi: Option<Str>,
+    pub(crate) nodes: Vec<ExternNode>,
+}
+
+impl ExternBlock {
+    pub fn new(abi: Option<Str>) -> Self {
+        Self {
+            abi,
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Adds a foreign function declaration, the function should not have a body
+    pub fn add_function(&mut self, name: impl Into<Str>) -> &mut Function {
+        push_mut!(@[ExternNode::Function] self.nodes, Function::new(name.into()))
+    }
+
+    pub fn add_static(
+        &mut self,
+        name: impl Into<Str>,
+        r#type: impl Into<Str>,
+    ) -> &mut ExternStatic {
+        push_mut!(@[ExternNode::Static] self.nodes, ExternStatic::new(name.into(), r#type.into()))
+    }
+
+    pub fn abi(&self) -> Option<&str> {
+        self.abi.as_deref()
+    }
+
+    pub fn nodes(&self) -> &[ExternNode] {
+        &self.nodes
+    }
+
+    pub fn nodes_mut(&mut self) -> &mut Vec<ExternNode> {
+        &mut self.nodes
+    }
+}
+
+impl GenerateCode for ExternBlock {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        _ = write!(fmt, "extern ");
+        if let Some(abi) = &self.abi {
+            _ = write!(fmt, "{abi:?} ");
+        }
+        fmt.write_block(|fmt| {
+            for node in &self.nodes {
+                node.generate(fmt);
+            }
+        });
+    }
+}
+
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ExternNode {
+    Function(Function),
+    Static(ExternStatic),
+}
+
+impl GenerateCode for ExternNode {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        match self {
+            ExternNode::Function(function) => function.generate(fmt),
+            ExternNode::Static(static_) => static_.generate(fmt),
+        }
+    }
+}
+
+/// A static declared in an extern block, it has no value
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ExternStatic {
+    pub(crate) public: bool,
+    pub(crate) mutable: bool,
+    pub(crate) name: Str,
+    pub(crate) r#type: Str,
+}
+
+impl ExternStatic {
+    pub fn new(name: Str, r#type: Str) -> Self {
+        Self {
+            public: false,
+            mutable: false,
+            name,
+            r#type,
+        }
+    }
+
+    pub fn set_public(&mut self, public: bool) -> &mut Self {
+        self.public = public;
+        self
+    }
+
+    pub fn set_mut(&mut self, mutable: bool) -> &mut Self {
+        self.mutable = mutable;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn r#type(&self) -> &str {
+        &self.r#type
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.public
+    }
+
+    pub fn is_mut(&self) -> bool {
+        self.mutable
+    }
+}
+
+impl GenerateCode for ExternStatic {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        if self.public {
+            _ = write!(fmt, "pub ");
+        }
+        _ = write!(fmt, "static ");
+        if self.mutable {
+            _ = write!(fmt, "mut ");
+        }
+        _ = writeln!(fmt, "{}: {};", self.name, self.r#type);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate() {
+        let mut block = ExternBlock::new(Some("C".into()));
+        block
+            .add_function("abs")
+            .add_param("x", "i32")
+            .set_return_type("i32");
+        block
+            .add_static("errno", "i32")
+            .set_public(true)
+            .set_mut(true);
+        assert_eq!(
+            block.to_code_string(),
+            "extern \"C\" {\n    fn abs(x: i32) -> i32;\n    pub static mut errno: i32;\n}\n"
+        );
+    }
+
+    #[test]
+    fn without_abi() {
+        let mut module = crate::nodes::Module::new();
+        module
+            .add_extern_block_without_abi()
+            .add_function("exit")
+            .add_param("code", "i32");
+        assert_eq!(module.to_string(), "extern {\n    fn exit(code: i32);\n}\n");
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt::Write;
+
+use crate::{generator::GenerateCode, Str};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ExternCrate {
+    pub(crate) public: bool,
+    pub(crate) name: Str,
+    pub(crate) alias: Option<Str>,
+}
+
+impl ExternCrate {
+    pub fn new(name: Str) -> Self {
+        Self {
+            public: false,
+            name,
+            alias: None,
+        }
+    }
+
+    pub fn set_public(&mut self, public: bool) -> &mut Self {
+        self.public = public;
+        self
+    }
+
+    /// Set the name the crate is imported as (`extern crate name as alias;`)
+    pub fn set_alias(&mut self, alias: impl Into<Str>) -> &mut Self {
+        self.alias = Some(alias.into());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn alias(&self) -> Option<&str> {
+        self.alias.as_deref()
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.public
+    }
+}
+
+impl GenerateCode for ExternCrate {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        if self.public {
+            _ = write!(fmt, "pub ");
+        }
+        _ = write!(fmt, "extern crate {}", self.name);
+        if let Some(alias) = &self.alias {
+            _ = write!(fmt, " as {alias}");
+        }
+        _ = writeln!(fmt, ";");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate() {
+        assert_eq!(
+            ExternCrate::new("alloc".into()).to_code_string(),
+            "extern crate alloc;\n"
+        );
+        let mut core = ExternCrate::new("core".into());
+        core.set_public(true).set_alias("std_core");
+        assert_eq!(
+            core.to_code_string(),
+            "pub extern crate core as std_core;\n"
+        );
+    }
+}
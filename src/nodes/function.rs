@@ -4,21 +4,34 @@ use crate::{generator::GenerateCode, Str};
 
 use super::statement::Block;
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Function {
-    public: bool,
-    constant: bool,
-    name: Str,
-    params: Vec<Param>,
-    return_type: Option<Str>,
-    body: Option<Block>,
+    pub(crate) doc: Option<Str>,
+    pub(crate) attributes: Vec<Str>,
+    pub(crate) public: bool,
+    pub(crate) public_in: Option<Str>,
+    pub(crate) constant: bool,
+    pub(crate) asynchronous: bool,
+    pub(crate) unsafety: bool,
+    pub(crate) name: Str,
+    pub(crate) generics: Vec<Str>,
+    pub(crate) params: Vec<Param>,
+    pub(crate) return_type: Option<Str>,
+    pub(crate) body: Option<Block>,
 }
 
 impl Function {
     pub fn new(name: Str) -> Self {
         Self {
+            doc: None,
+            attributes: Vec::new(),
             public: false,
+            public_in: None,
             constant: false,
+            asynchronous: false,
+            unsafety: false,
             name,
+            generics: Vec::new(),
             params: Vec::new(),
             return_type: None,
             body: None,
@@ -27,6 +40,14 @@ impl Function {
 
     pub fn set_public(&mut self, public: bool) -> &mut Self {
         self.public = public;
+        self.public_in = None;
+        self
+    }
+
+    /// Restricts the visibility to `path`, e.g. `crate` for `pub(crate)`
+    pub fn set_public_in(&mut self, path: impl Into<Str>) -> &mut Self {
+        self.public = true;
+        self.public_in = Some(path.into());
         self
     }
 
@@ -35,6 +56,22 @@ impl Function {
         self
     }
 
+    pub fn set_async(&mut self, asynchronous: bool) -> &mut Self {
+        self.asynchronous = asynchronous;
+        self
+    }
+
+    pub fn set_unsafe(&mut self, unsafety: bool) -> &mut Self {
+        self.unsafety = unsafety;
+        self
+    }
+
+    /// Adds a generic parameter with optional bounds, e.g. `T` or `H: std::hash::Hasher`
+    pub fn add_generic(&mut self, generic: impl Into<Str>) -> &mut Self {
+        self.generics.push(generic.into());
+        self
+    }
+
     pub fn add_self_param(&mut self, reference: bool, mutable: bool) -> &mut Self {
         self.params.push(Param::new_self(reference, mutable));
         self
@@ -50,21 +87,111 @@ impl Function {
         self
     }
 
+    /// Adds an empty body, an existing body is kept and returned
     pub fn add_body(&mut self) -> &mut Block {
-        self.body = Some(Block::new());
-        self.body.as_mut().unwrap()
+        self.body.get_or_insert_with(Block::new)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: impl Into<Str>) -> &mut Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.public
+    }
+
+    /// The path the visibility is restricted to, e.g. `crate` for `pub(crate)`
+    pub fn public_in(&self) -> Option<&str> {
+        self.public_in.as_deref()
+    }
+
+    pub fn is_const(&self) -> bool {
+        self.constant
+    }
+
+    pub fn is_async(&self) -> bool {
+        self.asynchronous
+    }
+
+    pub fn is_unsafe(&self) -> bool {
+        self.unsafety
+    }
+
+    /// Adds an outer attribute without `#[]`, e.g. `inline` or `must_use`
+    pub fn add_attribute(&mut self, attribute: impl Into<Str>) -> &mut Self {
+        self.attributes.push(attribute.into());
+        self
+    }
+
+    pub fn attributes(&self) -> &[Str] {
+        &self.attributes
+    }
+
+    pub fn attributes_mut(&mut self) -> &mut Vec<Str> {
+        &mut self.attributes
+    }
+
+    /// Sets the doc comment, each line becomes a `///` comment
+    pub fn set_doc(&mut self, doc: impl Into<Str>) -> &mut Self {
+        self.doc = Some(doc.into());
+        self
+    }
+
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
+    pub fn generics(&self) -> &[Str] {
+        &self.generics
+    }
+
+    pub fn params(&self) -> &[Param] {
+        &self.params
+    }
+
+    pub fn params_mut(&mut self) -> &mut Vec<Param> {
+        &mut self.params
+    }
+
+    pub fn return_type(&self) -> Option<&str> {
+        self.return_type.as_deref()
+    }
+
+    pub fn body(&self) -> Option<&Block> {
+        self.body.as_ref()
+    }
+
+    pub fn body_mut(&mut self) -> Option<&mut Block> {
+        self.body.as_mut()
+    }
+
+    /// Removes the body, the function is then generated as a declaration
+    pub fn remove_body(&mut self) -> Option<Block> {
+        self.body.take()
     }
 }
 
 impl GenerateCode for Function {
     fn generate(&self, fmt: &mut crate::generator::Formatter) {
-        if self.public {
-            _ = write!(fmt, "pub ");
-        }
+        fmt.write_doc_and_attributes(self.doc.as_deref(), &self.attributes);
+        fmt.write_visibility(self.public, self.public_in.as_deref());
         if self.constant {
             _ = write!(fmt, "const ");
         }
-        _ = write!(fmt, "fn {}(", self.name);
+        if self.asynchronous {
+            _ = write!(fmt, "async ");
+        }
+        if self.unsafety {
+            _ = write!(fmt, "unsafe ");
+        }
+        _ = write!(fmt, "fn {}", self.name);
+        fmt.write_generics(&self.generics);
+        _ = write!(fmt, "(");
         for (i, param) in self.params.iter().enumerate() {
             if i > 0 {
                 _ = write!(fmt, ", ");
@@ -85,9 +212,10 @@ impl GenerateCode for Function {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Param {
-    name: Str,
-    r#type: Option<Str>,
+    pub(crate) name: Str,
+    pub(crate) r#type: Option<Str>,
 }
 
 impl Param {
@@ -110,6 +238,20 @@ impl Param {
             r#type: None,
         }
     }
+
+    /// Returns the name, or the whole receiver such as `&mut self`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the type, which is `None` for receivers
+    pub fn r#type(&self) -> Option<&str> {
+        self.r#type.as_deref()
+    }
+
+    pub fn is_self(&self) -> bool {
+        self.r#type.is_none()
+    }
 }
 
 impl GenerateCode for Param {
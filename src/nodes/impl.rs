@@ -1,24 +1,69 @@
-use std::fmt::Write;
+use std::{cmp::Ordering, fmt::Write};
 
 use crate::{generator::GenerateCode, Str};
 
-use super::function::Function;
+use crate::expr::Expr;
 
+use super::{
+    function::Function, r#const::Const, r#type::TypeAlias, raw::Raw, user_region::UserRegion,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Impl {
-    target: Str,
-    r#trait: Option<Str>,
-    functions: Vec<Function>,
+    pub(crate) attributes: Vec<Str>,
+    pub(crate) generics: Vec<Str>,
+    pub(crate) target: Str,
+    pub(crate) r#trait: Option<Str>,
+    pub(crate) where_predicates: Vec<Str>,
+    pub(crate) nodes: Vec<ImplNode>,
 }
 
 impl Impl {
     pub fn new(target: Str) -> Self {
         Self {
+            attributes: Vec::new(),
+            generics: Vec::new(),
             target,
             r#trait: None,
-            functions: Vec::new(),
+            where_predicates: Vec::new(),
+            nodes: Vec::new(),
         }
     }
 
+    /// Adds a generic parameter of the impl, e.g. `T` for `impl<T> Wrapper<T>`
+    pub fn add_generic(&mut self, generic: impl Into<Str>) -> &mut Self {
+        self.generics.push(generic.into());
+        self
+    }
+
+    pub fn generics(&self) -> &[Str] {
+        &self.generics
+    }
+
+    /// Adds an outer attribute without `#[]`, e.g. `cfg(test)`
+    pub fn add_attribute(&mut self, attribute: impl Into<Str>) -> &mut Self {
+        self.attributes.push(attribute.into());
+        self
+    }
+
+    pub fn attributes(&self) -> &[Str] {
+        &self.attributes
+    }
+
+    pub fn attributes_mut(&mut self) -> &mut Vec<Str> {
+        &mut self.attributes
+    }
+
+    /// Adds a predicate to the `where` clause, e.g. `T: Clone`
+    pub fn add_where_predicate(&mut self, predicate: impl Into<Str>) -> &mut Self {
+        self.where_predicates.push(predicate.into());
+        self
+    }
+
+    pub fn where_predicates(&self) -> &[Str] {
+        &self.where_predicates
+    }
+
     /// Set the trait to implement
     pub fn set_trait(&mut self, r#trait: impl Into<Str>) -> &mut Self {
         self.r#trait = Some(r#trait.into());
@@ -26,24 +71,214 @@ impl Impl {
     }
 
     pub fn add_function(&mut self, name: impl Into<Str>) -> &mut Function {
-        push_mut!(self.functions, Function::new(name.into()))
+        push_mut!(@[ImplNode::Function] self.nodes, Function::new(name.into()))
+    }
+
+    /// Adds an associated const, e.g. `const COUNT: usize = 3;`
+    pub fn add_const(
+        &mut self,
+        name: impl Into<Str>,
+        r#type: impl Into<Str>,
+        value: impl Into<Expr>,
+    ) -> &mut Const {
+        push_mut!(@[ImplNode::Const] self.nodes, Const::new(name.into(), r#type.into(), value.into()))
+    }
+
+    /// Adds an associated type, e.g. `type Err = ParseError;`
+    pub fn add_type_alias(
+        &mut self,
+        name: impl Into<Str>,
+        r#type: impl Into<Str>,
+    ) -> &mut TypeAlias {
+        push_mut!(@[ImplNode::TypeAlias] self.nodes, TypeAlias::new(name.into(), r#type.into()))
+    }
+
+    /// Adds verbatim code, see [`Raw`]
+    pub fn add_raw(&mut self, code: impl Into<Str>) -> &mut Raw {
+        push_mut!(@[ImplNode::Raw] self.nodes, Raw::new(code.into()))
+    }
+
+    /// Adds an already constructed node
+    pub fn add_node(&mut self, node: ImplNode) -> &mut ImplNode {
+        push_mut!(self.nodes, node)
+    }
+
+    /// Adds a hand-written region, see [`UserRegion`]
+    pub fn add_user_region(&mut self, name: impl Into<Str>) -> &mut UserRegion {
+        push_mut!(@[ImplNode::UserRegion] self.nodes, UserRegion::new(name.into()))
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    pub fn set_target(&mut self, target: impl Into<Str>) -> &mut Self {
+        self.target = target.into();
+        self
+    }
+
+    pub fn r#trait(&self) -> Option<&str> {
+        self.r#trait.as_deref()
+    }
+
+    pub fn nodes(&self) -> &[ImplNode] {
+        &self.nodes
+    }
+
+    pub fn nodes_mut(&mut self) -> &mut [ImplNode] {
+        &mut self.nodes
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = &Function> {
+        self.nodes.iter().filter_map(|node| match node {
+            ImplNode::Function(function) => Some(function),
+            _ => None,
+        })
+    }
+
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions().find(|function| function.name() == name)
+    }
+
+    pub fn function_mut(&mut self, name: &str) -> Option<&mut Function> {
+        self.nodes.iter_mut().find_map(|node| match node {
+            ImplNode::Function(function) if function.name() == name => Some(function),
+            _ => None,
+        })
+    }
+
+    /// Inserts a node at `index`, shifting all nodes after it
+    pub fn insert_at(&mut self, index: usize, node: ImplNode) -> &mut ImplNode {
+        self.nodes.insert(index, node);
+        &mut self.nodes[index]
+    }
+
+    pub fn remove(&mut self, index: usize) -> ImplNode {
+        self.nodes.remove(index)
+    }
+
+    pub fn sort_by(&mut self, compare: impl FnMut(&ImplNode, &ImplNode) -> Ordering) {
+        self.nodes.sort_by(compare);
+    }
+
+    pub fn retain(&mut self, keep: impl FnMut(&ImplNode) -> bool) {
+        self.nodes.retain(keep);
     }
 }
 
 impl GenerateCode for Impl {
     fn generate(&self, fmt: &mut crate::generator::Formatter) {
-        _ = write!(fmt, "impl ");
+        fmt.write_doc_and_attributes(None, &self.attributes);
+        _ = write!(fmt, "impl");
+        fmt.write_generics(&self.generics);
+        _ = write!(fmt, " ");
         if let Some(trait_) = &self.r#trait {
             _ = write!(fmt, "{trait_} for ");
         }
-        _ = write!(fmt, "{} ", self.target);
+        match self.where_predicates.is_empty() {
+            true => _ = write!(fmt, "{} ", self.target),
+            false => {
+                _ = writeln!(fmt, "{}", self.target);
+                _ = writeln!(fmt, "where");
+                fmt.indent(|fmt| {
+                    for predicate in &self.where_predicates {
+                        _ = writeln!(fmt, "{predicate},");
+                    }
+                });
+            }
+        }
         fmt.write_block(|fmt| {
-            for (i, function) in self.functions.iter().enumerate() {
-                if i > 0 {
-                    _ = writeln!(fmt);
+            let mut previous: Option<&ImplNode> = None;
+            for node in &self.nodes {
+                // Consecutive consts and associated types are not separated by blank lines
+                match (previous, node) {
+                    (None, _) => {}
+                    (
+                        Some(ImplNode::Const(_) | ImplNode::TypeAlias(_)),
+                        ImplNode::Const(_) | ImplNode::TypeAlias(_),
+                    ) => {}
+                    _ => _ = writeln!(fmt),
                 }
-                function.generate(fmt);
+                node.generate(fmt);
+                previous = Some(node);
             }
         });
     }
 }
+
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ImplNode {
+    Function(Function),
+    Const(Const),
+    TypeAlias(TypeAlias),
+    Raw(Raw),
+    UserRegion(UserRegion),
+}
+
+impl ImplNode {
+    /// Returns the name of functions, consts and associated types
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            ImplNode::Function(function) => Some(function.name()),
+            ImplNode::Const(r#const) => Some(r#const.name()),
+            ImplNode::TypeAlias(alias) => Some(alias.name()),
+            ImplNode::Raw(_) | ImplNode::UserRegion(_) => None,
+        }
+    }
+}
+
+impl GenerateCode for ImplNode {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        match self {
+            ImplNode::Function(function) => function.generate(fmt),
+            ImplNode::Const(r#const) => r#const.generate(fmt),
+            ImplNode::TypeAlias(alias) => alias.generate(fmt),
+            ImplNode::Raw(raw) => raw.generate(fmt),
+            ImplNode::UserRegion(region) => region.generate(fmt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes_and_where_clause() {
+        let mut r#impl = Impl::new("Wrap<T>".into());
+        r#impl
+            .add_generic("T")
+            .set_trait("Clone")
+            .add_attribute("automatically_derived")
+            .add_where_predicate("T: Clone")
+            .add_where_predicate("Vec<T>: Clone");
+        assert_eq!(
+            r#impl.to_code_string(),
+            "#[automatically_derived]\nimpl<T> Clone for Wrap<T>\nwhere\n    T: Clone,\n    Vec<T>: Clone,\n{\n}\n"
+        );
+    }
+
+    #[test]
+    fn editing() {
+        let mut r#impl = Impl::new("r#type".into());
+        r#impl.add_function("b");
+        r#impl.add_const("A", "u8", crate::expr::u8(1));
+        r#impl.add_raw("// raw");
+        r#impl.add_function("c");
+
+        assert!(r#impl.function("b").is_some());
+        assert!(r#impl.function("A").is_none());
+        r#impl.function_mut("c").unwrap().set_return_type("u8");
+        assert_eq!(r#impl.functions().count(), 2);
+
+        r#impl.retain(|node| !matches!(node, ImplNode::Raw(_)));
+        r#impl.sort_by(|a, b| a.name().cmp(&b.name()));
+        r#impl.insert_at(1, ImplNode::Function(Function::new("r#fn".into())));
+        assert_eq!(r#impl.remove(2).name(), Some("b"));
+        assert_eq!(
+            r#impl.to_code_string(),
+            "impl r#type {\n    const A: u8 = 1u8;\n\n    fn r#fn();\n\n    fn c() -> u8;\n}\n"
+        );
+    }
+}
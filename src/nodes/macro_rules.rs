@@ -0,0 +1,133 @@
+use std::fmt::Write;
+
+use crate::{generator::GenerateCode, Str};
+
+use super::raw::Raw;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MacroRules {
+    pub(crate) exported: bool,
+    pub(crate) name: Str,
+    pub(crate) arms: Vec<MacroArm>,
+}
+
+impl MacroRules {
+    pub fn new(name: Str) -> Self {
+        Self {
+            exported: false,
+            name,
+            arms: Vec::new(),
+        }
+    }
+
+    /// Adds `#[macro_export]` to the macro
+    pub fn set_exported(&mut self, exported: bool) -> &mut Self {
+        self.exported = exported;
+        self
+    }
+
+    /// Adds a rule, the matcher is written without its surrounding parentheses
+    pub fn add_arm(&mut self, matcher: impl Into<Str>, transcriber: impl Into<Str>) -> &mut Self {
+        self.arms
+            .push(MacroArm::new(matcher.into(), transcriber.into()));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: impl Into<Str>) -> &mut Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn is_exported(&self) -> bool {
+        self.exported
+    }
+
+    pub fn arms(&self) -> &[MacroArm] {
+        &self.arms
+    }
+
+    pub fn arms_mut(&mut self) -> &mut Vec<MacroArm> {
+        &mut self.arms
+    }
+}
+
+impl GenerateCode for MacroRules {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        if self.exported {
+            _ = writeln!(fmt, "#[macro_export]");
+        }
+        _ = write!(fmt, "macro_rules! {} ", self.name);
+        fmt.write_block(|fmt| {
+            for arm in &self.arms {
+                arm.generate(fmt);
+            }
+        });
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MacroArm {
+    pub(crate) matcher: Str,
+    pub(crate) transcriber: Raw,
+}
+
+impl MacroArm {
+    pub fn new(matcher: Str, transcriber: Str) -> Self {
+        Self {
+            matcher,
+            transcriber: Raw::new(transcriber),
+        }
+    }
+
+    pub fn matcher(&self) -> &str {
+        &self.matcher
+    }
+
+    pub fn transcriber(&self) -> &Raw {
+        &self.transcriber
+    }
+}
+
+impl GenerateCode for MacroArm {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        _ = writeln!(fmt, "({}) => {{", self.matcher);
+        fmt.indent(|fmt| {
+            self.transcriber.generate(fmt);
+        });
+        _ = writeln!(fmt, "}};");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate() {
+        let mut square = MacroRules::new("square".into());
+        square
+            .set_exported(true)
+            .add_arm("$x:expr", "$x * $x")
+            .add_arm("", "{\n    let a = 1;\n    a\n}");
+        assert_eq!(
+            square.to_code_string(),
+            r#"#[macro_export]
+macro_rules! square {
+    ($x:expr) => {
+        $x * $x
+    };
+    () => {
+        {
+            let a = 1;
+            a
+        }
+    };
+}
+"#
+        );
+    }
+}
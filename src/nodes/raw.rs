@@ -0,0 +1,522 @@
+use std::fmt::{Display, Write};
+
+use crate::{generator::GenerateCode, Str};
+
+/// Verbatim code that is inserted as is
+///
+/// The common leading indentation of all lines is stripped, the code is then re-indented to the
+/// level it is generated at.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Raw {
+    code: Str,
+}
+
+impl Raw {
+    pub fn new(code: Str) -> Self {
+        Self { code }
+    }
+
+    /// Creates a new raw node after checking that its delimiters are balanced
+    pub fn new_checked(code: Str) -> Result<Self, DelimiterError> {
+        let raw = Self::new(code);
+        raw.check_delimiters()?;
+        Ok(raw)
+    }
+
+    /// Returns the code with its common indentation and surrounding blank lines removed
+    ///
+    /// Lines that continue a multi-line string literal are kept as they are.
+    pub fn dedented(&self) -> String {
+        let lines: Vec<&str> = self.code.lines().collect();
+        let in_string = lines_in_string(&self.code);
+        let start = lines.iter().position(|line| !line.trim().is_empty());
+        let Some(start) = start else {
+            return String::new();
+        };
+        let end = lines
+            .iter()
+            .rposition(|line| !line.trim().is_empty())
+            .unwrap()
+            + 1;
+        let mut indent: Option<&str> = None;
+        for (i, line) in lines.iter().enumerate().take(end).skip(start) {
+            if in_string[i] || line.trim().is_empty() {
+                continue;
+            }
+            let len = line.len() - line.trim_start_matches([' ', '\t']).len();
+            let current = &line[..len];
+            indent = Some(match indent {
+                Some(indent) => {
+                    let common = indent
+                        .bytes()
+                        .zip(current.bytes())
+                        .take_while(|(a, b)| a == b)
+                        .count();
+                    &indent[..common]
+                }
+                None => current,
+            });
+        }
+        let indent = indent.unwrap_or_default();
+        let mut dst = String::new();
+        for (i, line) in lines.iter().enumerate().take(end).skip(start) {
+            if i > start {
+                dst.push('\n');
+            }
+            let line = match in_string[i] {
+                true => line,
+                false => line.strip_prefix(indent).unwrap_or(""),
+            };
+            // Trailing whitespace is part of a string literal that continues on the next line
+            match in_string.get(i + 1) {
+                Some(true) => dst.push_str(line),
+                _ => dst.push_str(line.trim_end()),
+            }
+        }
+        dst
+    }
+
+    /// Checks that all parentheses, brackets and braces are balanced
+    ///
+    /// Delimiters inside of comments, string literals and char literals are ignored.
+    pub fn check_delimiters(&self) -> Result<(), DelimiterError> {
+        let mut stack: Vec<(char, usize, usize)> = Vec::new();
+        let mut chars = self.code.chars().peekable();
+        let (mut line, mut column) = (1, 0);
+        macro_rules! next {
+            () => {{
+                let c = chars.next();
+                if c == Some('\n') {
+                    line += 1;
+                    column = 0;
+                } else {
+                    column += 1;
+                }
+                c
+            }};
+        }
+        while let Some(c) = next!() {
+            match c {
+                '(' | '[' | '{' => stack.push((c, line, column)),
+                ')' | ']' | '}' => {
+                    let expected = match stack.pop() {
+                        Some((open, ..)) => closing(open),
+                        None => {
+                            return Err(DelimiterError::new(
+                                DelimiterErrorKind::Unexpected(c),
+                                line,
+                                column,
+                            ))
+                        }
+                    };
+                    if expected != c {
+                        return Err(DelimiterError::new(
+                            DelimiterErrorKind::Mismatched { expected, found: c },
+                            line,
+                            column,
+                        ));
+                    }
+                }
+                '/' if chars.peek() == Some(&'/') => {
+                    while !matches!(chars.peek(), None | Some('\n')) {
+                        next!();
+                    }
+                }
+                '/' if chars.peek() == Some(&'*') => {
+                    next!();
+                    let mut depth = 1;
+                    while depth > 0 {
+                        match next!() {
+                            Some('*') if chars.peek() == Some(&'/') => {
+                                next!();
+                                depth -= 1;
+                            }
+                            Some('/') if chars.peek() == Some(&'*') => {
+                                next!();
+                                depth += 1;
+                            }
+                            Some(_) => {}
+                            None => break,
+                        }
+                    }
+                }
+                '"' => {
+                    while let Some(c) = next!() {
+                        match c {
+                            '\\' => _ = next!(),
+                            '"' => break,
+                            _ => {}
+                        }
+                    }
+                }
+                'r' if matches!(chars.peek(), Some('"' | '#')) => {
+                    let mut hashes = 0;
+                    while chars.peek() == Some(&'#') {
+                        next!();
+                        hashes += 1;
+                    }
+                    if next!() != Some('"') {
+                        continue;
+                    }
+                    'string: while let Some(c) = next!() {
+                        if c == '"' {
+                            for _ in 0..hashes {
+                                if chars.peek() != Some(&'#') {
+                                    continue 'string;
+                                }
+                                next!();
+                            }
+                            break;
+                        }
+                    }
+                }
+                '\'' => {
+                    // Either a char literal or a lifetime
+                    let mut lookahead = chars.clone();
+                    let is_char = match lookahead.next() {
+                        Some('\\') => true,
+                        Some(_) => lookahead.next() == Some('\''),
+                        None => false,
+                    };
+                    if is_char {
+                        while let Some(c) = next!() {
+                            match c {
+                                '\\' => _ = next!(),
+                                '\'' => break,
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        match stack.pop() {
+            Some((open, line, column)) => Err(DelimiterError::new(
+                DelimiterErrorKind::Unclosed(open),
+                line,
+                column,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the code as it was given
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+}
+
+impl GenerateCode for Raw {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        let code = self.dedented();
+        if code.is_empty() {
+            return;
+        }
+        for (line, in_string) in code.lines().zip(lines_in_string(&code)) {
+            match in_string {
+                true => fmt.write_unindented(line),
+                false => _ = write!(fmt, "{line}"),
+            }
+            _ = writeln!(fmt);
+        }
+    }
+}
+
+/// Returns for every line whether it starts inside of a string literal
+pub(crate) fn lines_in_string(code: &str) -> Vec<bool> {
+    enum State {
+        Code,
+        String,
+        RawString(usize),
+        LineComment,
+        BlockComment(usize),
+    }
+
+    let mut lines = vec![false];
+    let mut state = State::Code;
+    let mut chars = code.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\n' {
+            lines.push(matches!(state, State::String | State::RawString(_)));
+            if matches!(state, State::LineComment) {
+                state = State::Code;
+            }
+            continue;
+        }
+        match state {
+            State::Code => match c {
+                '/' if chars.peek() == Some(&'/') => state = State::LineComment,
+                '/' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    state = State::BlockComment(1);
+                }
+                '"' => state = State::String,
+                'r' if matches!(chars.peek(), Some('"' | '#')) => {
+                    let mut lookahead = chars.clone();
+                    let mut hashes = 0;
+                    while lookahead.next_if_eq(&'#').is_some() {
+                        hashes += 1;
+                    }
+                    if lookahead.next() == Some('"') {
+                        chars = lookahead;
+                        state = State::RawString(hashes);
+                    }
+                }
+                '\'' => {
+                    // Either a char literal or a lifetime
+                    let mut lookahead = chars.clone();
+                    let is_char = match lookahead.next() {
+                        Some('\\') => true,
+                        Some(_) => lookahead.next() == Some('\''),
+                        None => false,
+                    };
+                    if is_char {
+                        while let Some(c) = chars.next() {
+                            match c {
+                                '\\' => _ = chars.next(),
+                                '\'' => break,
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            },
+            State::String => match c {
+                // An escaped newline still starts a new line of the literal
+                '\\' => _ = chars.next_if(|&c| c != '\n'),
+                '"' => state = State::Code,
+                _ => {}
+            },
+            State::RawString(hashes) => {
+                let mut lookahead = chars.clone();
+                if c == '"' && (0..hashes).all(|_| lookahead.next() == Some('#')) {
+                    chars = lookahead;
+                    state = State::Code;
+                }
+            }
+            State::LineComment => {}
+            State::BlockComment(depth) => {
+                if c == '*' && chars.next_if_eq(&'/').is_some() {
+                    state = match depth {
+                        1 => State::Code,
+                        _ => State::BlockComment(depth - 1),
+                    };
+                } else if c == '/' && chars.next_if_eq(&'*').is_some() {
+                    state = State::BlockComment(depth + 1);
+                }
+            }
+        }
+    }
+    lines
+}
+
+fn closing(open: char) -> char {
+    match open {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DelimiterError {
+    kind: DelimiterErrorKind,
+    line: usize,
+    column: usize,
+}
+
+impl DelimiterError {
+    fn new(kind: DelimiterErrorKind, line: usize, column: usize) -> Self {
+        Self { kind, line, column }
+    }
+
+    pub fn kind(&self) -> &DelimiterErrorKind {
+        &self.kind
+    }
+
+    /// The 1-based line of the offending delimiter
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The 1-based column of the offending delimiter
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl Display for DelimiterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            DelimiterErrorKind::Unexpected(found) => write!(f, "unexpected `{found}`")?,
+            DelimiterErrorKind::Mismatched { expected, found } => {
+                write!(f, "expected `{expected}`, found `{found}`")?
+            }
+            DelimiterErrorKind::Unclosed(open) => write!(f, "unclosed `{open}`")?,
+        }
+        write!(f, " at {}:{}", self.line, self.column)
+    }
+}
+
+impl std::error::Error for DelimiterError {}
+
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DelimiterErrorKind {
+    /// A closing delimiter without an opening one
+    Unexpected(char),
+    /// A closing delimiter that does not match the last opening one
+    Mismatched { expected: char, found: char },
+    /// An opening delimiter that is never closed
+    Unclosed(char),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(code: &str) -> Result<(), DelimiterError> {
+        Raw::new(code.to_owned().into()).check_delimiters()
+    }
+
+    fn kind(code: &str) -> DelimiterErrorKind {
+        *check(code).unwrap_err().kind()
+    }
+
+    #[test]
+    fn balanced() {
+        assert!(check("fn f(a: [u8; 2]) { g(a[0]) }").is_ok());
+    }
+
+    #[test]
+    fn strings() {
+        assert!(check(r#"let s = "(}\" [";"#).is_ok());
+        assert!(check(r#"let s = b"{";"#).is_ok());
+    }
+
+    #[test]
+    fn raw_strings() {
+        assert!(check(r##"let s = r"(\";"##).is_ok());
+        assert!(check(r###"let s = r#"a "} b"#;"###).is_ok());
+        assert!(check(r###"let s = br#"{"#;"###).is_ok());
+        // A quote followed by fewer hashes does not end the string
+        assert!(check(r####"let s = r##"}"#)"##;"####).is_ok());
+    }
+
+    #[test]
+    fn raw_identifiers() {
+        assert!(check("let r#type = (1);").is_ok());
+        assert_eq!(kind("let r#type = (1;"), DelimiterErrorKind::Unclosed('('));
+    }
+
+    #[test]
+    fn chars() {
+        assert!(check("let c = '{';").is_ok());
+        assert!(check(r"let c = '\'';").is_ok());
+        assert!(check(r"let c = '\u{7B}';").is_ok());
+        assert!(check("let c = ')'; let d = '(';").is_ok());
+    }
+
+    #[test]
+    fn lifetimes() {
+        assert!(check("fn f<'a>(x: &'a str) -> &'a str { x }").is_ok());
+        assert!(check("struct A<'a, 'b>(&'a u8, &'b u8);").is_ok());
+        assert_eq!(
+            kind("fn f<'a>(x: &'a str) -> &'a str { x )"),
+            DelimiterErrorKind::Mismatched {
+                expected: '}',
+                found: ')'
+            }
+        );
+    }
+
+    #[test]
+    fn comments() {
+        assert!(check("f(); // )\ng();").is_ok());
+        assert!(check("f(/* ) */);").is_ok());
+        assert!(check("f(/* /* ) */ } */);").is_ok());
+        assert_eq!(kind("f(/* /* */ */"), DelimiterErrorKind::Unclosed('('));
+    }
+
+    #[test]
+    fn unexpected() {
+        let error = check("f()\n  )").unwrap_err();
+        assert_eq!(*error.kind(), DelimiterErrorKind::Unexpected(')'));
+        assert_eq!((error.line(), error.column()), (2, 3));
+        assert_eq!(error.to_string(), "unexpected `)` at 2:3");
+    }
+
+    #[test]
+    fn mismatched() {
+        let error = check("{ f(] }").unwrap_err();
+        assert_eq!(
+            *error.kind(),
+            DelimiterErrorKind::Mismatched {
+                expected: ')',
+                found: ']'
+            }
+        );
+        assert_eq!((error.line(), error.column()), (1, 5));
+    }
+
+    #[test]
+    fn unclosed() {
+        let error = check("fn f() {\n    [1, 2\n}").unwrap_err();
+        assert_eq!(
+            *error.kind(),
+            DelimiterErrorKind::Mismatched {
+                expected: ']',
+                found: '}'
+            }
+        );
+        let error = check("fn f() {\n    g(").unwrap_err();
+        assert_eq!(*error.kind(), DelimiterErrorKind::Unclosed('('));
+        assert_eq!((error.line(), error.column()), (2, 6));
+    }
+
+    #[test]
+    fn dedent() {
+        let raw = Raw::new("\n\n        if a {\n            b();\n\n        }  \n\n".into());
+        assert_eq!(raw.dedented(), "if a {\n    b();\n\n}");
+        assert_eq!(Raw::new("  \n \n".into()).dedented(), "");
+    }
+
+    #[test]
+    fn dedent_common_prefix() {
+        let raw = Raw::new("\t  a\n\t b\n\t   c".into());
+        assert_eq!(raw.dedented(), " a\nb\n  c");
+    }
+
+    #[test]
+    fn multiline_strings() {
+        let raw = Raw::new(
+            "    let a = \"x\n  y  \n\";\n    let b = r#\"\n(\"#;\n    let c = '\"';".into(),
+        );
+        assert_eq!(
+            raw.dedented(),
+            "let a = \"x\n  y  \n\";\nlet b = r#\"\n(\"#;\nlet c = '\"';"
+        );
+        let mut code = String::new();
+        let mut fmt = crate::generator::Formatter::new(&mut code);
+        fmt.write_block(|fmt| raw.generate(fmt));
+        assert_eq!(
+            code,
+            "{\n    let a = \"x\n  y  \n\";\n    let b = r#\"\n(\"#;\n    let c = '\"';\n}\n"
+        );
+    }
+
+    #[test]
+    fn reindent() {
+        let mut code = String::new();
+        let mut fmt = crate::generator::Formatter::new(&mut code);
+        fmt.write_block(|fmt| {
+            Raw::new("    let a = 1;\n    if a {\n        b();\n    }".into()).generate(fmt);
+        });
+        assert_eq!(
+            code,
+            "{\n    let a = 1;\n    if a {\n        b();\n    }\n}\n"
+        );
+    }
+}
@@ -1,15 +1,28 @@
-use std::fmt::Write;
+use std::{
+    fmt::Write,
+    hash::{Hash, Hasher},
+};
 
-use crate::{expr::Expr, generator::GenerateCode, Str};
+use crate::{
+    expr::{self, Expr, Operator},
+    generator::GenerateCode,
+    Str,
+};
 
+use super::{raw::Raw, user_region::UserRegion};
+
+#[derive(Clone, Debug)]
 pub struct Block {
-    statements: Vec<Statement>,
+    pub(crate) statements: Vec<Statement>,
+    /// Names returned by [`Block::fresh`] or reserved with [`Block::reserve`]
+    pub(crate) reserved: Vec<Str>,
 }
 
 impl Block {
     pub fn new() -> Self {
         Self {
             statements: Vec::new(),
+            reserved: Vec::new(),
         }
     }
 
@@ -17,6 +30,83 @@ impl Block {
         self.statements.push(statement);
         self
     }
+
+    /// Adds an expression followed by a semicolon
+    pub fn add_expr(&mut self, expr: impl Into<Expr>) -> &mut ExprStatement {
+        push_mut!(@[Statement::Expr] self.statements, Box::new(ExprStatement::new(expr.into())))
+    }
+
+    /// Adds an expression without a semicolon, which is the value of the block
+    pub fn add_tail(&mut self, expr: impl Into<Expr>) -> &mut ExprStatement {
+        let statement = self.add_expr(expr);
+        statement.set_semicolon(false);
+        statement
+    }
+
+    /// Adds `let name = value;`, see [`fresh`](Self::fresh) for names that cannot collide
+    pub fn add_let(&mut self, name: impl Into<Str>, value: impl Into<Expr>) -> &mut LetStatement {
+        push_mut!(@[Statement::Let] self.statements, Box::new(LetStatement::new(name.into(), value.into())))
+    }
+
+    /// Adds an assignment `target = value;`
+    pub fn add_assign(
+        &mut self,
+        target: impl Into<Expr>,
+        value: impl Into<Expr>,
+    ) -> &mut AssignStatement {
+        push_mut!(@[Statement::Assign] self.statements, Box::new(AssignStatement::new(target.into(), value.into())))
+    }
+
+    pub fn add_if(&mut self, condition: impl Into<Expr>) -> &mut IfStatement {
+        push_mut!(@[Statement::If] self.statements, Box::new(IfStatement::new(condition.into())))
+    }
+
+    /// Adds verbatim code, see [`Raw`]
+    pub fn add_raw(&mut self, code: impl Into<Str>) -> &mut Raw {
+        push_mut!(@[Statement::Raw] self.statements, Box::new(Raw::new(code.into())))
+    }
+
+    /// Adds a hand-written region, see [`UserRegion`]
+    pub fn add_user_region(&mut self, name: impl Into<Str>) -> &mut UserRegion {
+        push_mut!(@[Statement::UserRegion] self.statements, Box::new(UserRegion::new(name.into())))
+    }
+
+    pub fn statements(&self) -> &[Statement] {
+        &self.statements
+    }
+
+    pub fn statements_mut(&mut self) -> &mut [Statement] {
+        &mut self.statements
+    }
+
+    /// Inserts a statement at `index`, shifting all statements after it
+    pub fn insert_at(&mut self, index: usize, statement: Statement) -> &mut Statement {
+        self.statements.insert(index, statement);
+        &mut self.statements[index]
+    }
+
+    pub fn remove(&mut self, index: usize) -> Statement {
+        self.statements.remove(index)
+    }
+
+    pub fn retain(&mut self, keep: impl FnMut(&Statement) -> bool) {
+        self.statements.retain(keep);
+    }
+}
+
+/// Only compares the statements, reserved names do not affect the generated code
+impl PartialEq for Block {
+    fn eq(&self, other: &Self) -> bool {
+        self.statements == other.statements
+    }
+}
+
+impl Eq for Block {}
+
+impl Hash for Block {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.statements.hash(state);
+    }
 }
 
 impl Default for Block {
@@ -36,9 +126,15 @@ impl GenerateCode for Block {
 }
 
 #[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Statement {
     Let(Box<LetStatement>),
     Return(Box<ReturnStatement>),
+    Expr(Box<ExprStatement>),
+    Assign(Box<AssignStatement>),
+    If(Box<IfStatement>),
+    Raw(Box<Raw>),
+    UserRegion(Box<UserRegion>),
 }
 
 impl GenerateCode for Statement {
@@ -46,14 +142,20 @@ impl GenerateCode for Statement {
         match self {
             Statement::Let(value) => value.generate(fmt),
             Statement::Return(value) => value.generate(fmt),
+            Statement::Expr(value) => value.generate(fmt),
+            Statement::Assign(value) => value.generate(fmt),
+            Statement::If(value) => value.generate(fmt),
+            Statement::Raw(value) => value.generate(fmt),
+            Statement::UserRegion(value) => value.generate(fmt),
         }
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct LetStatement {
-    mutable: bool,
-    name: Str,
-    value: Expr,
+    pub(crate) mutable: bool,
+    pub(crate) name: Str,
+    pub(crate) value: Expr,
 }
 
 impl LetStatement {
@@ -69,6 +171,22 @@ impl LetStatement {
         self.mutable = mutable;
         self
     }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_mut(&self) -> bool {
+        self.mutable
+    }
+
+    pub fn value(&self) -> &Expr {
+        &self.value
+    }
+
+    pub fn value_mut(&mut self) -> &mut Expr {
+        &mut self.value
+    }
 }
 
 impl GenerateCode for LetStatement {
@@ -78,13 +196,14 @@ impl GenerateCode for LetStatement {
             _ = write!(fmt, "mut ");
         }
         _ = write!(fmt, "{} = ", self.name);
-        self.value.generate(fmt);
-        _ = write!(fmt, ";");
+        expr::generate_unparenthesized(&self.value, fmt);
+        _ = writeln!(fmt, ";");
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ReturnStatement {
-    value: Option<Expr>,
+    pub(crate) value: Option<Expr>,
 }
 
 impl ReturnStatement {
@@ -95,6 +214,14 @@ impl ReturnStatement {
     pub fn new_empty() -> Self {
         Self { value: None }
     }
+
+    pub fn value(&self) -> Option<&Expr> {
+        self.value.as_ref()
+    }
+
+    pub fn value_mut(&mut self) -> Option<&mut Expr> {
+        self.value.as_mut()
+    }
 }
 
 impl GenerateCode for ReturnStatement {
@@ -102,8 +229,174 @@ impl GenerateCode for ReturnStatement {
         _ = write!(fmt, "return");
         if let Some(value) = &self.value {
             _ = write!(fmt, " ");
-            value.generate(fmt);
+            expr::generate_unparenthesized(value, fmt);
         }
         _ = writeln!(fmt, ";");
     }
 }
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ExprStatement {
+    pub(crate) expr: Expr,
+    pub(crate) semicolon: bool,
+}
+
+impl ExprStatement {
+    pub fn new(expr: Expr) -> Self {
+        Self {
+            expr,
+            semicolon: true,
+        }
+    }
+
+    /// Omit the semicolon for the value of a block
+    pub fn set_semicolon(&mut self, semicolon: bool) -> &mut Self {
+        self.semicolon = semicolon;
+        self
+    }
+
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    pub fn expr_mut(&mut self) -> &mut Expr {
+        &mut self.expr
+    }
+
+    pub fn has_semicolon(&self) -> bool {
+        self.semicolon
+    }
+}
+
+impl GenerateCode for ExprStatement {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        expr::generate_unparenthesized(&self.expr, fmt);
+        if self.semicolon {
+            _ = write!(fmt, ";");
+        }
+        _ = writeln!(fmt);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AssignStatement {
+    pub(crate) target: Expr,
+    pub(crate) operator: Option<Operator>,
+    pub(crate) value: Expr,
+}
+
+impl AssignStatement {
+    pub fn new(target: Expr, value: Expr) -> Self {
+        Self {
+            target,
+            operator: None,
+            value,
+        }
+    }
+
+    /// Makes this a compound assignment, e.g. `Operator::Or` for `a |= b`
+    pub fn set_operator(&mut self, operator: Operator) -> &mut Self {
+        self.operator = Some(operator);
+        self
+    }
+
+    pub fn operator(&self) -> Option<Operator> {
+        self.operator
+    }
+
+    pub fn target(&self) -> &Expr {
+        &self.target
+    }
+
+    pub fn target_mut(&mut self) -> &mut Expr {
+        &mut self.target
+    }
+
+    pub fn value(&self) -> &Expr {
+        &self.value
+    }
+
+    pub fn value_mut(&mut self) -> &mut Expr {
+        &mut self.value
+    }
+}
+
+impl GenerateCode for AssignStatement {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        self.target.generate(fmt);
+        match self.operator {
+            Some(operator) => _ = write!(fmt, " {}= ", operator.as_str()),
+            None => _ = write!(fmt, " = "),
+        }
+        expr::generate_unparenthesized(&self.value, fmt);
+        _ = writeln!(fmt, ";");
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IfStatement {
+    pub(crate) condition: Expr,
+    pub(crate) then: Block,
+    pub(crate) otherwise: Option<Block>,
+}
+
+impl IfStatement {
+    pub fn new(condition: Expr) -> Self {
+        Self {
+            condition,
+            then: Block::new(),
+            otherwise: None,
+        }
+    }
+
+    pub fn condition(&self) -> &Expr {
+        &self.condition
+    }
+
+    pub fn condition_mut(&mut self) -> &mut Expr {
+        &mut self.condition
+    }
+
+    pub fn then(&self) -> &Block {
+        &self.then
+    }
+
+    pub fn then_mut(&mut self) -> &mut Block {
+        &mut self.then
+    }
+
+    /// Adds an empty `else` block, a block containing only an `if` is generated as `else if`
+    pub fn add_else(&mut self) -> &mut Block {
+        self.otherwise.insert(Block::new())
+    }
+
+    pub fn otherwise(&self) -> Option<&Block> {
+        self.otherwise.as_ref()
+    }
+
+    pub fn otherwise_mut(&mut self) -> Option<&mut Block> {
+        self.otherwise.as_mut()
+    }
+
+    pub fn remove_else(&mut self) -> Option<Block> {
+        self.otherwise.take()
+    }
+}
+
+impl GenerateCode for IfStatement {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        _ = write!(fmt, "if ");
+        expr::generate_unparenthesized(&self.condition, fmt);
+        _ = write!(fmt, " ");
+        self.then.generate(fmt);
+        let Some(otherwise) = &self.otherwise else {
+            return;
+        };
+        fmt.pop_newline();
+        _ = write!(fmt, " else ");
+        match otherwise.statements.as_slice() {
+            [Statement::If(r#if)] => r#if.generate(fmt),
+            _ => otherwise.generate(fmt),
+        }
+    }
+}
@@ -2,17 +2,20 @@ use std::fmt::Write;
 
 use crate::{expr::Expr, generator::GenerateCode, Str};
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Static {
-    public: bool,
-    name: Str,
-    r#type: Str,
-    value: Expr,
+    pub(crate) public: bool,
+    pub(crate) mutable: bool,
+    pub(crate) name: Str,
+    pub(crate) r#type: Str,
+    pub(crate) value: Expr,
 }
 
 impl Static {
     pub fn new(name: Str, r#type: Str, value: Expr) -> Self {
         Self {
             public: false,
+            mutable: false,
             name,
             r#type,
             value,
@@ -23,6 +26,45 @@ impl Static {
         self.public = public;
         self
     }
+
+    pub fn set_mut(&mut self, mutable: bool) -> &mut Self {
+        self.mutable = mutable;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: impl Into<Str>) -> &mut Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn r#type(&self) -> &str {
+        &self.r#type
+    }
+
+    pub fn set_type(&mut self, r#type: impl Into<Str>) -> &mut Self {
+        self.r#type = r#type.into();
+        self
+    }
+
+    pub fn value(&self) -> &Expr {
+        &self.value
+    }
+
+    pub fn value_mut(&mut self) -> &mut Expr {
+        &mut self.value
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.public
+    }
+
+    pub fn is_mut(&self) -> bool {
+        self.mutable
+    }
 }
 
 impl GenerateCode for Static {
@@ -30,7 +72,11 @@ impl GenerateCode for Static {
         if self.public {
             _ = write!(fmt, "pub ");
         }
-        _ = write!(fmt, "static {}: {} = ", self.name, self.r#type);
+        _ = write!(fmt, "static ");
+        if self.mutable {
+            _ = write!(fmt, "mut ");
+        }
+        _ = write!(fmt, "{}: {} = ", self.name, self.r#type);
         self.value.generate(fmt);
         _ = writeln!(fmt, ";");
     }
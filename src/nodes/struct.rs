@@ -1,56 +1,220 @@
-use std::fmt::Write;
+use std::{cmp::Ordering, fmt::Write};
 
 use crate::{generator::GenerateCode, Str};
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Struct {
-    public: bool,
-    name: Str,
-    fields: Vec<Field>,
+    pub(crate) doc: Option<Str>,
+    pub(crate) attributes: Vec<Str>,
+    pub(crate) public: bool,
+    pub(crate) public_in: Option<Str>,
+    pub(crate) name: Str,
+    pub(crate) generics: Vec<Str>,
+    pub(crate) fields: Vec<Field>,
+    pub(crate) tuple: bool,
 }
 
 impl Struct {
     pub fn new(name: Str) -> Self {
         Self {
+            doc: None,
+            attributes: Vec::new(),
             public: false,
+            public_in: None,
             name,
+            generics: Vec::new(),
             fields: Vec::new(),
+            tuple: false,
         }
     }
 
     pub fn set_public(&mut self, public: bool) -> &mut Self {
         self.public = public;
+        self.public_in = None;
         self
     }
 
+    /// Restricts the visibility to `path`, e.g. `crate` for `pub(crate)`
+    pub fn set_public_in(&mut self, path: impl Into<Str>) -> &mut Self {
+        self.public = true;
+        self.public_in = Some(path.into());
+        self
+    }
+
+    /// Adds a named field, turning a tuple struct into a struct with named fields
     pub fn add_field(&mut self, name: impl Into<Str>, r#type: impl Into<Str>) -> &mut Field {
+        if self.tuple {
+            self.fields.clear();
+            self.tuple = false;
+        }
         push_mut!(self.fields, Field::new(name.into(), r#type.into()))
     }
+
+    /// Adds an unnamed field, turning the struct into a tuple struct
+    ///
+    /// The field is named by its index, so it can be accessed as `self.0`.
+    pub fn add_tuple_field(&mut self, r#type: impl Into<Str>) -> &mut Field {
+        if !self.tuple {
+            self.fields.clear();
+            self.tuple = true;
+        }
+        let name = self.fields.len().to_string();
+        push_mut!(self.fields, Field::new(name.into(), r#type.into()))
+    }
+
+    /// Returns whether this is a tuple struct like `struct Id(u64);`
+    pub fn is_tuple(&self) -> bool {
+        self.tuple
+    }
+
+    /// Adds a generic parameter with optional bounds, e.g. `T` or `T: Clone`
+    pub fn add_generic(&mut self, generic: impl Into<Str>) -> &mut Self {
+        self.generics.push(generic.into());
+        self
+    }
+
+    pub fn generics(&self) -> &[Str] {
+        &self.generics
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: impl Into<Str>) -> &mut Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.public
+    }
+
+    /// The path the visibility is restricted to, e.g. `crate` for `pub(crate)`
+    pub fn public_in(&self) -> Option<&str> {
+        self.public_in.as_deref()
+    }
+
+    /// Adds an outer attribute without `#[]`, e.g. `derive(Debug)`
+    pub fn add_attribute(&mut self, attribute: impl Into<Str>) -> &mut Self {
+        self.attributes.push(attribute.into());
+        self
+    }
+
+    pub fn attributes(&self) -> &[Str] {
+        &self.attributes
+    }
+
+    pub fn attributes_mut(&mut self) -> &mut Vec<Str> {
+        &mut self.attributes
+    }
+
+    /// Sets the doc comment, each line becomes a `///` comment
+    pub fn set_doc(&mut self, doc: impl Into<Str>) -> &mut Self {
+        self.doc = Some(doc.into());
+        self
+    }
+
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    pub fn fields_mut(&mut self) -> &mut [Field] {
+        &mut self.fields
+    }
+
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    pub fn field_mut(&mut self, name: &str) -> Option<&mut Field> {
+        self.fields.iter_mut().find(|field| field.name == name)
+    }
+
+    /// Inserts a field at `index`, shifting all fields after it
+    pub fn insert_field_at(
+        &mut self,
+        index: usize,
+        name: impl Into<Str>,
+        r#type: impl Into<Str>,
+    ) -> &mut Field {
+        self.fields
+            .insert(index, Field::new(name.into(), r#type.into()));
+        &mut self.fields[index]
+    }
+
+    pub fn remove_field(&mut self, name: &str) -> Option<Field> {
+        let index = self.fields.iter().position(|field| field.name == name)?;
+        Some(self.fields.remove(index))
+    }
+
+    pub fn sort_fields_by(&mut self, compare: impl FnMut(&Field, &Field) -> Ordering) {
+        self.fields.sort_by(compare);
+    }
 }
 
 impl GenerateCode for Struct {
     fn generate(&self, fmt: &mut crate::generator::Formatter) {
-        if self.public {
-            _ = write!(fmt, "pub ");
+        fmt.write_doc_and_attributes(self.doc.as_deref(), &self.attributes);
+        fmt.write_visibility(self.public, self.public_in.as_deref());
+        _ = write!(fmt, "struct {}", self.name);
+        fmt.write_generics(&self.generics);
+        if !self.tuple {
+            _ = write!(fmt, " ");
+            fmt.write_block(|fmt| {
+                for field in &self.fields {
+                    field.generate(fmt);
+                }
+            });
+            return;
         }
-        _ = write!(fmt, "struct {} ", self.name);
-        fmt.write_block(|fmt| {
-            for field in &self.fields {
-                field.generate(fmt);
+        // Documented fields are written on separate lines
+        if self.fields.iter().any(|field| field.doc.is_some()) {
+            _ = writeln!(fmt, "(");
+            fmt.indent(|fmt| {
+                for field in &self.fields {
+                    fmt.write_doc_and_attributes(field.doc.as_deref(), &field.attributes);
+                    field.generate_tuple(fmt);
+                    _ = writeln!(fmt, ",");
+                }
+            });
+        } else {
+            _ = write!(fmt, "(");
+            for (i, field) in self.fields.iter().enumerate() {
+                if i > 0 {
+                    _ = write!(fmt, ", ");
+                }
+                for attribute in &field.attributes {
+                    _ = write!(fmt, "#[{attribute}] ");
+                }
+                field.generate_tuple(fmt);
             }
-        });
+        }
+        _ = writeln!(fmt, ");");
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Field {
-    public: bool,
-    name: Str,
-    r#type: Str,
+    pub(crate) doc: Option<Str>,
+    pub(crate) attributes: Vec<Str>,
+    pub(crate) public: bool,
+    pub(crate) public_in: Option<Str>,
+    pub(crate) name: Str,
+    pub(crate) r#type: Str,
 }
 
 impl Field {
     pub fn new(name: Str, r#type: Str) -> Self {
         Self {
+            doc: None,
+            attributes: Vec::new(),
             public: false,
+            public_in: None,
             name,
             r#type,
         }
@@ -58,15 +222,81 @@ impl Field {
 
     pub fn set_public(&mut self, public: bool) -> &mut Self {
         self.public = public;
+        self.public_in = None;
+        self
+    }
+
+    /// Restricts the visibility to `path`, e.g. `crate` for `pub(crate)`
+    pub fn set_public_in(&mut self, path: impl Into<Str>) -> &mut Self {
+        self.public = true;
+        self.public_in = Some(path.into());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: impl Into<Str>) -> &mut Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn r#type(&self) -> &str {
+        &self.r#type
+    }
+
+    pub fn set_type(&mut self, r#type: impl Into<Str>) -> &mut Self {
+        self.r#type = r#type.into();
         self
     }
+
+    pub fn is_public(&self) -> bool {
+        self.public
+    }
+
+    /// The path the visibility is restricted to, e.g. `crate` for `pub(crate)`
+    pub fn public_in(&self) -> Option<&str> {
+        self.public_in.as_deref()
+    }
+
+    /// Adds an outer attribute without `#[]`, e.g. `serde(default)`
+    pub fn add_attribute(&mut self, attribute: impl Into<Str>) -> &mut Self {
+        self.attributes.push(attribute.into());
+        self
+    }
+
+    pub fn attributes(&self) -> &[Str] {
+        &self.attributes
+    }
+
+    pub fn attributes_mut(&mut self) -> &mut Vec<Str> {
+        &mut self.attributes
+    }
+
+    /// Sets the doc comment, each line becomes a `///` comment
+    pub fn set_doc(&mut self, doc: impl Into<Str>) -> &mut Self {
+        self.doc = Some(doc.into());
+        self
+    }
+
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+}
+
+impl Field {
+    /// Writes the visibility and type of a tuple struct field
+    fn generate_tuple(&self, fmt: &mut crate::generator::Formatter) {
+        fmt.write_visibility(self.public, self.public_in.as_deref());
+        _ = write!(fmt, "{}", self.r#type);
+    }
 }
 
 impl GenerateCode for Field {
     fn generate(&self, fmt: &mut crate::generator::Formatter) {
-        if self.public {
-            _ = write!(fmt, "pub ");
-        }
+        fmt.write_doc_and_attributes(self.doc.as_deref(), &self.attributes);
+        fmt.write_visibility(self.public, self.public_in.as_deref());
         _ = writeln!(fmt, "{}: {},", self.name, self.r#type);
     }
 }
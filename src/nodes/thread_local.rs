@@ -0,0 +1,86 @@
+use std::fmt::Write;
+
+use crate::{expr::Expr, generator::GenerateCode, Str};
+
+use super::r#static::Static;
+
+/// A `thread_local!` invocation declaring one or more statics
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ThreadLocal {
+    pub(crate) statics: Vec<Static>,
+}
+
+impl ThreadLocal {
+    pub fn new() -> Self {
+        Self {
+            statics: Vec::new(),
+        }
+    }
+
+    pub fn add_static(
+        &mut self,
+        name: impl Into<Str>,
+        r#type: impl Into<Str>,
+        value: impl Into<Expr>,
+    ) -> &mut Static {
+        push_mut!(
+            self.statics,
+            Static::new(name.into(), r#type.into(), value.into())
+        )
+    }
+
+    pub fn statics(&self) -> &[Static] {
+        &self.statics
+    }
+
+    pub fn statics_mut(&mut self) -> &mut Vec<Static> {
+        &mut self.statics
+    }
+}
+
+impl Default for ThreadLocal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GenerateCode for ThreadLocal {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        _ = write!(fmt, "thread_local! ");
+        fmt.write_block(|fmt| {
+            for static_ in &self.statics {
+                static_.generate(fmt);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr;
+
+    #[test]
+    fn generate() {
+        let mut thread_local = ThreadLocal::new();
+        thread_local.add_static(
+            "COUNT",
+            "std::cell::Cell<u32>",
+            expr::call("std::cell::Cell::new").arg(expr::u32(0)),
+        );
+        thread_local
+            .add_static(
+                "NAME",
+                "std::cell::RefCell<String>",
+                expr::call("std::cell::RefCell::new").arg(expr::call("String::new")),
+            )
+            .set_public(true);
+        assert_eq!(
+            thread_local.to_code_string(),
+            "thread_local! {\n    \
+             static COUNT: std::cell::Cell<u32> = std::cell::Cell::new(0u32);\n    \
+             pub static NAME: std::cell::RefCell<String> = std::cell::RefCell::new(String::new());\n\
+             }\n"
+        );
+    }
+}
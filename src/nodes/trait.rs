@@ -1,13 +1,14 @@
-use std::fmt::Write;
+use std::{cmp::Ordering, fmt::Write};
 
 use crate::{generator::GenerateCode, Str};
 
-use super::function::Function;
+use super::{function::Function, raw::Raw};
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Trait {
-    public: bool,
-    name: Str,
-    functions: Vec<Function>,
+    pub(crate) public: bool,
+    pub(crate) name: Str,
+    pub(crate) nodes: Vec<TraitNode>,
 }
 
 impl Trait {
@@ -15,7 +16,7 @@ impl Trait {
         Self {
             public: false,
             name,
-            functions: Vec::new(),
+            nodes: Vec::new(),
         }
     }
 
@@ -25,7 +26,74 @@ impl Trait {
     }
 
     pub fn add_function(&mut self, name: impl Into<Str>) -> &mut Function {
-        push_mut!(self.functions, Function::new(name.into()))
+        push_mut!(@[TraitNode::Function] self.nodes, Function::new(name.into()))
+    }
+
+    /// Adds verbatim code, see [`Raw`]
+    pub fn add_raw(&mut self, code: impl Into<Str>) -> &mut Raw {
+        push_mut!(@[TraitNode::Raw] self.nodes, Raw::new(code.into()))
+    }
+
+    /// Adds an already constructed node
+    pub fn add_node(&mut self, node: TraitNode) -> &mut TraitNode {
+        push_mut!(self.nodes, node)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: impl Into<Str>) -> &mut Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.public
+    }
+
+    pub fn nodes(&self) -> &[TraitNode] {
+        &self.nodes
+    }
+
+    pub fn nodes_mut(&mut self) -> &mut [TraitNode] {
+        &mut self.nodes
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = &Function> {
+        self.nodes.iter().filter_map(|node| match node {
+            TraitNode::Function(function) => Some(function),
+            _ => None,
+        })
+    }
+
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions().find(|function| function.name() == name)
+    }
+
+    pub fn function_mut(&mut self, name: &str) -> Option<&mut Function> {
+        self.nodes.iter_mut().find_map(|node| match node {
+            TraitNode::Function(function) if function.name() == name => Some(function),
+            _ => None,
+        })
+    }
+
+    /// Inserts a node at `index`, shifting all nodes after it
+    pub fn insert_at(&mut self, index: usize, node: TraitNode) -> &mut TraitNode {
+        self.nodes.insert(index, node);
+        &mut self.nodes[index]
+    }
+
+    pub fn remove(&mut self, index: usize) -> TraitNode {
+        self.nodes.remove(index)
+    }
+
+    pub fn sort_by(&mut self, compare: impl FnMut(&TraitNode, &TraitNode) -> Ordering) {
+        self.nodes.sort_by(compare);
+    }
+
+    pub fn retain(&mut self, keep: impl FnMut(&TraitNode) -> bool) {
+        self.nodes.retain(keep);
     }
 }
 
@@ -36,12 +104,58 @@ impl GenerateCode for Trait {
         }
         _ = write!(fmt, "trait {} ", self.name);
         fmt.write_block(|fmt| {
-            for (i, function) in self.functions.iter().enumerate() {
+            for (i, node) in self.nodes.iter().enumerate() {
                 if i > 0 {
                     _ = writeln!(fmt);
                 }
-                function.generate(fmt);
+                node.generate(fmt);
             }
         });
     }
 }
+
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TraitNode {
+    Function(Function),
+    Raw(Raw),
+}
+
+impl GenerateCode for TraitNode {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        match self {
+            TraitNode::Function(function) => function.generate(fmt),
+            TraitNode::Raw(raw) => raw.generate(fmt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editing() {
+        let mut r#trait = Trait::new("Shape".into());
+        r#trait.add_function("b");
+        r#trait.add_raw("// raw");
+        r#trait.add_function("a");
+
+        assert!(r#trait.function("a").is_some());
+        assert!(r#trait.function("missing").is_none());
+        r#trait.function_mut("a").unwrap().set_return_type("f32");
+        assert_eq!(r#trait.functions().count(), 2);
+
+        r#trait.retain(|node| matches!(node, TraitNode::Function(_)));
+        r#trait.sort_by(|a, b| match (a, b) {
+            (TraitNode::Function(a), TraitNode::Function(b)) => a.name().cmp(b.name()),
+            _ => Ordering::Equal,
+        });
+        r#trait.insert_at(0, TraitNode::Raw(Raw::new("// first".into())));
+        assert!(matches!(r#trait.remove(2), TraitNode::Function(_)));
+        assert_eq!(
+            r#trait.to_code_string(),
+            "trait Shape {\n    // first\n\n    fn a() -> f32;\n}\n"
+        );
+    }
+}
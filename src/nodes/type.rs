@@ -0,0 +1,85 @@
+use std::fmt::Write;
+
+use crate::{generator::GenerateCode, Str};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TypeAlias {
+    pub(crate) public: bool,
+    pub(crate) name: Str,
+    pub(crate) generics: Vec<Str>,
+    pub(crate) r#type: Str,
+}
+
+impl TypeAlias {
+    pub fn new(name: Str, r#type: Str) -> Self {
+        Self {
+            public: false,
+            name,
+            generics: Vec::new(),
+            r#type,
+        }
+    }
+
+    pub fn set_public(&mut self, public: bool) -> &mut Self {
+        self.public = public;
+        self
+    }
+
+    /// Adds a generic parameter, e.g. `T` or `'a`
+    pub fn add_generic(&mut self, generic: impl Into<Str>) -> &mut Self {
+        self.generics.push(generic.into());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: impl Into<Str>) -> &mut Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn r#type(&self) -> &str {
+        &self.r#type
+    }
+
+    pub fn set_type(&mut self, r#type: impl Into<Str>) -> &mut Self {
+        self.r#type = r#type.into();
+        self
+    }
+
+    pub fn generics(&self) -> &[Str] {
+        &self.generics
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.public
+    }
+}
+
+impl GenerateCode for TypeAlias {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        if self.public {
+            _ = write!(fmt, "pub ");
+        }
+        _ = write!(fmt, "type {}", self.name);
+        fmt.write_generics(&self.generics);
+        _ = writeln!(fmt, " = {};", self.r#type);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate() {
+        let mut alias = TypeAlias::new("Result".into(), "std::result::Result<T, Error>".into());
+        alias.set_public(true).add_generic("T");
+        assert_eq!(
+            alias.to_code_string(),
+            "pub type Result<T> = std::result::Result<T, Error>;\n"
+        );
+    }
+}
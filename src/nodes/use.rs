@@ -0,0 +1,41 @@
+use std::fmt::Write;
+
+use crate::{generator::GenerateCode, Str};
+
+/// A `use` declaration, the path may contain a use tree such as `std::{fmt, io}`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Use {
+    pub(crate) public: bool,
+    pub(crate) path: Str,
+}
+
+impl Use {
+    pub fn new(path: Str) -> Self {
+        Self {
+            public: false,
+            path,
+        }
+    }
+
+    pub fn set_public(&mut self, public: bool) -> &mut Self {
+        self.public = public;
+        self
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.public
+    }
+}
+
+impl GenerateCode for Use {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        if self.public {
+            _ = write!(fmt, "pub ");
+        }
+        _ = writeln!(fmt, "use {};", self.path);
+    }
+}
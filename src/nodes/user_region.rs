@@ -0,0 +1,48 @@
+use std::fmt::Write;
+
+use crate::{generator::GenerateCode, regions, Str};
+
+use super::raw::Raw;
+
+/// A region whose contents are written by hand and kept when the code is regenerated
+///
+/// The region is delimited by `// codegen:user-begin(name)` and `// codegen:user-end(name)`
+/// markers, see [`regions`](crate::regions) for how existing contents are preserved.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct UserRegion {
+    pub(crate) name: Str,
+    pub(crate) default: Option<Raw>,
+}
+
+impl UserRegion {
+    pub fn new(name: Str) -> Self {
+        Self {
+            name,
+            default: None,
+        }
+    }
+
+    /// Set the code that is used when there are no existing contents
+    pub fn set_default(&mut self, code: impl Into<Str>) -> &mut Self {
+        self.default = Some(Raw::new(code.into()));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn default(&self) -> Option<&Raw> {
+        self.default.as_ref()
+    }
+}
+
+impl GenerateCode for UserRegion {
+    fn generate(&self, fmt: &mut crate::generator::Formatter) {
+        _ = writeln!(fmt, "{}", regions::begin_marker(&self.name));
+        if let Some(default) = &self.default {
+            default.generate(fmt);
+        }
+        _ = writeln!(fmt, "{}", regions::end_marker(&self.name));
+    }
+}
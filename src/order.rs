@@ -0,0 +1,193 @@
+//! Deterministic ordering of module items
+//!
+//! Items are normally generated in the order they were added. [`Module::sort_canonical`] orders
+//! them by kind and name instead, so the output does not depend on how the tree was built.
+
+use crate::{
+    generator::GenerateCode,
+    nodes::{Module, ModuleNode},
+    parse::{self, ItemHeader},
+};
+
+/// The groups in canonical order
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Group {
+    /// Inner attributes and module docs before the first item
+    Header,
+    Imports,
+    /// Macros can only be used after their definition
+    Macros,
+    Values,
+    Types,
+    Traits,
+    /// Impls of types that are not defined in the module
+    ForeignImpls,
+    Functions,
+    Submodules,
+}
+
+type Key = (Group, String, u8, String);
+
+impl Module {
+    /// Sorts the items of this module and all submodules into a canonical order
+    ///
+    /// The order is `use` declarations and extern crates, `macro_rules!`, consts and statics,
+    /// types each followed by their impls, traits, impls of foreign types, functions and
+    /// submodules. Within each group items are sorted by name, inherent impls come before trait
+    /// impls. Raw code is sorted by the item it contains, raw code that is not an item stays
+    /// after the node before it and raw code before the first item stays at the top.
+    pub fn sort_canonical(&mut self) {
+        let headers: Vec<Option<ItemHeader>> = self
+            .nodes()
+            .iter()
+            .map(|node| match node {
+                ModuleNode::Raw(raw) => parse::item_header(raw.code()),
+                _ => None,
+            })
+            .collect();
+        let types: Vec<&str> = self
+            .nodes()
+            .iter()
+            .zip(&headers)
+            .filter_map(|(node, header)| match (node, header) {
+                (ModuleNode::Struct(_) | ModuleNode::Enum(_) | ModuleNode::TypeAlias(_), _) => {
+                    node.name()
+                }
+                (_, Some(header))
+                    if matches!(&*header.keyword, "struct" | "enum" | "union" | "type") =>
+                {
+                    Some(&header.name)
+                }
+                _ => None,
+            })
+            .collect();
+        let header = self
+            .nodes()
+            .iter()
+            .take_while(|node| matches!(node, ModuleNode::Raw(_)))
+            .count();
+        // Ties are broken by the generated code, the sort is stable for identical items
+        let mut keys: Vec<(Key, String, usize)> = Vec::with_capacity(headers.len());
+        for (index, (node, item_header)) in self.nodes().iter().zip(&headers).enumerate() {
+            let key = match (index < header, item_header) {
+                (true, _) => Some((Group::Header, String::new(), 0, String::new())),
+                (false, Some(item_header)) => Some(header_key(item_header, &types)),
+                (false, None) => key(node, &types),
+            };
+            keys.push(match (key, keys.last()) {
+                (Some(key), _) => (key, node.to_code_string(), 0),
+                (None, Some((key, code, n))) => (key.clone(), code.clone(), n + 1),
+                (None, None) => unreachable!("raw code at the start is part of the header"),
+            });
+        }
+        let mut nodes: Vec<_> = keys.into_iter().zip(self.nodes.drain(..)).collect();
+        nodes.sort_by(|(a, _), (b, _)| a.cmp(b));
+        self.nodes = nodes.into_iter().map(|(_, node)| node).collect();
+        for node in self.nodes_mut() {
+            if let ModuleNode::Submodule(submodule) = node {
+                submodule.module_mut().sort_canonical();
+            }
+        }
+    }
+}
+
+/// Returns the sort key of a node, `None` for raw code
+fn key(node: &ModuleNode, types: &[&str]) -> Option<Key> {
+    let name = node.name().unwrap_or_default().to_owned();
+    Some(match node {
+        ModuleNode::Use(r#use) => (Group::Imports, r#use.path().to_owned(), 0, String::new()),
+        ModuleNode::ExternCrate(_) => (Group::Imports, String::new(), 1, name),
+        ModuleNode::MacroRules(_) => (Group::Macros, name, 0, String::new()),
+        ModuleNode::Const(_) => (Group::Values, name, 0, String::new()),
+        ModuleNode::Static(_) => (Group::Values, name, 1, String::new()),
+        ModuleNode::ThreadLocal(_) => (Group::Values, String::new(), 2, String::new()),
+        ModuleNode::ExternBlock(block) => (
+            Group::Values,
+            String::new(),
+            3,
+            block.abi().unwrap_or_default().to_owned(),
+        ),
+        ModuleNode::Struct(_) | ModuleNode::Enum(_) | ModuleNode::TypeAlias(_) => {
+            (Group::Types, name, 0, String::new())
+        }
+        ModuleNode::Impl(r#impl) => impl_key(r#impl.target(), r#impl.r#trait(), types),
+        ModuleNode::Trait(_) => (Group::Traits, name, 0, String::new()),
+        ModuleNode::Function(_) => (Group::Functions, name, 0, String::new()),
+        ModuleNode::Submodule(_) => (Group::Submodules, name, 0, String::new()),
+        ModuleNode::Raw(_) => return None,
+    })
+}
+
+/// Returns the sort key of raw code by the item it contains, like the key of the parsed node
+fn header_key(header: &ItemHeader, types: &[&str]) -> Key {
+    let name = header.name.clone();
+    match &*header.keyword {
+        "use" => (Group::Imports, name, 0, String::new()),
+        "extern" => (Group::Imports, String::new(), 1, name),
+        "macro_rules" => (Group::Macros, name, 0, String::new()),
+        "const" => (Group::Values, name, 0, String::new()),
+        "static" => (Group::Values, name, 1, String::new()),
+        "struct" | "enum" | "union" | "type" => (Group::Types, name, 0, String::new()),
+        "impl" => impl_key(&header.name, header.r#trait.as_deref(), types),
+        "trait" => (Group::Traits, name, 0, String::new()),
+        "mod" => (Group::Submodules, name, 0, String::new()),
+        // `fn`
+        _ => (Group::Functions, name, 0, String::new()),
+    }
+}
+
+/// Impls follow the type they are for, or go into [`Group::ForeignImpls`]
+fn impl_key(target: &str, r#trait: Option<&str>, types: &[&str]) -> Key {
+    let target = base_name(target);
+    let group = match types.contains(&target) {
+        true => Group::Types,
+        false => Group::ForeignImpls,
+    };
+    match r#trait {
+        None => (group, target.to_owned(), 1, String::new()),
+        Some(r#trait) => (group, target.to_owned(), 2, r#trait.to_owned()),
+    }
+}
+
+/// Strips generic arguments and references from an impl target, e.g. `&'a Foo<T>` -> `Foo`
+fn base_name(target: &str) -> &str {
+    let target = target.split('<').next().unwrap_or(target).trim();
+    let target = target.trim_start_matches('&');
+    let target = match target.starts_with('\'') {
+        true => target.split_once(' ').map_or(target, |(_, rest)| rest),
+        false => target,
+    };
+    target.trim_start_matches("mut ").trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::parse_module;
+
+    fn sorted(code: &str) -> String {
+        let mut module = parse_module(code).unwrap();
+        module.sort_canonical();
+        module.to_string()
+    }
+
+    #[test]
+    fn raw_items() {
+        // The struct is raw because of its `where` clause, its impl still follows it
+        assert_eq!(
+            sorted(
+                "fn f() {}\n\nimpl<T> A<T> {}\n\nstruct A<T> where T: Copy {\n    x: T,\n}\n\n\
+                 impl B for A<u8> {}\n\nconst C: u8 = 1;\n"
+            ),
+            "const C: u8 = 1;\n\nstruct A<T> where T: Copy {\n    x: T,\n}\n\n\
+             impl<T> A<T> {\n}\n\nimpl B for A<u8> {\n}\n\nfn f() {\n}\n"
+        );
+    }
+
+    #[test]
+    fn raw_code_stays_in_place() {
+        assert_eq!(
+            sorted("//! Header\n\nfn b() {}\n\nfoo!(b);\n\nfn a() {}\n\nfoo!(a);\n\nbar!();\n"),
+            "//! Header\n\nfn a() {\n}\n\nfoo!(a);\n\nbar!();\n\nfn b() {\n}\n\nfoo!(b);\n"
+        );
+    }
+}
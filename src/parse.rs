@@ -0,0 +1,520 @@
+//! Parsing Rust source code into a [`Module`]
+//!
+//! The parser understands a reasonable subset of Rust: items, their signatures, simple function
+//! bodies and literals. Doc comments and outer attributes of structs, enums, functions and their
+//! fields are kept in the nodes. Anything it cannot model, such as `where` clauses or comments
+//! inside of items, is kept verbatim as [`Raw`](crate::nodes::raw::Raw) nodes, so parsing and
+//! generating a module keeps all of its code.
+//!
+//! ```
+//! use codegen_rs::parse::parse_module;
+//!
+//! let mut module = parse_module("pub struct A {\n    x: u8,\n}\n").unwrap();
+//! module.add_struct("B");
+//! assert_eq!(module.to_string(), "pub struct A {\n    x: u8,\n}\n\nstruct B {\n}\n");
+//! ```
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::nodes::Module;
+
+use self::lexer::{Lexer, Token, TokenKind};
+
+mod expr;
+mod item;
+pub(crate) mod lexer;
+
+/// Parses source code into a module
+pub fn parse_module(source: &str) -> Result<Module, ParseError> {
+    let (tokens, comments) = Lexer::new(source).tokenize()?;
+    let mut module = Module::new();
+    let cursor = Cursor::new(source, &tokens, 0, tokens.len());
+    item::ItemParser::new(source, &comments).items(cursor, &mut module);
+    Ok(module)
+}
+
+/// Parses a single expression, `placeholders` are substituted for `$name`
+pub(crate) fn parse_expr(
+    source: &str,
+    placeholders: &[(&str, crate::expr::Expr)],
+) -> Result<crate::expr::Expr, ParseError> {
+    let (tokens, _) = Lexer::new(source).tokenize()?;
+    let mut cursor = Cursor::new(source, &tokens, 0, tokens.len());
+    let expr = expr::ExprParser::new(placeholders).expr(&mut cursor)?;
+    match cursor.peek() {
+        Some(token) => Err(cursor.error(format!("unexpected `{}`", cursor.text(token)))),
+        None => Ok(expr),
+    }
+}
+
+/// The keyword and name of an item, see [`item_header`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ItemHeader {
+    /// `struct`, `fn`, `impl`, `macro_rules` etc.
+    pub(crate) keyword: String,
+    /// The name of the item, or the target of an impl
+    pub(crate) name: String,
+    /// The trait of an impl
+    pub(crate) r#trait: Option<String>,
+}
+
+/// Finds the keyword and name of the item at the start of raw code
+///
+/// Attributes, visibility and qualifiers like `unsafe` are skipped, `None` is returned for code
+/// that does not start with an item, e.g. a macro invocation.
+pub(crate) fn item_header(source: &str) -> Option<ItemHeader> {
+    let (tokens, _) = Lexer::new(source).tokenize().ok()?;
+    let mut cursor = Cursor::new(source, &tokens, 0, tokens.len());
+    while cursor.eat("#") {
+        cursor.group("[")?;
+    }
+    if cursor.eat("pub") {
+        _ = cursor.group("(");
+    }
+    // `const` and `extern` are only qualifiers if a function follows
+    let is_function = |cursor: &Cursor| {
+        cursor.peek_nth(1).is_some_and(|token| {
+            token.kind == TokenKind::Literal
+                || matches!(cursor.text(token), "fn" | "async" | "unsafe" | "extern")
+        })
+    };
+    while cursor.is("default")
+        || cursor.is("async")
+        || cursor.is("unsafe")
+        || (cursor.is("const") || cursor.is("extern")) && is_function(&cursor)
+        || cursor
+            .peek()
+            .is_some_and(|token| token.kind == TokenKind::Literal)
+    {
+        cursor.bump();
+    }
+    let keyword = cursor.ident()?;
+    let header = |name: &str, r#trait: Option<&str>| ItemHeader {
+        keyword: keyword.to_owned(),
+        name: name.split_whitespace().collect::<Vec<_>>().join(" "),
+        r#trait: r#trait.map(|r#trait| r#trait.split_whitespace().collect::<Vec<_>>().join(" ")),
+    };
+    match keyword {
+        "struct" | "enum" | "union" | "trait" | "fn" | "type" | "mod" | "const" => {
+            Some(header(cursor.ident()?, None))
+        }
+        "static" => {
+            cursor.eat("mut");
+            Some(header(cursor.ident()?, None))
+        }
+        "extern" => {
+            cursor.expect("crate")?;
+            Some(header(cursor.ident()?, None))
+        }
+        "macro_rules" => {
+            cursor.expect("!")?;
+            Some(header(cursor.ident()?, None))
+        }
+        "use" => Some(header(cursor.collect_until(|text| text == ";")?, None)),
+        "impl" => {
+            if cursor.eat("<") {
+                cursor.collect_until(|text| text == ">");
+                cursor.expect(">")?;
+            }
+            let stop = |text: &str| matches!(text, "for" | "where" | "{");
+            let first = cursor.collect_until(stop)?;
+            match cursor.eat("for") {
+                true => Some(header(cursor.collect_until(stop)?, Some(first))),
+                false => Some(header(first, None)),
+            }
+        }
+        _ => None,
+    }
+}
+
+impl FromStr for Module {
+    type Err = ParseError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        parse_module(source)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    message: String,
+    line: usize,
+    column: usize,
+}
+
+impl ParseError {
+    /// Creates an error at the byte `offset` of `src`
+    pub(crate) fn at(message: impl Into<String>, src: &str, offset: usize) -> Self {
+        let before = &src[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            message: message.into(),
+            line,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The 1-based line of the error
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The 1-based column of the error, counted in characters
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {}:{}", self.message, self.line, self.column)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A position in a range of tokens
+///
+/// Delimited groups are skipped as a whole, so a cursor only ever sees the tokens of one level.
+#[derive(Clone, Copy)]
+pub(crate) struct Cursor<'a> {
+    src: &'a str,
+    tokens: &'a [Token],
+    pos: usize,
+    end: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(src: &'a str, tokens: &'a [Token], pos: usize, end: usize) -> Self {
+        Self {
+            src,
+            tokens,
+            pos,
+            end,
+        }
+    }
+
+    fn is_end(&self) -> bool {
+        self.pos >= self.end
+    }
+
+    fn peek(&self) -> Option<&'a Token> {
+        self.peek_nth(0)
+    }
+
+    /// Returns the `n`th token after the current one, skipping groups
+    fn peek_nth(&self, n: usize) -> Option<&'a Token> {
+        let mut pos = self.pos;
+        for _ in 0..n {
+            pos = self.skip(pos);
+        }
+        (pos < self.end).then(|| &self.tokens[pos])
+    }
+
+    fn skip(&self, pos: usize) -> usize {
+        match self.tokens.get(pos) {
+            Some(token) if token.kind == TokenKind::Open => token.matching + 1,
+            _ => pos + 1,
+        }
+    }
+
+    fn bump(&mut self) -> Option<&'a Token> {
+        let token = self.peek()?;
+        self.pos = self.skip(self.pos);
+        Some(token)
+    }
+
+    fn text(&self, token: &Token) -> &'a str {
+        &self.src[token.start..token.end]
+    }
+
+    /// Checks if the current token is the punctuation or keyword `text`
+    fn is(&self, text: &str) -> bool {
+        self.is_nth(0, text)
+    }
+
+    fn is_nth(&self, n: usize, text: &str) -> bool {
+        self.peek_nth(n).is_some_and(|token| {
+            token.kind != TokenKind::Literal
+                && token.kind != TokenKind::Lifetime
+                && self.text(token) == text
+        })
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        if self.is(text) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, text: &str) -> Option<()> {
+        self.eat(text).then_some(())
+    }
+
+    fn ident(&mut self) -> Option<&'a str> {
+        let token = self.peek()?;
+        if token.kind != TokenKind::Ident {
+            return None;
+        }
+        self.bump();
+        Some(self.text(token))
+    }
+
+    /// Enters the group at the current position if it is delimited by `open`
+    fn group(&mut self, open: &str) -> Option<Cursor<'a>> {
+        let token = self.peek()?;
+        if token.kind != TokenKind::Open || self.text(token) != open {
+            return None;
+        }
+        let index = self.pos;
+        self.bump();
+        Some(Cursor::new(
+            self.src,
+            self.tokens,
+            index + 1,
+            token.matching,
+        ))
+    }
+
+    /// Byte offset of the current token, or the end of the range
+    fn offset(&self) -> usize {
+        match self.peek() {
+            Some(token) => token.start,
+            None if self.end < self.tokens.len() => self.tokens[self.end].start,
+            None => self.src.len(),
+        }
+    }
+
+    /// Byte offset after the previous token
+    fn prev_end(&self) -> usize {
+        match self.pos.checked_sub(1) {
+            Some(pos) => self.tokens[pos].end,
+            None => 0,
+        }
+    }
+
+    /// Collects tokens until `stop` returns true for a non-literal token on this level, and
+    /// returns their source text
+    ///
+    /// Angle brackets are tracked, so commas in generic arguments do not stop the collection.
+    fn collect_until(&mut self, stop: impl Fn(&str) -> bool) -> Option<&'a str> {
+        let start = self.offset();
+        let mut depth = 0usize;
+        while let Some(token) = self.peek() {
+            let text = self.text(token);
+            if depth == 0 && token.kind != TokenKind::Literal && stop(text) {
+                break;
+            }
+            match text {
+                "<" => depth += 1,
+                ">" => depth = depth.saturating_sub(1),
+                ">>" => depth = depth.saturating_sub(2),
+                _ => {}
+            }
+            self.bump();
+        }
+        let end = self.prev_end();
+        (end > start).then(|| &self.src[start..end])
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::at(message, self.src, self.offset())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nodes::{Module, ModuleNode};
+
+    use super::*;
+
+    /// Checks that the code is parsed into one node that is not raw and generated unchanged
+    fn round_trip(code: &str) -> Module {
+        let module = parse_module(code).unwrap();
+        assert!(
+            matches!(module.nodes(), [node] if !matches!(node, ModuleNode::Raw(_))),
+            "{:?}",
+            module.nodes()
+        );
+        assert_eq!(module.to_string(), code);
+        module
+    }
+
+    fn is_raw(code: &str) -> bool {
+        let module = parse_module(code).unwrap();
+        assert_eq!(module.to_string(), code);
+        matches!(module.nodes(), [ModuleNode::Raw(_)])
+    }
+
+    #[test]
+    fn attributed_struct() {
+        let mut module = round_trip(
+            r#"/// A point
+///
+/// In two dimensions.
+#[derive(Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Point<T: Copy> {
+    /// The horizontal position
+    #[serde(default)]
+    pub x: T,
+    pub(super) y: T,
+}
+"#,
+        );
+        let point = module.get_struct_mut("Point").unwrap();
+        assert_eq!(point.doc(), Some("A point\n\nIn two dimensions."));
+        assert_eq!(point.public_in(), Some("crate"));
+        assert_eq!(point.generics(), ["T: Copy"]);
+        point.field_mut("x").unwrap().set_type("f32");
+        point.add_attribute("non_exhaustive");
+        assert_eq!(
+            module.to_string(),
+            r#"/// A point
+///
+/// In two dimensions.
+#[derive(Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub(crate) struct Point<T: Copy> {
+    /// The horizontal position
+    #[serde(default)]
+    pub x: f32,
+    pub(super) y: T,
+}
+"#
+        );
+    }
+
+    #[test]
+    fn tuple_struct() {
+        let module = round_trip("pub struct Id(#[serde(skip)] pub(crate) u64, u8);\n");
+        let id = module.get_struct("Id").unwrap();
+        assert_eq!(id.fields()[0].attributes(), ["serde(skip)"]);
+        assert_eq!(id.fields()[0].public_in(), Some("crate"));
+    }
+
+    #[test]
+    fn documented_enum() {
+        let mut module = round_trip(
+            r#"/// The kind of a token
+#[derive(Debug)]
+#[repr(u8)]
+pub enum Kind<'a> {
+    /// A single character
+    #[default]
+    Char = 1,
+    Word(&'a str),
+}
+"#,
+        );
+        let kind = module.get_enum_mut("Kind").unwrap();
+        assert_eq!(
+            kind.variant("Char").unwrap().doc(),
+            Some("A single character")
+        );
+        kind.add_variant("End").set_doc("The end of the input");
+        assert_eq!(
+            module.to_string(),
+            r#"/// The kind of a token
+#[derive(Debug)]
+#[repr(u8)]
+pub enum Kind<'a> {
+    /// A single character
+    #[default]
+    Char = 1,
+    Word(&'a str),
+    /// The end of the input
+    End,
+}
+"#
+        );
+    }
+
+    #[test]
+    fn attributed_function() {
+        let mut module = round_trip(
+            r#"/// Runs the task
+#[inline]
+#[must_use]
+pub(in crate::tasks) async unsafe fn run<T>(task: T) -> T {
+    task
+}
+"#,
+        );
+        let run = module.get_function_mut("run").unwrap();
+        assert!(run.is_async() && run.is_unsafe());
+        assert_eq!(run.attributes(), ["inline", "must_use"]);
+        run.set_return_type("Option<T>");
+        assert!(module
+            .to_string()
+            .contains("pub(in crate::tasks) async unsafe fn run<T>(task: T) -> Option<T> {"));
+    }
+
+    #[test]
+    fn generic_impl() {
+        let module = round_trip(
+            r#"impl<T: Copy> Point<T> {
+    /// Creates a point
+    #[must_use]
+    pub const fn new(x: T, y: T) -> Self {
+        Self { x, y }
+    }
+}
+"#,
+        );
+        let r#impl = module.impls().next().unwrap();
+        assert_eq!(r#impl.generics(), ["T: Copy"]);
+        let new = r#impl.function("new").unwrap();
+        assert_eq!((new.doc(), new.is_const()), (Some("Creates a point"), true));
+    }
+
+    #[test]
+    fn attributed_impl_with_where_clause() {
+        let module = round_trip(
+            r#"#[cfg(test)]
+impl<K, V> Clone for Map<K, V>
+where
+    K: Clone,
+    HashMap<K, V>: Clone,
+{
+}
+"#,
+        );
+        let r#impl = module.impls().next().unwrap();
+        assert_eq!(r#impl.attributes(), ["cfg(test)"]);
+        assert_eq!(
+            r#impl.where_predicates(),
+            ["K: Clone", "HashMap<K, V>: Clone"]
+        );
+    }
+
+    #[test]
+    fn raw_fallbacks() {
+        // Docs that would not be generated the same way
+        assert!(is_raw("/** Block doc */\nstruct A;\n"));
+        assert!(is_raw("/// A\n\nstruct A;\n"));
+        assert!(is_raw("/// A\n///\nstruct A;\n"));
+        assert!(is_raw("///A\nstruct A;\n"));
+        // Comments that cannot be kept
+        assert!(is_raw("struct A {\n    a: u8, // a\n}\n"));
+        assert!(is_raw("struct A {\n    // a\n    a: u8,\n}\n"));
+        assert!(is_raw("#[derive(Debug)] // a\nstruct A;\n"));
+        // Attributes of nodes that do not keep them
+        assert!(is_raw("#[cfg(test)]\nmod tests {}\n"));
+        assert!(is_raw("#[allow(dead_code)]\nconst A: u8 = 1;\n"));
+        assert!(is_raw("#[cfg(all(\n    a,\n    b\n))]\nfn f() {}\n"));
+        // Restricted visibilities of nodes that do not support them
+        assert!(is_raw("pub(crate) const A: u8 = 1;\n"));
+        assert!(is_raw("pub(in crate) fn f() {}\n"));
+        assert!(is_raw("struct A<T> where T: Copy {\n    a: T,\n}\n"));
+    }
+}
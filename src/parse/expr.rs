@@ -0,0 +1,472 @@
+//! Parsing expressions
+
+use crate::expr::{self, Expr, Literal, Operator, Radix};
+
+use super::{lexer::TokenKind, Cursor, ParseError};
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+/// Parses an expression without placeholders
+pub(crate) fn parse_expr(cursor: &mut Cursor) -> Result<Expr> {
+    ExprParser::new(&[]).expr(cursor)
+}
+
+fn binary_operator(text: &str) -> Option<(Operator, u8)> {
+    Some(match text {
+        "||" => (Operator::DoubleOr, 1),
+        "&&" => (Operator::DoubleAnd, 2),
+        "==" => (Operator::Equals, 3),
+        "!=" => (Operator::NotEquals, 3),
+        "<" => (Operator::Less, 3),
+        "<=" => (Operator::LessEqual, 3),
+        ">" => (Operator::Greater, 3),
+        ">=" => (Operator::GreaterEqual, 3),
+        "|" => (Operator::Or, 4),
+        "^" => (Operator::Xor, 5),
+        "&" => (Operator::And, 6),
+        "<<" => (Operator::ShiftLeft, 7),
+        ">>" => (Operator::ShiftRight, 7),
+        "+" => (Operator::Plus, 8),
+        "-" => (Operator::Minus, 8),
+        "*" => (Operator::Star, 9),
+        "/" => (Operator::Slash, 9),
+        "%" => (Operator::Percent, 9),
+        _ => return None,
+    })
+}
+
+/// Parses expressions, replacing `$name` placeholders with their bound expressions
+pub(crate) struct ExprParser<'p> {
+    placeholders: &'p [(&'p str, Expr)],
+}
+
+impl<'p> ExprParser<'p> {
+    pub fn new(placeholders: &'p [(&'p str, Expr)]) -> Self {
+        Self { placeholders }
+    }
+
+    /// Parses an expression and stops at the first token that cannot continue it
+    pub fn expr(&self, cursor: &mut Cursor) -> Result<Expr> {
+        self.binary(cursor, 0)
+    }
+
+    /// Precedence climbing over binary operators that bind tighter than `min`
+    fn binary(&self, cursor: &mut Cursor, min: u8) -> Result<Expr> {
+        let mut left = self.cast(cursor)?;
+        while let Some(token) = cursor.peek() {
+            if token.kind != TokenKind::Punct {
+                break;
+            }
+            let Some((operator, precedence)) = binary_operator(cursor.text(token)) else {
+                break;
+            };
+            if precedence <= min {
+                break;
+            }
+            cursor.bump();
+            // Comparisons are not associative
+            let right = self.binary(cursor, precedence)?;
+            left = expr::BinaryOperation::new(operator, left, right).into();
+            if precedence == 3
+                && cursor.peek().is_some_and(|token| {
+                    binary_operator(cursor.text(token))
+                        .is_some_and(|(_, precedence)| precedence == 3)
+                })
+            {
+                return Err(cursor.error("comparison operators cannot be chained"));
+            }
+        }
+        Ok(left)
+    }
+
+    fn cast(&self, cursor: &mut Cursor) -> Result<Expr> {
+        let mut expr = self.unary(cursor)?;
+        while cursor.eat("as") {
+            let r#type = cursor
+                .collect_until(|text| {
+                    binary_operator(text).is_some() || matches!(text, "as" | "," | ";")
+                })
+                .ok_or_else(|| cursor.error("expected type"))?;
+            expr = expr.cast(r#type.to_owned());
+        }
+        Ok(expr)
+    }
+
+    fn unary(&self, cursor: &mut Cursor) -> Result<Expr> {
+        let operator = match cursor.peek() {
+            Some(token) if token.kind == TokenKind::Punct => match cursor.text(token) {
+                "-" => Operator::Minus,
+                "!" => Operator::Not,
+                "*" => Operator::Star,
+                "&" => Operator::And,
+                _ => return self.postfix(cursor),
+            },
+            _ => return self.postfix(cursor),
+        };
+        cursor.bump();
+        let operator = match operator {
+            Operator::And if cursor.is("mut") => {
+                cursor.bump();
+                Operator::AndMut
+            }
+            operator => operator,
+        };
+        let expr = self.unary(cursor)?;
+        if matches!(operator, Operator::Minus) {
+            if let Expr::Literal(literal) = &expr {
+                if let Some(negated) = negate(literal) {
+                    return Ok(negated.into());
+                }
+            }
+        }
+        Ok(expr::UnaryOperation::new(operator, expr).into())
+    }
+
+    /// Parses field accesses and method calls
+    fn postfix(&self, cursor: &mut Cursor) -> Result<Expr> {
+        let mut expr = self.primary(cursor)?;
+        loop {
+            match cursor.peek() {
+                Some(token) if cursor.text(token) == "." => {
+                    cursor.bump();
+                    let name = match cursor.peek() {
+                        Some(token)
+                            if token.kind == TokenKind::Ident
+                                || (token.kind == TokenKind::Literal
+                                    && cursor.text(token).bytes().all(|b| b.is_ascii_digit())) =>
+                        {
+                            cursor.bump();
+                            cursor.text(token)
+                        }
+                        _ => return Err(cursor.error("expected field or method name")),
+                    };
+                    expr = match cursor.group("(") {
+                        Some(mut args) => {
+                            let mut call = expr.method(name.to_owned());
+                            for arg in self.list(&mut args)?.0 {
+                                call = call.arg(arg);
+                            }
+                            call.into()
+                        }
+                        None => expr.field(name.to_owned()),
+                    };
+                }
+                Some(token) if matches!(cursor.text(token), "?" | "[" | "(") => {
+                    return Err(
+                        cursor.error(format!("`{}` is not supported here", cursor.text(token)))
+                    );
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn primary(&self, cursor: &mut Cursor) -> Result<Expr> {
+        let Some(token) = cursor.peek() else {
+            return Err(cursor.error("expected expression"));
+        };
+        let text = cursor.text(token);
+        match token.kind {
+            TokenKind::Literal => {
+                let literal = parse_literal(text).map_err(|message| cursor.error(message))?;
+                cursor.bump();
+                Ok(literal.into())
+            }
+            TokenKind::Ident => match text {
+                "true" | "false" => {
+                    cursor.bump();
+                    Ok(expr::bool(text == "true"))
+                }
+                "match" => self.r#match(cursor),
+                "if" | "loop" | "while" | "for" | "unsafe" | "async" | "move" | "return"
+                | "break" | "continue" | "let" => {
+                    Err(cursor.error(format!("`{text}` expressions are not supported")))
+                }
+                _ => self.path_expr(cursor),
+            },
+            TokenKind::Punct if text == "::" => self.path_expr(cursor),
+            TokenKind::Punct if text == "$" => self.placeholder(cursor),
+            TokenKind::Open => {
+                let mut group = cursor.group(text).unwrap();
+                match text {
+                    "(" => {
+                        let (values, trailing) = self.list(&mut group)?;
+                        if values.len() == 1 && !trailing {
+                            return Ok(values.into_iter().next().unwrap());
+                        }
+                        Ok(expr::tuple_of(values).into())
+                    }
+                    "[" => Ok(expr::array_of(self.list(&mut group)?.0).into()),
+                    _ => Err(cursor.error("block expressions are not supported")),
+                }
+            }
+            _ => Err(cursor.error(format!("expected expression, found `{text}`"))),
+        }
+    }
+
+    /// Parses a path, a call or a struct initializer
+    fn path_expr(&self, cursor: &mut Cursor) -> Result<Expr> {
+        let start = cursor.offset();
+        cursor.eat("::");
+        loop {
+            if cursor.ident().is_none() {
+                return Err(cursor.error("expected identifier"));
+            }
+            if cursor.is("::") && cursor.is_nth(1, "<") {
+                return Err(cursor.error("generic arguments are not supported"));
+            }
+            if !cursor.eat("::") {
+                break;
+            }
+        }
+        let path = &cursor.src[start..cursor.prev_end()];
+        if cursor.eat("!") {
+            let mut args = cursor
+                .group("(")
+                .ok_or_else(|| cursor.error("only macro invocations with `(` are supported"))?;
+            let mut call = expr::macro_call(path.to_owned());
+            for arg in self.list(&mut args)?.0 {
+                call = call.arg(arg);
+            }
+            return Ok(call.into());
+        }
+        if let Some(mut args) = cursor.group("(") {
+            let mut call = expr::call(path.to_owned());
+            for arg in self.list(&mut args)?.0 {
+                call = call.arg(arg);
+            }
+            return Ok(call.into());
+        }
+        let is_type = path
+            .rsplit("::")
+            .next()
+            .is_some_and(|name| name.starts_with(|c: char| c.is_uppercase()));
+        if is_type && cursor.peek().is_some_and(|token| cursor.text(token) == "{") {
+            let mut fields = cursor.group("{").unwrap();
+            let mut init = expr::init_struct(path.to_owned());
+            while !fields.is_end() {
+                let name = fields
+                    .ident()
+                    .ok_or_else(|| fields.error("expected field name"))?;
+                init = if fields.eat(":") {
+                    init.field(name.to_owned(), self.expr(&mut fields)?)
+                } else {
+                    init.field_auto(name.to_owned())
+                };
+                if !fields.eat(",") && !fields.is_end() {
+                    return Err(fields.error("expected `,`"));
+                }
+            }
+            return Ok(init.into());
+        }
+        Ok(expr::binding(path.to_owned()))
+    }
+
+    /// Parses a `match` with expression arms, block arms are not supported
+    fn r#match(&self, cursor: &mut Cursor) -> Result<Expr> {
+        cursor.bump();
+        let mut r#match = expr::r#match(self.expr(cursor)?);
+        let mut arms = cursor
+            .group("{")
+            .ok_or_else(|| cursor.error("expected `{`"))?;
+        while !arms.is_end() {
+            let pattern = arms
+                .collect_until(|text| matches!(text, "=>" | "if"))
+                .ok_or_else(|| arms.error("expected pattern"))?;
+            let guard = match arms.eat("if") {
+                true => Some(self.expr(&mut arms)?),
+                false => None,
+            };
+            if !arms.eat("=>") {
+                return Err(arms.error("expected `=>`"));
+            }
+            let value = self.expr(&mut arms)?;
+            let pattern = pattern.split_whitespace().collect::<Vec<_>>().join(" ");
+            r#match = match guard {
+                Some(guard) => r#match.arm_if(pattern, guard, value),
+                None => r#match.arm(pattern, value),
+            };
+            if !arms.eat(",") && !arms.is_end() {
+                return Err(arms.error("expected `,`"));
+            }
+        }
+        Ok(r#match.into())
+    }
+
+    fn placeholder(&self, cursor: &mut Cursor) -> Result<Expr> {
+        let start = *cursor;
+        cursor.bump();
+        let name = cursor
+            .ident()
+            .ok_or_else(|| cursor.error("expected placeholder name"))?;
+        self.placeholders
+            .iter()
+            .find(|(placeholder, _)| *placeholder == name)
+            .map(|(_, expr)| expr.clone())
+            .ok_or_else(|| start.error(format!("unbound placeholder `${name}`")))
+    }
+
+    /// Parses comma separated expressions, returns whether there was a trailing comma
+    fn list(&self, cursor: &mut Cursor) -> Result<(Vec<Expr>, bool)> {
+        let mut values = Vec::new();
+        let mut trailing = false;
+        while !cursor.is_end() {
+            values.push(self.expr(cursor)?);
+            trailing = cursor.eat(",");
+            if !trailing && !cursor.is_end() {
+                return Err(cursor.error("expected `,`"));
+            }
+        }
+        Ok((values, trailing))
+    }
+}
+
+/// Folds the sign into typed literals
+fn negate(literal: &Literal) -> Option<Literal> {
+    Some(match *literal {
+        Literal::I8(value) => Literal::I8(value.checked_neg()?),
+        Literal::I16(value) => Literal::I16(value.checked_neg()?),
+        Literal::I32(value) => Literal::I32(value.checked_neg()?),
+        Literal::I64(value) => Literal::I64(value.checked_neg()?),
+        Literal::I128(value) => Literal::I128(value.checked_neg()?),
+        Literal::F32(value) => Literal::F32(-value),
+        Literal::F64(value) => Literal::F64(-value),
+        _ => return None,
+    })
+}
+
+/// Parses the text of a literal token
+pub(crate) fn parse_literal(text: &str) -> std::result::Result<Literal, String> {
+    if let Some(rest) = text.strip_prefix('r') {
+        let hashes = rest.len() - rest.trim_start_matches('#').len();
+        let value = &rest[hashes + 1..rest.len() - hashes - 1];
+        return Ok(Literal::Str(value.to_owned().into()));
+    }
+    if let Some(rest) = text.strip_prefix('"') {
+        let end = rest.rfind('"').unwrap();
+        if end + 1 != rest.len() {
+            return Err("literal suffixes are not supported".into());
+        }
+        return Ok(Literal::Str(unescape(&rest[..end])?.into()));
+    }
+    if let Some(rest) = text.strip_prefix('\'') {
+        let end = rest.rfind('\'').unwrap();
+        let value = unescape(&rest[..end])?;
+        let mut chars = value.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(Literal::Char(c)),
+            _ => Err("invalid character literal".into()),
+        };
+    }
+    if !text.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err("byte and C string literals are not supported".into());
+    }
+    parse_number(text)
+}
+
+fn parse_number(text: &str) -> std::result::Result<Literal, String> {
+    let (radix, digits) = match text.get(..2) {
+        Some("0x") => (16, &text[2..]),
+        Some("0o") => (8, &text[2..]),
+        Some("0b") => (2, &text[2..]),
+        _ => (10, text),
+    };
+    let mut len = 0;
+    let mut float = false;
+    let bytes = digits.as_bytes();
+    while len < bytes.len() {
+        let c = bytes[len] as char;
+        if c == '_' || c.is_digit(radix) {
+            len += 1;
+        } else if radix == 10 && c == '.' {
+            float = true;
+            len += 1;
+        } else if radix == 10 && matches!(c, 'e' | 'E') {
+            float = true;
+            len += 1;
+            if matches!(bytes.get(len), Some(b'+' | b'-')) {
+                len += 1;
+            }
+        } else {
+            break;
+        }
+    }
+    let (value, suffix) = digits.split_at(len);
+    let value = value.replace('_', "");
+    let invalid = |_: std::num::ParseIntError| format!("invalid number `{text}`");
+    let invalid_float = |_: std::num::ParseFloatError| format!("invalid number `{text}`");
+    macro_rules! int {
+        ($variant: ident, $type: ty) => {
+            Literal::$variant(<$type>::from_str_radix(&value, radix).map_err(invalid)?)
+        };
+    }
+    let literal = match suffix {
+        "f32" if radix == 10 => Literal::F32(value.parse().map_err(invalid_float)?),
+        "f64" if radix == 10 => Literal::F64(value.parse().map_err(invalid_float)?),
+        "" if float => Literal::Float(value.parse().map_err(invalid_float)?),
+        _ if float => return Err(format!("invalid number `{text}`")),
+        "" => int!(Integer, u128),
+        "i8" => int!(I8, i8),
+        "u8" => int!(U8, u8),
+        "i16" => int!(I16, i16),
+        "u16" => int!(U16, u16),
+        "i32" => int!(I32, i32),
+        "u32" => int!(U32, u32),
+        "i64" => int!(I64, i64),
+        "u64" => int!(U64, u64),
+        "i128" => int!(I128, i128),
+        "u128" => int!(U128, u128),
+        _ => return Err(format!("unsupported literal suffix `{suffix}`")),
+    };
+    // The typed literal checks the range, the radix is kept for generating
+    let base = match radix {
+        2 => Radix::Binary,
+        8 => Radix::Octal,
+        16 => Radix::Hexadecimal,
+        _ => return Ok(literal),
+    };
+    let value = u128::from_str_radix(&value, radix).map_err(invalid)?;
+    Ok(Literal::Radix(value, base, suffix.to_owned().into()))
+}
+
+fn unescape(text: &str) -> std::result::Result<String, String> {
+    let mut dst = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            dst.push(c);
+            continue;
+        }
+        let c = match chars.next() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some(c @ ('\\' | '\'' | '"')) => c,
+            Some('x') => {
+                let code: String = chars.by_ref().take(2).collect();
+                u8::from_str_radix(&code, 16)
+                    .ok()
+                    .filter(u8::is_ascii)
+                    .ok_or_else(|| format!("invalid escape `\\x{code}`"))? as char
+            }
+            Some('u') => {
+                let code: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let code = code.trim_start_matches('{').replace('_', "");
+                u32::from_str_radix(&code, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("invalid escape `\\u{{{code}}}`"))?
+            }
+            Some('\n') => {
+                // Line continuation, skips the following whitespace
+                let rest = chars.as_str().trim_start();
+                chars = rest.chars();
+                continue;
+            }
+            _ => return Err("invalid escape".into()),
+        };
+        dst.push(c);
+    }
+    Ok(dst)
+}
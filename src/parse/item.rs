@@ -0,0 +1,1058 @@
+//! Parsing items and function bodies
+//!
+//! Every parse function returns `None` if it encounters something the node tree cannot model,
+//! the caller then keeps the source text as a raw node.
+
+use crate::{
+    expr::Operator,
+    nodes::{
+        extern_block::ExternBlock,
+        function::Function,
+        macro_rules::MacroRules,
+        r#const::Const,
+        r#enum::Enum,
+        r#impl::{Impl, ImplNode},
+        r#static::Static,
+        r#struct::Field,
+        r#struct::Struct,
+        r#trait::{Trait, TraitNode},
+        r#type::TypeAlias,
+        r#use::Use,
+        raw::Raw,
+        statement::{
+            AssignStatement, Block, ExprStatement, IfStatement, LetStatement, ReturnStatement,
+            Statement,
+        },
+        thread_local::ThreadLocal,
+        Module, ModuleNode, Submodule,
+    },
+    regions, Str,
+};
+
+use super::{
+    expr::{parse_expr, parse_literal},
+    lexer::{Token, TokenKind},
+    Cursor,
+};
+
+/// Keywords that start an item that ends with a semicolon
+const SEMICOLON_ITEMS: &[&str] = &["const", "static", "type", "use", "let"];
+
+/// Compound assignment tokens and their operators
+const ASSIGN_OPERATORS: &[(&str, Operator)] = &[
+    ("+=", Operator::Plus),
+    ("-=", Operator::Minus),
+    ("*=", Operator::Star),
+    ("/=", Operator::Slash),
+    ("%=", Operator::Percent),
+    ("^=", Operator::Xor),
+    ("&=", Operator::And),
+    ("|=", Operator::Or),
+    ("<<=", Operator::ShiftLeft),
+    (">>=", Operator::ShiftRight),
+];
+
+/// Keywords that start a statement that ends with a block
+const BLOCK_STATEMENTS: &[&str] = &["if", "match", "loop", "while", "for", "unsafe"];
+
+pub(crate) struct ItemParser<'a> {
+    src: &'a str,
+    comments: &'a [Token],
+}
+
+impl<'a> ItemParser<'a> {
+    pub fn new(src: &'a str, comments: &'a [Token]) -> Self {
+        Self { src, comments }
+    }
+
+    /// Parses all items of the cursor into the module
+    pub fn items(&self, mut cursor: Cursor<'a>, module: &mut Module) {
+        let mut last = start_offset(&cursor);
+        loop {
+            let doc = self.comments(&mut cursor, &mut last, false, &mut |member| {
+                if let Member::Raw(code) = member {
+                    module.add_raw(code);
+                }
+            });
+            if cursor.is_end() {
+                break;
+            }
+            let item = self.item_cursor(&mut cursor, item_end);
+            let (start, end) = (doc.unwrap_or(item.offset()), cursor.prev_end());
+            last = end;
+            let node = match doc {
+                Some(doc) => self.doc(doc, item.offset()).and_then(|doc| {
+                    let mut node = self.item(item)?;
+                    match &mut node {
+                        ModuleNode::Struct(r#struct) => _ = r#struct.set_doc(doc),
+                        ModuleNode::Enum(r#enum) => _ = r#enum.set_doc(doc),
+                        ModuleNode::Function(function) => _ = function.set_doc(doc),
+                        _ => return None,
+                    }
+                    Some(node)
+                }),
+                None => self.item(item),
+            };
+            match node {
+                Some(node) => _ = module.add_node(node),
+                None => _ = module.add_raw(self.raw_text(start, end)),
+            }
+        }
+    }
+
+    /// Advances the cursor past one item and returns a cursor over it
+    fn item_cursor(&self, cursor: &mut Cursor<'a>, end: impl Fn(&mut Cursor<'a>)) -> Cursor<'a> {
+        let mut item = *cursor;
+        end(cursor);
+        item.end = cursor.pos;
+        item
+    }
+
+    fn item(&self, mut cursor: Cursor<'a>) -> Option<ModuleNode> {
+        let item = cursor;
+        let attributes = attributes(&mut cursor)?;
+        let visibility = visibility(&mut cursor)?;
+        let mut node = if let Some(qualifiers) = function_qualifiers(&mut cursor) {
+            ModuleNode::Function(self.function(&mut cursor, visibility, qualifiers)?)
+        } else {
+            match cursor.ident()? {
+                "struct" => ModuleNode::Struct(self.r#struct(&mut cursor, item, visibility)?),
+                "enum" => ModuleNode::Enum(self.r#enum(&mut cursor, item, visibility)?),
+                "macro_rules" if visibility.is_private() => {
+                    cursor.expect("!")?;
+                    let mut macro_rules = self.macro_rules(&mut cursor)?;
+                    match attributes[..] {
+                        [] => {}
+                        ["macro_export"] => _ = macro_rules.set_exported(true),
+                        _ => return None,
+                    }
+                    return cursor
+                        .is_end()
+                        .then_some(ModuleNode::MacroRules(macro_rules));
+                }
+                "const" => ModuleNode::Const(self.r#const(&mut cursor, visibility.public()?)?),
+                "static" => ModuleNode::Static(self.r#static(&mut cursor, visibility.public()?)?),
+                "impl" if visibility.is_private() => ModuleNode::Impl(self.r#impl(&mut cursor)?),
+                "trait" => ModuleNode::Trait(self.r#trait(&mut cursor, visibility.public()?)?),
+                "mod" => {
+                    let name = cursor.ident()?;
+                    let mut submodule = Submodule::new(name.to_owned().into());
+                    submodule.set_public(visibility.public()?);
+                    self.items(cursor.group("{")?, submodule.module_mut());
+                    ModuleNode::Submodule(submodule)
+                }
+                "type" => {
+                    ModuleNode::TypeAlias(self.type_alias(&mut cursor, visibility.public()?)?)
+                }
+                "use" => {
+                    let mut r#use = Use::new(type_until(&mut cursor, |text| text == ";")?);
+                    r#use.set_public(visibility.public()?);
+                    cursor.expect(";")?;
+                    ModuleNode::Use(r#use)
+                }
+                "extern" if cursor.eat("crate") => {
+                    let mut extern_crate = crate::nodes::extern_crate::ExternCrate::new(
+                        cursor.ident()?.to_owned().into(),
+                    );
+                    extern_crate.set_public(visibility.public()?);
+                    if cursor.eat("as") {
+                        extern_crate.set_alias(cursor.ident()?.to_owned());
+                    }
+                    cursor.expect(";")?;
+                    ModuleNode::ExternCrate(extern_crate)
+                }
+                "extern" if visibility.is_private() => {
+                    ModuleNode::ExternBlock(self.extern_block(&mut cursor)?)
+                }
+                "thread_local" if visibility.is_private() => {
+                    cursor.expect("!")?;
+                    ModuleNode::ThreadLocal(self.thread_local(&mut cursor)?)
+                }
+                _ => return None,
+            }
+        };
+        if !attributes.is_empty() {
+            // Only structs, enums, functions and impls keep attributes
+            let node_attributes = match &mut node {
+                ModuleNode::Struct(r#struct) => r#struct.attributes_mut(),
+                ModuleNode::Enum(r#enum) => r#enum.attributes_mut(),
+                ModuleNode::Function(function) => function.attributes_mut(),
+                ModuleNode::Impl(r#impl) => r#impl.attributes_mut(),
+                _ => return None,
+            };
+            node_attributes.extend(
+                attributes
+                    .into_iter()
+                    .map(|attribute| attribute.to_owned().into()),
+            );
+        }
+        // Comments in bodies are handled by their parsers, anywhere else they cannot be kept
+        let comments = match node {
+            ModuleNode::Function(_)
+            | ModuleNode::Impl(_)
+            | ModuleNode::Trait(_)
+            | ModuleNode::Submodule(_) => self.has_header_comments(item),
+            // Checked by their parsers, which keep the docs of fields and variants
+            ModuleNode::Struct(_) | ModuleNode::Enum(_) => false,
+            _ => self.has_comments(item.offset(), end_offset(&item)),
+        };
+        (!comments && cursor.is_end()).then_some(node)
+    }
+
+    /// Checks for comments before the first braced group
+    fn has_header_comments(&self, mut cursor: Cursor<'a>) -> bool {
+        let start = cursor.offset();
+        while let Some(token) = cursor.peek() {
+            if token.kind == TokenKind::Open && cursor.text(token) == "{" {
+                break;
+            }
+            cursor.bump();
+        }
+        self.has_comments(start, cursor.offset())
+    }
+
+    /// Parses a struct after the `struct` keyword, `item` is a cursor over the whole item
+    fn r#struct(
+        &self,
+        cursor: &mut Cursor<'a>,
+        item: Cursor<'a>,
+        item_visibility: Visibility<'a>,
+    ) -> Option<Struct> {
+        let mut r#struct = Struct::new(cursor.ident()?.to_owned().into());
+        item_visibility.apply(&mut r#struct);
+        for generic in generics(cursor)? {
+            r#struct.add_generic(generic);
+        }
+        if self.has_comments(item.offset(), cursor.offset()) {
+            return None;
+        }
+        if let Some(types) = cursor.group("(") {
+            r#struct.tuple = true;
+            self.fields(types, true, |types, attributes, doc| {
+                let visibility = visibility(types)?;
+                let r#type = type_until(types, |text| text == ",")?;
+                let field = r#struct.add_tuple_field(r#type);
+                visibility.apply(field);
+                set_field_attributes(field, attributes, doc);
+                Some(())
+            })?;
+            cursor.expect(";")?;
+            return Some(r#struct);
+        }
+        let fields = cursor.group("{")?;
+        self.fields(fields, true, |fields, attributes, doc| {
+            let visibility = visibility(fields)?;
+            let name = fields.ident()?;
+            fields.expect(":")?;
+            let r#type = type_until(fields, |text| text == ",")?;
+            let field = r#struct.add_field(name.to_owned(), r#type);
+            visibility.apply(field);
+            set_field_attributes(field, attributes, doc);
+            Some(())
+        })?;
+        Some(r#struct)
+    }
+
+    /// Parses an enum after the `enum` keyword, `item` is a cursor over the whole item
+    fn r#enum(
+        &self,
+        cursor: &mut Cursor<'a>,
+        item: Cursor<'a>,
+        item_visibility: Visibility<'a>,
+    ) -> Option<Enum> {
+        let mut r#enum = Enum::new(cursor.ident()?.to_owned().into());
+        item_visibility.apply(&mut r#enum);
+        for generic in generics(cursor)? {
+            r#enum.add_generic(generic);
+        }
+        if self.has_comments(item.offset(), cursor.offset()) {
+            return None;
+        }
+        let variants = cursor.group("{")?;
+        self.fields(variants, true, |variants, attributes, doc| {
+            let variant = r#enum.add_variant(variants.ident()?.to_owned());
+            for attribute in attributes {
+                variant.add_attribute(attribute.to_owned());
+            }
+            if let Some(doc) = doc {
+                variant.set_doc(doc);
+            }
+            if let Some(types) = variants.group("(") {
+                self.fields(types, false, |types, _, _| {
+                    variant.add_tuple_field(type_until(types, |text| text == ",")?);
+                    Some(())
+                })?;
+            } else if let Some(fields) = variants.group("{") {
+                self.fields(fields, true, |fields, attributes, doc| {
+                    let name = fields.ident()?;
+                    fields.expect(":")?;
+                    let r#type = type_until(fields, |text| text == ",")?;
+                    let field = variant.add_field(name.to_owned(), r#type);
+                    set_field_attributes(field, attributes, doc);
+                    Some(())
+                })?;
+            }
+            if variants.eat("=") {
+                variant.set_discriminant(parse_expr(variants).ok()?);
+            }
+            Some(())
+        })?;
+        Some(r#enum)
+    }
+
+    /// Parses a comma separated list of fields or variants with `parse`
+    ///
+    /// `parse` gets the attributes and the doc comment of each element, they must be empty unless
+    /// `attributed` is set. Any other comment fails the list.
+    fn fields(
+        &self,
+        mut cursor: Cursor<'a>,
+        attributed: bool,
+        mut parse: impl FnMut(&mut Cursor<'a>, Vec<&'a str>, Option<String>) -> Option<()>,
+    ) -> Option<()> {
+        let mut last = start_offset(&cursor);
+        while !cursor.is_end() {
+            let start = cursor.offset();
+            let doc = match self.has_comments(last, start) {
+                true => Some(self.doc(self.comments_in(last, start)[0].start, start)?),
+                false => None,
+            };
+            let attributes = attributes(&mut cursor)?;
+            if !attributed && (doc.is_some() || !attributes.is_empty()) {
+                return None;
+            }
+            parse(&mut cursor, attributes, doc)?;
+            if self.has_comments(start, cursor.offset()) {
+                return None;
+            }
+            list_separator(&mut cursor)?;
+            last = cursor.prev_end();
+        }
+        (!self.has_comments(last, end_offset(&cursor))).then_some(())
+    }
+
+    fn r#const(&self, cursor: &mut Cursor<'a>, public: bool) -> Option<Const> {
+        let name = cursor.ident()?;
+        cursor.expect(":")?;
+        let r#type = type_until(cursor, |text| text == "=")?;
+        cursor.expect("=")?;
+        let value = parse_expr(cursor).ok()?;
+        cursor.expect(";")?;
+        let mut r#const = Const::new(name.to_owned().into(), r#type, value);
+        r#const.set_public(public);
+        Some(r#const)
+    }
+
+    fn r#static(&self, cursor: &mut Cursor<'a>, public: bool) -> Option<Static> {
+        let mutable = cursor.eat("mut");
+        let name = cursor.ident()?;
+        cursor.expect(":")?;
+        let r#type = type_until(cursor, |text| text == "=")?;
+        cursor.expect("=")?;
+        let value = parse_expr(cursor).ok()?;
+        cursor.expect(";")?;
+        let mut r#static = Static::new(name.to_owned().into(), r#type, value);
+        r#static.set_public(public).set_mut(mutable);
+        Some(r#static)
+    }
+
+    fn type_alias(&self, cursor: &mut Cursor<'a>, public: bool) -> Option<TypeAlias> {
+        let name = cursor.ident()?;
+        let mut generics = Vec::new();
+        if cursor.eat("<") {
+            while !cursor.eat(">") {
+                let token = cursor.bump()?;
+                if !matches!(token.kind, TokenKind::Ident | TokenKind::Lifetime) {
+                    return None;
+                }
+                generics.push(cursor.text(token));
+                if !cursor.eat(",") && !cursor.is(">") {
+                    return None;
+                }
+            }
+        }
+        cursor.expect("=")?;
+        let r#type = type_until(cursor, |text| text == ";")?;
+        cursor.expect(";")?;
+        let mut alias = TypeAlias::new(name.to_owned().into(), r#type);
+        alias.set_public(public);
+        for generic in generics {
+            alias.add_generic(generic.to_owned());
+        }
+        Some(alias)
+    }
+
+    /// Parses a function after the `fn` keyword, `qualifiers` are `const`, `async` and `unsafe`
+    fn function(
+        &self,
+        cursor: &mut Cursor<'a>,
+        visibility: Visibility<'a>,
+        [constant, asynchronous, unsafety]: [bool; 3],
+    ) -> Option<Function> {
+        let mut function = Function::new(cursor.ident()?.to_owned().into());
+        visibility.apply(&mut function);
+        function
+            .set_const(constant)
+            .set_async(asynchronous)
+            .set_unsafe(unsafety);
+        for generic in generics(cursor)? {
+            function.add_generic(generic);
+        }
+        let mut params = cursor.group("(")?;
+        while !params.is_end() {
+            if params.is("&") {
+                params.bump();
+                let mutable = params.eat("mut");
+                params.expect("self")?;
+                function.add_self_param(true, mutable);
+            } else {
+                let mutable = params.eat("mut");
+                let name = params.ident()?;
+                if params.eat(":") {
+                    let r#type = type_until(&mut params, |text| text == ",")?;
+                    let name = if mutable {
+                        format!("mut {name}")
+                    } else {
+                        name.to_owned()
+                    };
+                    function.add_param(name, r#type);
+                } else if name == "self" {
+                    function.add_self_param(false, mutable);
+                } else {
+                    return None;
+                }
+            }
+            list_separator(&mut params)?;
+        }
+        if cursor.eat("->") {
+            function.set_return_type(type_until(cursor, |text| {
+                matches!(text, "{" | ";" | "where")
+            })?);
+        }
+        if !cursor.eat(";") {
+            self.block(cursor.group("{")?, function.add_body())?;
+        }
+        Some(function)
+    }
+
+    /// Parses the statements of a block
+    fn block(&self, mut cursor: Cursor<'a>, block: &mut Block) -> Option<()> {
+        let mut last = start_offset(&cursor);
+        loop {
+            let doc = self.comments(&mut cursor, &mut last, true, &mut |member| match member {
+                Member::Raw(code) => _ = block.add_raw(code),
+                Member::Region(name, contents) => {
+                    block.add_user_region(name).set_default(contents);
+                }
+                Member::Function(_) | Member::Const(_) | Member::TypeAlias(_) => unreachable!(),
+            });
+            if cursor.is_end() {
+                break;
+            }
+            let statement = self.item_cursor(&mut cursor, statement_end);
+            let (start, end) = (doc.unwrap_or(statement.offset()), cursor.prev_end());
+            last = end;
+            let parsed = if self.has_comments(start, end) {
+                None
+            } else {
+                self.statement(statement)
+            };
+            block.add_statement(parsed.unwrap_or_else(|| {
+                Statement::Raw(Box::new(Raw::new(self.raw_text(start, end).into())))
+            }));
+        }
+        Some(())
+    }
+
+    fn statement(&self, mut cursor: Cursor<'a>) -> Option<Statement> {
+        if cursor.eat("if") {
+            let mut statement = IfStatement::new(parse_expr(&mut cursor).ok()?);
+            self.block(cursor.group("{")?, statement.then_mut())?;
+            if cursor.eat("else") {
+                if cursor.is("if") {
+                    let r#else = self.statement(cursor)?;
+                    statement.add_else().add_statement(r#else);
+                    return Some(Statement::If(Box::new(statement)));
+                }
+                self.block(cursor.group("{")?, statement.add_else())?;
+            }
+            cursor.eat(";");
+            return cursor
+                .is_end()
+                .then_some(Statement::If(Box::new(statement)));
+        }
+        if !cursor.is("let") && !cursor.is("return") {
+            let expr = parse_expr(&mut cursor).ok()?;
+            let operator = ASSIGN_OPERATORS
+                .iter()
+                .find(|(text, _)| cursor.is(text))
+                .map(|&(_, operator)| operator);
+            if operator.is_some() || cursor.is("=") {
+                cursor.bump();
+                let value = parse_expr(&mut cursor).ok()?;
+                cursor.expect(";")?;
+                let mut statement = AssignStatement::new(expr, value);
+                if let Some(operator) = operator {
+                    statement.set_operator(operator);
+                }
+                return cursor
+                    .is_end()
+                    .then_some(Statement::Assign(Box::new(statement)));
+            }
+            let mut statement = ExprStatement::new(expr);
+            statement.set_semicolon(cursor.eat(";"));
+            return cursor
+                .is_end()
+                .then_some(Statement::Expr(Box::new(statement)));
+        }
+        let statement = match cursor.ident()? {
+            "let" => {
+                let mutable = cursor.eat("mut");
+                let name = cursor.ident()?;
+                cursor.expect("=")?;
+                let value = parse_expr(&mut cursor).ok()?;
+                let mut statement = LetStatement::new(name.to_owned().into(), value);
+                statement.set_mut(mutable);
+                Statement::Let(Box::new(statement))
+            }
+            "return" if cursor.is(";") => Statement::Return(Box::new(ReturnStatement::new_empty())),
+            "return" => Statement::Return(Box::new(ReturnStatement::new(
+                parse_expr(&mut cursor).ok()?,
+            ))),
+            _ => return None,
+        };
+        cursor.expect(";")?;
+        cursor.is_end().then_some(statement)
+    }
+
+    fn r#impl(&self, cursor: &mut Cursor<'a>) -> Option<Impl> {
+        let generics = generics(cursor)?;
+        let first = type_until(cursor, |text| matches!(text, "for" | "{" | "where"))?;
+        let mut r#impl = if cursor.eat("for") {
+            let mut r#impl = Impl::new(type_until(cursor, |text| matches!(text, "{" | "where"))?);
+            r#impl.set_trait(first);
+            r#impl
+        } else {
+            Impl::new(first)
+        };
+        for generic in generics {
+            r#impl.add_generic(generic);
+        }
+        if cursor.eat("where") {
+            while !cursor.is("{") {
+                r#impl.add_where_predicate(type_until(cursor, |text| matches!(text, "," | "{"))?);
+                if !cursor.eat(",") && !cursor.is("{") {
+                    return None;
+                }
+            }
+        }
+        let members = cursor.group("{")?;
+        self.members(members, true, |node| match node {
+            Member::Function(function) => _ = r#impl.add_node(ImplNode::Function(function)),
+            Member::Const(r#const) => _ = r#impl.add_node(ImplNode::Const(r#const)),
+            Member::TypeAlias(alias) => _ = r#impl.add_node(ImplNode::TypeAlias(alias)),
+            Member::Raw(code) => _ = r#impl.add_raw(code),
+            Member::Region(name, contents) => {
+                r#impl.add_user_region(name).set_default(contents);
+            }
+        });
+        Some(r#impl)
+    }
+
+    fn r#trait(&self, cursor: &mut Cursor<'a>, public: bool) -> Option<Trait> {
+        let mut r#trait = Trait::new(cursor.ident()?.to_owned().into());
+        r#trait.set_public(public);
+        let members = cursor.group("{")?;
+        self.members(members, false, |node| match node {
+            Member::Function(function) => _ = r#trait.add_node(TraitNode::Function(function)),
+            Member::Raw(code) => _ = r#trait.add_raw(code),
+            Member::Const(_) | Member::TypeAlias(_) | Member::Region(..) => unreachable!(),
+        });
+        Some(r#trait)
+    }
+
+    /// Parses the members of an impl or a trait, regions, consts and type aliases are only parsed
+    /// in impls
+    fn members(&self, mut cursor: Cursor<'a>, in_impl: bool, mut add: impl FnMut(Member)) {
+        let mut last = start_offset(&cursor);
+        loop {
+            let doc = self.comments(&mut cursor, &mut last, in_impl, &mut add);
+            if cursor.is_end() {
+                break;
+            }
+            let member = self.item_cursor(&mut cursor, item_end);
+            let (start, end) = (doc.unwrap_or(member.offset()), cursor.prev_end());
+            last = end;
+            let parsed = match doc {
+                Some(doc) => self.doc(doc, member.offset()).and_then(|doc| {
+                    let Member::Function(mut function) = self.member(member, in_impl)? else {
+                        return None;
+                    };
+                    function.set_doc(doc);
+                    Some(Member::Function(function))
+                }),
+                None => self.member(member, in_impl),
+            };
+            let parsed = parsed.filter(|_| !self.has_header_comments(member));
+            add(parsed.unwrap_or_else(|| Member::Raw(self.raw_text(start, end))));
+        }
+    }
+
+    fn member(&self, mut cursor: Cursor<'a>, in_impl: bool) -> Option<Member> {
+        let attributes = attributes(&mut cursor)?;
+        let visibility = visibility(&mut cursor)?;
+        let member = if let Some(qualifiers) = function_qualifiers(&mut cursor) {
+            let mut function = self.function(&mut cursor, visibility, qualifiers)?;
+            for attribute in attributes {
+                function.add_attribute(attribute.to_owned());
+            }
+            Member::Function(function)
+        } else if !attributes.is_empty() {
+            return None;
+        } else if in_impl && cursor.eat("type") {
+            Member::TypeAlias(self.type_alias(&mut cursor, visibility.public()?)?)
+        } else if in_impl && cursor.eat("const") {
+            Member::Const(self.r#const(&mut cursor, visibility.public()?)?)
+        } else {
+            return None;
+        };
+        cursor.is_end().then_some(member)
+    }
+
+    fn extern_block(&self, cursor: &mut Cursor<'a>) -> Option<ExternBlock> {
+        let abi = match cursor.peek() {
+            Some(token) if token.kind == TokenKind::Literal => {
+                cursor.bump();
+                match parse_literal(cursor.text(token)).ok()? {
+                    crate::expr::Literal::Str(abi) => Some(abi),
+                    _ => return None,
+                }
+            }
+            _ => None,
+        };
+        let mut extern_block = ExternBlock::new(abi);
+        let mut items = cursor.group("{")?;
+        while !items.is_end() {
+            let public = visibility(&mut items)?.public()?;
+            match items.ident()? {
+                "fn" => {
+                    let name = items.text(items.peek()?);
+                    let mut function =
+                        self.function(&mut items, Visibility::default(), [false; 3])?;
+                    // Foreign functions have no body
+                    if !items.tokens[items.pos - 1].is_punct(items.src, ";") {
+                        return None;
+                    }
+                    function.set_public(public);
+                    *extern_block.add_function(name.to_owned()) = function;
+                }
+                "static" => {
+                    let mutable = items.eat("mut");
+                    let name = items.ident()?;
+                    items.expect(":")?;
+                    let r#type = type_until(&mut items, |text| text == ";")?;
+                    items.expect(";")?;
+                    extern_block
+                        .add_static(name.to_owned(), r#type)
+                        .set_public(public)
+                        .set_mut(mutable);
+                }
+                _ => return None,
+            }
+        }
+        Some(extern_block)
+    }
+
+    fn macro_rules(&self, cursor: &mut Cursor<'a>) -> Option<MacroRules> {
+        let mut macro_rules = MacroRules::new(cursor.ident()?.to_owned().into());
+        let mut arms = cursor.group("{")?;
+        while !arms.is_end() {
+            let matcher = group_contents(&mut arms)?;
+            arms.expect("=>")?;
+            let transcriber = group_contents(&mut arms)?;
+            macro_rules.add_arm(matcher.to_owned(), transcriber.to_owned());
+            if !arms.eat(";") && !arms.is_end() {
+                return None;
+            }
+        }
+        Some(macro_rules)
+    }
+
+    fn thread_local(&self, cursor: &mut Cursor<'a>) -> Option<ThreadLocal> {
+        let mut thread_local = ThreadLocal::new();
+        let mut statics = cursor.group("{")?;
+        while !statics.is_end() {
+            let public = visibility(&mut statics)?.public()?;
+            statics.expect("static")?;
+            let name = statics.ident()?;
+            statics.expect(":")?;
+            let r#type = type_until(&mut statics, |text| text == "=")?;
+            statics.expect("=")?;
+            let value = parse_expr(&mut statics).ok()?;
+            thread_local
+                .add_static(name.to_owned(), r#type, value)
+                .set_public(public);
+            if !statics.eat(";") && !statics.is_end() {
+                return None;
+            }
+        }
+        Some(thread_local)
+    }
+
+    /// Handles the comments before the next token of the cursor
+    ///
+    /// Comments are added as raw code. If `regions` is set, user regions are added as such, they
+    /// also consume the tokens of their contents. Doc comments directly before the next token
+    /// belong to it, their start is returned.
+    fn comments(
+        &self,
+        cursor: &mut Cursor<'a>,
+        last: &mut usize,
+        regions: bool,
+        add: &mut impl FnMut(Member),
+    ) -> Option<usize> {
+        loop {
+            let mut comments = self.comments_in(*last, cursor.offset());
+            if comments.is_empty() {
+                return None;
+            }
+            let region = comments.iter().enumerate().find_map(|(i, comment)| {
+                let name = regions::parse_begin_marker(self.comment_text(comment))?;
+                let end = self
+                    .comments_in(comment.end, end_offset(cursor))
+                    .iter()
+                    .find(|end| regions::parse_end_marker(self.comment_text(end)) == Some(name))?;
+                Some((i, name, end))
+            });
+            let region = region.filter(|_| regions);
+            let mut doc = None;
+            if region.is_none() {
+                let docs = comments
+                    .iter()
+                    .rev()
+                    .take_while(|comment| is_outer_doc(self.comment_text(comment)))
+                    .count();
+                if docs > 0 {
+                    doc = Some(comments[comments.len() - docs].start);
+                    comments = &comments[..comments.len() - docs];
+                }
+            }
+            let raw = region.map_or(comments.len(), |(i, ..)| i);
+            if raw > 0 {
+                add(Member::Raw(
+                    self.raw_text(comments[0].start, comments[raw - 1].end),
+                ));
+            }
+            let Some((i, name, end)) = region else {
+                *last = cursor.offset();
+                return doc;
+            };
+            let contents = &self.src[comments[i].end..line_start(self.src, end.start)];
+            add(Member::Region(name.to_owned(), contents.to_owned()));
+            while cursor.peek().is_some_and(|token| token.start < end.end) {
+                cursor.bump();
+            }
+            *last = end.end;
+        }
+    }
+
+    fn comment_text(&self, comment: &Token) -> &'a str {
+        &self.src[comment.start..comment.end]
+    }
+
+    fn comments_in(&self, start: usize, end: usize) -> &'a [Token] {
+        let from = self
+            .comments
+            .partition_point(|comment| comment.start < start);
+        let to = self.comments.partition_point(|comment| comment.start < end);
+        &self.comments[from..to]
+    }
+
+    fn has_comments(&self, start: usize, end: usize) -> bool {
+        !self.comments_in(start, end).is_empty()
+    }
+
+    /// Returns the text of the doc comment between `start` and `end`
+    ///
+    /// Returns `None` unless it consists of `///` comments on consecutive lines directly before
+    /// `end`, which are generated the same way.
+    fn doc(&self, start: usize, end: usize) -> Option<String> {
+        let mut lines = Vec::new();
+        let mut last = None;
+        for comment in self.comments_in(start, end) {
+            let line = match self.comment_text(comment).strip_prefix("///")? {
+                "" => "",
+                line => line.strip_prefix(' ')?,
+            };
+            if last.is_some_and(|last| !is_line_break(&self.src[last..comment.start])) {
+                return None;
+            }
+            lines.push(line);
+            last = Some(comment.end);
+        }
+        // A trailing empty line would be lost
+        if lines.last()?.is_empty() || !is_line_break(&self.src[last?..end]) {
+            return None;
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// Returns the source text between two offsets, including the indentation of the first line
+    fn raw_text(&self, start: usize, end: usize) -> String {
+        let line = line_start(self.src, start);
+        let start = if self.src[line..start].trim().is_empty() {
+            line
+        } else {
+            start
+        };
+        self.src[start..end].to_owned()
+    }
+}
+
+enum Member {
+    Function(Function),
+    Const(Const),
+    TypeAlias(TypeAlias),
+    Raw(String),
+    Region(String, String),
+}
+
+/// Advances the cursor past an item
+fn item_end(cursor: &mut Cursor) {
+    // Attributes
+    while cursor.is("#") {
+        cursor.bump();
+        cursor.eat("!");
+        cursor.bump();
+    }
+    let text = |n| {
+        cursor
+            .peek_nth(n)
+            .filter(|token| token.kind != TokenKind::Literal)
+            .map(|token| cursor.text(token))
+    };
+    let mut n = 0;
+    loop {
+        match text(n) {
+            Some("pub") if text(n + 1) == Some("(") => n += 2,
+            Some("pub" | "unsafe" | "async" | "default") => n += 1,
+            _ => break,
+        }
+    }
+    let semicolon = match text(n) {
+        Some("const") => !matches!(text(n + 1), Some("fn" | "unsafe" | "async" | "extern")),
+        Some("extern") => text(n + 1) == Some("crate"),
+        Some(text) => SEMICOLON_ITEMS.contains(&text),
+        None => false,
+    };
+    while let Some(token) = cursor.bump() {
+        if token.is_punct(cursor.src, ";") {
+            break;
+        }
+        if !semicolon && token.kind == TokenKind::Open && cursor.text(token) == "{" {
+            cursor.eat(";");
+            break;
+        }
+    }
+}
+
+/// Advances the cursor past a statement
+fn statement_end(cursor: &mut Cursor) {
+    let block = cursor.peek().is_some_and(|token| {
+        let text = cursor.text(token);
+        BLOCK_STATEMENTS.contains(&text) || (token.kind == TokenKind::Open && text == "{")
+    });
+    while let Some(token) = cursor.bump() {
+        let text = cursor.text(token);
+        if token.is_punct(cursor.src, ";") {
+            break;
+        }
+        if block && token.kind == TokenKind::Open && text == "{" && !cursor.is("else") {
+            if cursor.is(".") || cursor.is("?") {
+                continue;
+            }
+            cursor.eat(";");
+            break;
+        }
+    }
+}
+
+/// A parsed visibility, `path` is set for restricted visibilities like `pub(crate)`
+#[derive(Clone, Copy, Default)]
+struct Visibility<'a> {
+    public: bool,
+    path: Option<&'a str>,
+}
+
+impl<'a> Visibility<'a> {
+    fn is_private(self) -> bool {
+        !self.public
+    }
+
+    /// Returns whether the visibility is `pub`, `None` for restricted visibilities, which only
+    /// some nodes support
+    fn public(self) -> Option<bool> {
+        self.path.is_none().then_some(self.public)
+    }
+
+    fn apply(self, node: &mut impl RestrictedVisibility) {
+        match self.path {
+            Some(path) => node.set_public_in(path.to_owned()),
+            None => node.set_public(self.public),
+        }
+    }
+}
+
+/// Nodes that support restricted visibilities
+trait RestrictedVisibility {
+    fn set_public(&mut self, public: bool);
+    fn set_public_in(&mut self, path: String);
+}
+
+macro_rules! impl_restricted_visibility {
+    ($($type:ty),*) => {
+        $(impl RestrictedVisibility for $type {
+            fn set_public(&mut self, public: bool) {
+                <$type>::set_public(self, public);
+            }
+
+            fn set_public_in(&mut self, path: String) {
+                <$type>::set_public_in(self, path);
+            }
+        })*
+    };
+}
+
+impl_restricted_visibility!(Struct, Field, Enum, Function);
+
+/// Parses a visibility like `pub` or `pub(in crate::a)`
+fn visibility<'a>(cursor: &mut Cursor<'a>) -> Option<Visibility<'a>> {
+    if !cursor.eat("pub") {
+        return Some(Visibility::default());
+    }
+    let Some(mut restriction) = cursor.group("(") else {
+        return Some(Visibility {
+            public: true,
+            path: None,
+        });
+    };
+    let path = match restriction.ident()? {
+        path @ ("crate" | "super" | "self") => path,
+        "in" => {
+            let path = restriction.collect_until(|_| false)?;
+            // `pub(in crate)` would be generated as `pub(crate)`
+            if matches!(path, "crate" | "super" | "self") || path.contains(char::is_whitespace) {
+                return None;
+            }
+            path
+        }
+        _ => return None,
+    };
+    restriction.is_end().then_some(Visibility {
+        public: true,
+        path: Some(path),
+    })
+}
+
+/// Parses outer attributes and returns their contents, e.g. `derive(Debug)`
+///
+/// Inner attributes and attributes spanning multiple lines are not supported.
+fn attributes<'a>(cursor: &mut Cursor<'a>) -> Option<Vec<&'a str>> {
+    let mut attributes = Vec::new();
+    while cursor.eat("#") {
+        let attribute = group_contents(cursor)?.trim();
+        if attribute.is_empty() || attribute.contains('\n') || attribute.starts_with('[') {
+            return None;
+        }
+        attributes.push(attribute);
+    }
+    Some(attributes)
+}
+
+/// Parses generic parameters like `<T: Clone, 'a>` if there are any
+fn generics(cursor: &mut Cursor) -> Option<Vec<Str>> {
+    let mut generics = Vec::new();
+    if cursor.eat("<") {
+        while !cursor.eat(">") {
+            generics.push(type_until(cursor, |text| matches!(text, "," | ">"))?);
+            if !cursor.eat(",") && !cursor.is(">") {
+                return None;
+            }
+        }
+    }
+    Some(generics)
+}
+
+/// Parses the qualifiers of a function and the `fn` keyword
+///
+/// Returns whether the function is `const`, `async` and `unsafe`, the cursor is only advanced if
+/// it is at a function.
+fn function_qualifiers(cursor: &mut Cursor) -> Option<[bool; 3]> {
+    let mut lookahead = *cursor;
+    let qualifiers = ["const", "async", "unsafe"].map(|qualifier| lookahead.eat(qualifier));
+    lookahead.expect("fn")?;
+    *cursor = lookahead;
+    Some(qualifiers)
+}
+
+fn set_field_attributes(field: &mut Field, attributes: Vec<&str>, doc: Option<String>) {
+    for attribute in attributes {
+        field.add_attribute(attribute.to_owned());
+    }
+    if let Some(doc) = doc {
+        field.set_doc(doc);
+    }
+}
+
+/// Parses a type up to a stop token
+fn type_until(cursor: &mut Cursor, stop: impl Fn(&str) -> bool) -> Option<Str> {
+    let text = cursor.collect_until(stop)?;
+    Some(text.split_whitespace().collect::<Vec<_>>().join(" ").into())
+}
+
+/// Expects a comma or the end of a list
+fn list_separator(cursor: &mut Cursor) -> Option<()> {
+    (cursor.eat(",") || cursor.is_end()).then_some(())
+}
+
+/// Returns the source text inside of a group with any delimiter
+fn group_contents<'a>(cursor: &mut Cursor<'a>) -> Option<&'a str> {
+    let token = cursor.peek()?;
+    if token.kind != TokenKind::Open {
+        return None;
+    }
+    let close = cursor.tokens[token.matching];
+    cursor.bump();
+    Some(&cursor.src[token.end..close.start])
+}
+
+/// Byte offset of the start of a cursor's range
+fn start_offset(cursor: &Cursor) -> usize {
+    match cursor.pos.checked_sub(1) {
+        Some(pos) => cursor.tokens[pos].end,
+        None => 0,
+    }
+}
+
+/// Byte offset of the end of a cursor's range
+fn end_offset(cursor: &Cursor) -> usize {
+    match cursor.tokens.get(cursor.end) {
+        Some(token) => token.start,
+        None => cursor.src.len(),
+    }
+}
+
+/// Checks for `///` and `/** */` comments, which document the following item
+fn is_outer_doc(comment: &str) -> bool {
+    (comment.starts_with("///") && !comment.starts_with("////"))
+        || (comment.starts_with("/**") && !comment.starts_with("/***") && comment != "/**/")
+}
+
+/// Checks that `text` is whitespace containing exactly one line break
+fn is_line_break(text: &str) -> bool {
+    text.trim().is_empty() && text.matches('\n').count() == 1
+}
+
+fn line_start(src: &str, offset: usize) -> usize {
+    src[..offset].rfind('\n').map_or(0, |i| i + 1)
+}
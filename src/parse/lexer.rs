@@ -0,0 +1,350 @@
+//! Splits source code into tokens
+
+use super::ParseError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    /// Identifiers and keywords, including raw identifiers
+    Ident,
+    Lifetime,
+    Literal,
+    Punct,
+    Open,
+    Close,
+    /// Line and block comments, including doc comments
+    Comment,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Token {
+    pub kind: TokenKind,
+    /// Byte offset of the first character
+    pub start: usize,
+    /// Byte offset after the last character
+    pub end: usize,
+    /// For delimiters, the index of the matching delimiter
+    pub matching: usize,
+}
+
+impl Token {
+    pub fn is_punct(&self, src: &str, punct: &str) -> bool {
+        self.kind == TokenKind::Punct && &src[self.start..self.end] == punct
+    }
+}
+
+/// Multi-character punctuation, longest first
+const PUNCTS: &[&str] = &[
+    "...", "..=", "<<=", ">>=", "::", "->", "=>", "==", "!=", "<=", ">=", "&&", "||", "+=", "-=",
+    "*=", "/=", "%=", "^=", "&=", "|=", "<<", ">>", "..",
+];
+
+pub(crate) struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+    tokens: Vec<Token>,
+    comments: Vec<Token>,
+    /// Indices of open delimiters
+    stack: Vec<usize>,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            pos: 0,
+            tokens: Vec::new(),
+            comments: Vec::new(),
+            stack: Vec::new(),
+        }
+    }
+
+    /// Returns the tokens and the comments of the source separately
+    pub fn tokenize(mut self) -> Result<(Vec<Token>, Vec<Token>), ParseError> {
+        while let Some(c) = self.peek() {
+            let start = self.pos;
+            let kind = match c {
+                _ if c.is_whitespace() => {
+                    self.bump();
+                    continue;
+                }
+                '/' if self.peek_nth(1) == Some('/') => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.bump();
+                    }
+                    TokenKind::Comment
+                }
+                '/' if self.peek_nth(1) == Some('*') => {
+                    self.block_comment(start)?;
+                    TokenKind::Comment
+                }
+                '(' | '[' | '{' => {
+                    self.bump();
+                    self.stack.push(self.tokens.len());
+                    TokenKind::Open
+                }
+                ')' | ']' | '}' => {
+                    self.bump();
+                    let Some(open) = self.stack.pop() else {
+                        return Err(self.error(format!("unexpected `{c}`"), start));
+                    };
+                    let expected = match &self.src[self.tokens[open].start..][..1] {
+                        "(" => ')',
+                        "[" => ']',
+                        _ => '}',
+                    };
+                    if c != expected {
+                        return Err(
+                            self.error(format!("expected `{expected}`, found `{c}`"), start)
+                        );
+                    }
+                    let index = self.tokens.len();
+                    self.tokens[open].matching = index;
+                    self.tokens.push(Token {
+                        kind: TokenKind::Close,
+                        start,
+                        end: self.pos,
+                        matching: open,
+                    });
+                    continue;
+                }
+                '"' => {
+                    self.bump();
+                    self.string(start)?;
+                    TokenKind::Literal
+                }
+                'r' if matches!(self.peek_nth(1), Some('"'))
+                    || (self.peek_nth(1) == Some('#') && self.is_raw_string(1)) =>
+                {
+                    self.bump();
+                    self.raw_string(start)?;
+                    TokenKind::Literal
+                }
+                'b' | 'c' if self.peek_nth(1) == Some('"') => {
+                    self.bump();
+                    self.bump();
+                    self.string(start)?;
+                    TokenKind::Literal
+                }
+                'b' if self.peek_nth(1) == Some('\'') => {
+                    self.bump();
+                    self.bump();
+                    self.char(start)?;
+                    TokenKind::Literal
+                }
+                'b' | 'c'
+                    if self.peek_nth(1) == Some('r')
+                        && matches!(self.peek_nth(2), Some('"' | '#')) =>
+                {
+                    self.bump();
+                    self.bump();
+                    self.raw_string(start)?;
+                    TokenKind::Literal
+                }
+                '\'' => {
+                    self.bump();
+                    let is_char = matches!(
+                        (self.peek(), self.peek_nth(1)),
+                        (Some('\\'), _) | (Some(_), Some('\''))
+                    );
+                    if is_char {
+                        self.char(start)?;
+                        TokenKind::Literal
+                    } else {
+                        while self.peek().is_some_and(is_ident_continue) {
+                            self.bump();
+                        }
+                        TokenKind::Lifetime
+                    }
+                }
+                '0'..='9' => {
+                    self.number();
+                    TokenKind::Literal
+                }
+                _ if is_ident_start(c) => {
+                    if c == 'r' && self.peek_nth(1) == Some('#') {
+                        self.bump();
+                        self.bump();
+                    }
+                    while self.peek().is_some_and(is_ident_continue) {
+                        self.bump();
+                    }
+                    TokenKind::Ident
+                }
+                _ => {
+                    let rest = &self.src[self.pos..];
+                    let len = PUNCTS
+                        .iter()
+                        .find(|punct| rest.starts_with(**punct))
+                        .map_or(c.len_utf8(), |punct| punct.len());
+                    self.pos += len;
+                    TokenKind::Punct
+                }
+            };
+            let token = Token {
+                kind,
+                start,
+                end: self.pos,
+                matching: 0,
+            };
+            if kind == TokenKind::Comment {
+                self.comments.push(token);
+            } else {
+                self.tokens.push(token);
+            }
+        }
+        if let Some(open) = self.stack.pop() {
+            let c = &self.src[self.tokens[open].start..][..1];
+            return Err(self.error(format!("unclosed `{c}`"), self.tokens[open].start));
+        }
+        Ok((self.tokens, self.comments))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.src[self.pos..].chars().nth(n)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn error(&self, message: String, offset: usize) -> ParseError {
+        ParseError::at(message, self.src, offset)
+    }
+
+    /// Checks for `r#"` or `r##"` as opposed to a raw identifier
+    fn is_raw_string(&self, mut n: usize) -> bool {
+        while self.peek_nth(n) == Some('#') {
+            n += 1;
+        }
+        self.peek_nth(n) == Some('"')
+    }
+
+    fn block_comment(&mut self, start: usize) -> Result<(), ParseError> {
+        self.pos += 2;
+        let mut depth = 1;
+        while depth > 0 {
+            match self.bump() {
+                Some('*') if self.peek() == Some('/') => {
+                    self.bump();
+                    depth -= 1;
+                }
+                Some('/') if self.peek() == Some('*') => {
+                    self.bump();
+                    depth += 1;
+                }
+                Some(_) => {}
+                None => return Err(self.error("unterminated block comment".into(), start)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Lexes the rest of a string after the opening quote
+    fn string(&mut self, start: usize) -> Result<(), ParseError> {
+        loop {
+            match self.bump() {
+                Some('\\') => _ = self.bump(),
+                Some('"') => break,
+                Some(_) => {}
+                None => return Err(self.error("unterminated string".into(), start)),
+            }
+        }
+        self.suffix();
+        Ok(())
+    }
+
+    /// Lexes a raw string starting at the hashes
+    fn raw_string(&mut self, start: usize) -> Result<(), ParseError> {
+        let mut hashes = 0;
+        while self.peek() == Some('#') {
+            self.bump();
+            hashes += 1;
+        }
+        self.bump();
+        let terminator = format!("\"{}", "#".repeat(hashes));
+        match self.src[self.pos..].find(&terminator) {
+            Some(end) => self.pos += end + terminator.len(),
+            None => return Err(self.error("unterminated raw string".into(), start)),
+        }
+        self.suffix();
+        Ok(())
+    }
+
+    /// Lexes the rest of a char after the opening quote
+    fn char(&mut self, start: usize) -> Result<(), ParseError> {
+        loop {
+            match self.bump() {
+                Some('\\') => _ = self.bump(),
+                Some('\'') => break,
+                Some('\n') | None => {
+                    return Err(self.error("unterminated character literal".into(), start))
+                }
+                Some(_) => {}
+            }
+        }
+        self.suffix();
+        Ok(())
+    }
+
+    fn number(&mut self) {
+        let radix = self.peek() == Some('0') && matches!(self.peek_nth(1), Some('x' | 'o' | 'b'));
+        if radix {
+            self.bump();
+            self.bump();
+            while self
+                .peek()
+                .is_some_and(|c| c.is_ascii_hexdigit() || c == '_')
+            {
+                self.bump();
+            }
+        } else {
+            self.digits();
+            // `1.0` but not `1..2` or `1.max(2)`
+            if self.peek() == Some('.')
+                && !matches!(self.peek_nth(1), Some('.'))
+                && !self.peek_nth(1).is_some_and(is_ident_start)
+            {
+                self.bump();
+                self.digits();
+            }
+            if matches!(self.peek(), Some('e' | 'E'))
+                && matches!(self.peek_nth(1), Some('0'..='9' | '+' | '-'))
+            {
+                self.bump();
+                if matches!(self.peek(), Some('+' | '-')) {
+                    self.bump();
+                }
+                self.digits();
+            }
+        }
+        self.suffix();
+    }
+
+    fn digits(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '_') {
+            self.bump();
+        }
+    }
+
+    fn suffix(&mut self) {
+        if self.peek().is_some_and(is_ident_start) {
+            while self.peek().is_some_and(is_ident_continue) {
+                self.bump();
+            }
+        }
+    }
+}
+
+pub(crate) fn is_ident_start(c: char) -> bool {
+    c == '_' || c.is_alphabetic()
+}
+
+pub(crate) fn is_ident_continue(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
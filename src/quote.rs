@@ -0,0 +1,407 @@
+//! Building nodes from Rust syntax with the [`item!`](crate::item) and [`expr!`](crate::expr!)
+//! macros
+//!
+//! The macros parse their input with the [`parse`](crate::parse) module. `#name` interpolates the
+//! value of the variable `name`, which can be anything implementing [`Interpolate`]: strings are
+//! inserted as code, so they work for names and types, while expressions are inserted as
+//! sub-expressions. Both macros return a [`ParseError`] for input they cannot parse, `item!` also
+//! for items that would only be kept as raw code, such as unions and macro invocations. Doc
+//! comments and attributes end up in the fields of the node.
+//!
+//! ```
+//! use codegen_rs::{expr, generator::GenerateCode, item, nodes::ModuleNode};
+//!
+//! # fn main() -> Result<(), codegen_rs::parse::ParseError> {
+//! let name = "Vec2";
+//! let r#type = String::from("f32");
+//! let node = item! {
+//!     /// A vector
+//!     #[derive(Clone, Copy)]
+//!     pub struct #name {
+//!         pub x: #r#type,
+//!     }
+//! }?;
+//! let ModuleNode::Struct(r#struct) = &node else { panic!() };
+//! assert_eq!(r#struct.doc(), Some("A vector"));
+//! assert_eq!(
+//!     node.to_code_string(),
+//!     "/// A vector\n#[derive(Clone, Copy)]\npub struct Vec2 {\n    pub x: f32,\n}\n"
+//! );
+//!
+//! let b = expr::call("f").arg(expr::i32(1));
+//! assert_eq!(expr!(a + #b * 2)?.to_code_string(), "(a + (f(1i32) * 2))");
+//! assert!(expr!(a +).is_err());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    expr::{self, Expr},
+    generator::GenerateCode,
+    nodes::ModuleNode,
+    parse::{
+        self,
+        lexer::{Lexer, TokenKind},
+        ParseError,
+    },
+    Str,
+};
+
+/// Builds a [`ModuleNode`] from a single item, see the [module docs](crate::quote)
+///
+/// Returns a [`ParseError`](crate::parse::ParseError) if the input is not exactly one item that
+/// parses into a node other than [`ModuleNode::Raw`](crate::nodes::ModuleNode::Raw).
+#[macro_export]
+macro_rules! item {
+    ($($tokens: tt)*) => {
+        $crate::quote::item(
+            ::core::stringify!($($tokens)*),
+            &$crate::__interpolations!([] $($tokens)*),
+        )
+    };
+}
+
+/// Builds an [`Expr`] from an expression, see the [module docs](crate::quote)
+///
+/// Returns a [`ParseError`](crate::parse::ParseError) if the input is not a supported expression.
+#[macro_export]
+macro_rules! expr {
+    ($($tokens: tt)*) => {
+        $crate::quote::expr(
+            ::core::stringify!($($tokens)*),
+            &$crate::__interpolations!([] $($tokens)*),
+        )
+    };
+}
+
+/// Collects the `#name` interpolations of the tokens into an array
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __interpolations {
+    ([$($done: tt)*]) => {
+        [$($done)*]
+    };
+    ([$($done: tt)*] # $name: ident $($rest: tt)*) => {
+        $crate::__interpolations!(
+            [$($done)* (
+                ::core::stringify!($name),
+                $crate::quote::Interpolate::interpolate(&$name),
+            ),]
+            $($rest)*
+        )
+    };
+    ([$($done: tt)*] ($($group: tt)*) $($rest: tt)*) => {
+        $crate::__interpolations!([$($done)*] $($group)* $($rest)*)
+    };
+    ([$($done: tt)*] [$($group: tt)*] $($rest: tt)*) => {
+        $crate::__interpolations!([$($done)*] $($group)* $($rest)*)
+    };
+    ([$($done: tt)*] {$($group: tt)*} $($rest: tt)*) => {
+        $crate::__interpolations!([$($done)*] $($group)* $($rest)*)
+    };
+    ([$($done: tt)*] $token: tt $($rest: tt)*) => {
+        $crate::__interpolations!([$($done)*] $($rest)*)
+    };
+}
+
+/// A value inserted into quoted code
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum Interpolation {
+    /// Code, such as a name or a type
+    Code(Str),
+    Expr(Expr),
+}
+
+/// Values that can be interpolated with `#name`
+pub trait Interpolate {
+    fn interpolate(&self) -> Interpolation;
+}
+
+impl<T: Interpolate + ?Sized> Interpolate for &T {
+    fn interpolate(&self) -> Interpolation {
+        (**self).interpolate()
+    }
+}
+
+impl Interpolate for str {
+    fn interpolate(&self) -> Interpolation {
+        Interpolation::Code(self.to_owned().into())
+    }
+}
+
+impl Interpolate for String {
+    fn interpolate(&self) -> Interpolation {
+        Interpolation::Code(self.clone().into())
+    }
+}
+
+impl Interpolate for Str {
+    fn interpolate(&self) -> Interpolation {
+        Interpolation::Code(self.clone())
+    }
+}
+
+impl Interpolate for Expr {
+    fn interpolate(&self) -> Interpolation {
+        Interpolation::Expr(self.clone())
+    }
+}
+
+macro_rules! node {
+    ($($type: ident),*) => {
+        $(
+            impl Interpolate for expr::$type {
+                fn interpolate(&self) -> Interpolation {
+                    Interpolation::Expr(self.clone().into())
+                }
+            }
+        )*
+    };
+}
+
+node!(
+    Binding,
+    Literal,
+    Call,
+    MacroCall,
+    MethodCall,
+    FieldAccess,
+    UnaryOperation,
+    BinaryOperation,
+    Cast,
+    Tuple,
+    Array,
+    StructInitializer,
+    TupleStructInitializer,
+    Match
+);
+
+macro_rules! literal {
+    ($($type: ident),*) => {
+        $(
+            impl Interpolate for $type {
+                fn interpolate(&self) -> Interpolation {
+                    Interpolation::Expr(expr::$type(*self))
+                }
+            }
+        )*
+    };
+}
+
+literal!(bool, char, i8, u8, i16, u16, i32, u32, i64, u64, i128, u128, f32, f64);
+
+/// Implementation of [`item!`](crate::item)
+#[doc(hidden)]
+pub fn item(
+    source: &str,
+    interpolations: &[(&str, Interpolation)],
+) -> Result<ModuleNode, ParseError> {
+    let (source, _) = substitute(source, interpolations, true)?;
+    let mut nodes = parse::parse_module(&source)?.into_nodes();
+    if nodes.len() != 1 {
+        return Err(ParseError::at(
+            format!("expected exactly one item, found {}", nodes.len()),
+            &source,
+            0,
+        ));
+    }
+    let mut node = nodes.pop().unwrap();
+    match &mut node {
+        // The parser keeps anything it does not understand as raw code
+        ModuleNode::Raw(_) => {
+            return Err(ParseError::at(
+                "expected a supported item, found unparsed code",
+                &source,
+                0,
+            ));
+        }
+        ModuleNode::Struct(r#struct) => {
+            if let Some(doc) = doc_attributes(r#struct.attributes_mut()) {
+                r#struct.set_doc(doc);
+            }
+            for field in r#struct.fields_mut() {
+                if let Some(doc) = doc_attributes(field.attributes_mut()) {
+                    field.set_doc(doc);
+                }
+            }
+        }
+        ModuleNode::Enum(r#enum) => {
+            if let Some(doc) = doc_attributes(r#enum.attributes_mut()) {
+                r#enum.set_doc(doc);
+            }
+            for variant in r#enum.variants_mut() {
+                if let Some(doc) = doc_attributes(variant.attributes_mut()) {
+                    variant.set_doc(doc);
+                }
+            }
+        }
+        ModuleNode::Function(function) => {
+            if let Some(doc) = doc_attributes(function.attributes_mut()) {
+                function.set_doc(doc);
+            }
+        }
+        _ => {}
+    }
+    Ok(node)
+}
+
+/// Implementation of [`expr!`](crate::expr!)
+#[doc(hidden)]
+pub fn expr(source: &str, interpolations: &[(&str, Interpolation)]) -> Result<Expr, ParseError> {
+    let (source, placeholders) = substitute(source, interpolations, false)?;
+    parse::parse_expr(&source, &placeholders)
+}
+
+/// Removes the leading `#[doc = r" .."]` attributes that `///` comments turn into in macro
+/// input and returns their text
+fn doc_attributes(attributes: &mut Vec<Str>) -> Option<String> {
+    let lines: Vec<&str> = attributes
+        .iter()
+        .map_while(|attribute| {
+            let text = attribute.strip_prefix("doc = ")?;
+            let text = text.strip_prefix('r').unwrap_or(text);
+            match text.strip_prefix('"')?.strip_suffix('"')? {
+                "" => Some(""),
+                line if line.contains(['\\', '"']) => None,
+                line => line.strip_prefix(' '),
+            }
+        })
+        .collect();
+    if lines.is_empty() {
+        return None;
+    }
+    let doc = lines.join("\n");
+    attributes.drain(..lines.len());
+    Some(doc)
+}
+
+/// Expressions standing in for `$name` in the source passed to the expression parser
+type Placeholders<'a> = Vec<(&'a str, Expr)>;
+
+/// Replaces the interpolations in `source`
+///
+/// Expressions are replaced by `$name` placeholders unless `render` is set, in which case their
+/// code is inserted.
+fn substitute<'a>(
+    source: &str,
+    interpolations: &'a [(&'a str, Interpolation)],
+    render: bool,
+) -> Result<(String, Placeholders<'a>), ParseError> {
+    let (tokens, _) = Lexer::new(source).tokenize()?;
+    let mut dst = String::with_capacity(source.len());
+    let mut placeholders = Vec::new();
+    let mut last = 0;
+    for pair in tokens.windows(2) {
+        let (hash, name) = (&pair[0], &pair[1]);
+        if !hash.is_punct(source, "#") || name.kind != TokenKind::Ident {
+            continue;
+        }
+        let name = &source[name.start..name.end];
+        let Some((name, value)) = interpolations.iter().find(|(n, _)| *n == name) else {
+            continue;
+        };
+        dst.push_str(&source[last..hash.start]);
+        match value {
+            Interpolation::Code(code) => dst.push_str(code),
+            Interpolation::Expr(expr) if render => dst.push_str(&expr.to_code_string()),
+            Interpolation::Expr(expr) => {
+                dst.push('$');
+                dst.push_str(name);
+                placeholders.push((*name, expr.clone()));
+            }
+        }
+        last = pair[1].end;
+    }
+    dst.push_str(&source[last..]);
+    Ok((dst, placeholders))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn documented_items() {
+        let node = crate::item! {
+            /// An enum
+            ///
+            /// With two paragraphs
+            #[derive(Debug)]
+            enum E {
+                /// The first variant
+                A,
+                #[default]
+                B,
+            }
+        }
+        .unwrap();
+        let ModuleNode::Enum(r#enum) = &node else {
+            panic!("expected an enum, found {node:?}");
+        };
+        assert_eq!(r#enum.doc(), Some("An enum\n\nWith two paragraphs"));
+        assert_eq!(r#enum.attributes(), ["derive(Debug)"]);
+        assert_eq!(r#enum.variants()[0].doc(), Some("The first variant"));
+        assert_eq!(r#enum.variants()[1].attributes(), ["default"]);
+
+        let node = crate::item! {
+            /// Adds one
+            #[inline]
+            pub fn f(a: u8) -> u8 {
+                a + 1
+            }
+        }
+        .unwrap();
+        let ModuleNode::Function(function) = &node else {
+            panic!("expected a function, found {node:?}");
+        };
+        assert_eq!(function.doc(), Some("Adds one"));
+        assert_eq!(function.attributes(), ["inline"]);
+    }
+
+    #[test]
+    fn errors() {
+        let error = crate::item!(
+            struct A;
+            struct B;
+        )
+        .unwrap_err();
+        assert_eq!(error.message(), "expected exactly one item, found 2");
+        assert!(crate::item!().is_err());
+        let error = crate::item!(struct A x;).unwrap_err();
+        assert_eq!(
+            error.message(),
+            "expected a supported item, found unparsed code"
+        );
+        assert!(crate::item!(union U { a: u8 }).is_err());
+        assert!(crate::item!(fn f() -> u8 { 1 } + 2).is_err());
+        assert!(crate::expr!(a+).is_err());
+        assert!(crate::expr!(a b).is_err());
+    }
+
+    #[test]
+    fn interpolations() {
+        let name = "Point";
+        let r#type = String::from("Vec<u8>");
+        let node = crate::item!(pub struct #name(pub #r#type);).unwrap();
+        assert_eq!(node.to_code_string(), "pub struct Point(pub Vec<u8>);\n");
+
+        let value = expr::call("f").arg(expr::u8(1));
+        let node = crate::item! {
+            fn #name() -> #r#type {
+                vec![#value]
+            }
+        }
+        .unwrap();
+        assert_eq!(
+            node.to_code_string(),
+            "fn Point() -> Vec<u8> {\n    vec![f(1u8)]\n}\n"
+        );
+
+        let (field, r#type) = ("x", "u64");
+        let expr = crate::expr!(#value + p.#field as #r#type).unwrap();
+        assert_eq!(expr.to_code_string(), "(f(1u8) + (p.x as u64))");
+        let expr = crate::expr!([#value, 2]).unwrap();
+        assert_eq!(expr.to_code_string(), "[f(1u8), 2]");
+    }
+}
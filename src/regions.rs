@@ -0,0 +1,373 @@
+//! Preserving hand-written regions across regeneration
+//!
+//! Generated code can contain [`UserRegion`](crate::nodes::user_region::UserRegion)s delimited
+//! by `// codegen:user-begin(name)` and `// codegen:user-end(name)`. When the code is
+//! regenerated, the contents of each region are taken from the existing file.
+
+use std::{collections::HashMap, fmt::Display, io, path::Path};
+
+use crate::{fs::write_if_changed, nodes::Module};
+
+const BEGIN: &str = "// codegen:user-begin(";
+const END: &str = "// codegen:user-end(";
+
+pub fn begin_marker(name: &str) -> String {
+    format!("{BEGIN}{name})")
+}
+
+pub fn end_marker(name: &str) -> String {
+    format!("{END}{name})")
+}
+
+/// Returns the name of a region if the line is a begin marker
+pub fn parse_begin_marker(line: &str) -> Option<&str> {
+    parse_marker(line, BEGIN)
+}
+
+/// Returns the name of a region if the line is an end marker
+pub fn parse_end_marker(line: &str) -> Option<&str> {
+    parse_marker(line, END)
+}
+
+impl Module {
+    /// Renders the module, keeping the contents of the user regions in `existing`
+    pub fn render_preserving(&self, existing: &str) -> Result<Preserved, RegionError> {
+        UserRegions::parse(existing)?.apply(&self.to_string())
+    }
+
+    /// Regenerates the file at `path`, keeping the contents of its user regions
+    ///
+    /// The file is only rewritten if its contents have changed. Returns the regions of the
+    /// existing file that no longer exist in the generated code.
+    pub fn regenerate(&self, path: impl AsRef<Path>) -> io::Result<Vec<Region>> {
+        let path = path.as_ref();
+        let existing = match std::fs::read_to_string(path) {
+            Ok(existing) => existing,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        let preserved = self
+            .render_preserving(&existing)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        write_if_changed(path, &preserved.code)?;
+        Ok(preserved.orphaned)
+    }
+}
+
+/// A user region read from existing code
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    name: String,
+    indent: String,
+    contents: String,
+}
+
+impl Region {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The lines between the markers, including their indentation
+    pub fn contents(&self) -> &str {
+        &self.contents
+    }
+}
+
+/// The user regions of existing code
+#[derive(Clone, Debug, Default)]
+pub struct UserRegions {
+    regions: Vec<Region>,
+}
+
+impl UserRegions {
+    pub fn parse(code: &str) -> Result<Self, RegionError> {
+        let mut regions = Vec::new();
+        let mut names = HashMap::new();
+        let mut lines = code.lines().enumerate();
+        while let Some((i, line)) = lines.next() {
+            if let Some(name) = parse_marker(line, END) {
+                return Err(RegionError::new(
+                    RegionErrorKind::UnexpectedEnd(name.into()),
+                    i,
+                ));
+            }
+            let Some(name) = parse_marker(line, BEGIN) else {
+                continue;
+            };
+            if names.insert(name, i).is_some() {
+                return Err(RegionError::new(RegionErrorKind::Duplicate(name.into()), i));
+            }
+            let mut contents = String::new();
+            loop {
+                let Some((j, line)) = lines.next() else {
+                    return Err(RegionError::new(RegionErrorKind::Unclosed(name.into()), i));
+                };
+                if let Some(end) = parse_marker(line, END) {
+                    if end != name {
+                        return Err(RegionError::new(
+                            RegionErrorKind::UnexpectedEnd(end.into()),
+                            j,
+                        ));
+                    }
+                    break;
+                }
+                if parse_marker(line, BEGIN).is_some() {
+                    return Err(RegionError::new(RegionErrorKind::Unclosed(name.into()), i));
+                }
+                contents.push_str(line);
+                contents.push('\n');
+            }
+            regions.push(Region {
+                name: name.into(),
+                indent: indentation(line).into(),
+                contents,
+            });
+        }
+        Ok(Self { regions })
+    }
+
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Region> {
+        self.regions.iter().find(|region| region.name == name)
+    }
+
+    /// Replaces the contents of the regions in `generated` with the stored ones
+    ///
+    /// Contents are re-indented if the markers moved to a different indentation level.
+    pub fn apply(&self, generated: &str) -> Result<Preserved, RegionError> {
+        let target = UserRegions::parse(generated)?;
+        let mut code = String::with_capacity(generated.len());
+        let mut lines = generated.lines();
+        while let Some(line) = lines.next() {
+            code.push_str(line);
+            code.push('\n');
+            let Some(name) = parse_marker(line, BEGIN) else {
+                continue;
+            };
+            let Some(region) = self.get(name) else {
+                continue;
+            };
+            // Skip the generated default contents
+            for line in lines.by_ref() {
+                if parse_marker(line, END).is_some() {
+                    let indent = indentation(line);
+                    for content in region.contents.lines() {
+                        if !content.trim().is_empty() {
+                            code.push_str(indent);
+                            code.push_str(
+                                content
+                                    .strip_prefix(region.indent.as_str())
+                                    .unwrap_or(content.trim_start()),
+                            );
+                        }
+                        code.push('\n');
+                    }
+                    code.push_str(line);
+                    code.push('\n');
+                    break;
+                }
+            }
+        }
+        if !generated.ends_with('\n') {
+            code.pop();
+        }
+        let orphaned = self
+            .regions
+            .iter()
+            .filter(|region| target.get(&region.name).is_none())
+            .cloned()
+            .collect();
+        Ok(Preserved { code, orphaned })
+    }
+}
+
+/// Generated code with preserved user regions
+#[derive(Clone, Debug)]
+pub struct Preserved {
+    code: String,
+    orphaned: Vec<Region>,
+}
+
+impl Preserved {
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn into_code(self) -> String {
+        self.code
+    }
+
+    /// Regions of the existing code that do not exist in the generated code
+    pub fn orphaned(&self) -> &[Region] {
+        &self.orphaned
+    }
+}
+
+fn parse_marker<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    line.trim().strip_prefix(marker)?.strip_suffix(')')
+}
+
+fn indentation(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegionError {
+    kind: RegionErrorKind,
+    line: usize,
+}
+
+impl RegionError {
+    fn new(kind: RegionErrorKind, index: usize) -> Self {
+        Self {
+            kind,
+            line: index + 1,
+        }
+    }
+
+    pub fn kind(&self) -> &RegionErrorKind {
+        &self.kind
+    }
+
+    /// The 1-based line of the offending marker
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+impl Display for RegionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            RegionErrorKind::Unclosed(name) => write!(f, "user region `{name}` is not closed")?,
+            RegionErrorKind::UnexpectedEnd(name) => {
+                write!(f, "end of user region `{name}` without a beginning")?
+            }
+            RegionErrorKind::Duplicate(name) => write!(f, "duplicate user region `{name}`")?,
+        }
+        write!(f, " on line {}", self.line)
+    }
+}
+
+impl std::error::Error for RegionError {}
+
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RegionErrorKind {
+    /// A region without an end marker, regions cannot be nested
+    Unclosed(String),
+    /// An end marker without a matching begin marker
+    UnexpectedEnd(String),
+    Duplicate(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module() -> Module {
+        let mut module = Module::new();
+        module
+            .add_impl("App")
+            .add_function("run")
+            .add_body()
+            .add_user_region("body")
+            .set_default("todo!()");
+        module
+    }
+
+    fn parse_error(code: &str) -> RegionError {
+        UserRegions::parse(code).unwrap_err()
+    }
+
+    #[test]
+    fn preserved() {
+        let existing = r#"impl App {
+    fn run() {
+        // codegen:user-begin(body)
+        let a = 1;
+        println!("{a}");
+        // codegen:user-end(body)
+    }
+}
+"#;
+        let preserved = module().render_preserving(existing).unwrap();
+        assert_eq!(preserved.code(), existing);
+        assert!(preserved.orphaned().is_empty());
+
+        let preserved = module().render_preserving("").unwrap();
+        assert_eq!(preserved.code(), module().to_string());
+    }
+
+    #[test]
+    fn indentation() {
+        // The function moved into an impl, the contents move with the markers
+        let existing = "fn run() {\n    // codegen:user-begin(body)\n    if a {\n        b();\n\n    }\n    // codegen:user-end(body)\n}\n";
+        let preserved = module().render_preserving(existing).unwrap();
+        assert_eq!(
+            preserved.code(),
+            "impl App {\n    fn run() {\n        // codegen:user-begin(body)\n        if a {\n            b();\n\n        }\n        // codegen:user-end(body)\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn orphaned() {
+        let existing = "// codegen:user-begin(body)\nkeep();\n// codegen:user-end(body)\n// codegen:user-begin(old)\nlost();\n// codegen:user-end(old)\n";
+        let preserved = module().render_preserving(existing).unwrap();
+        assert!(preserved.code().contains("        keep();\n"));
+        let [orphaned] = preserved.orphaned() else {
+            panic!("expected one orphaned region");
+        };
+        assert_eq!(orphaned.name(), "old");
+        assert_eq!(orphaned.contents(), "lost();\n");
+    }
+
+    #[test]
+    fn duplicate() {
+        let error = parse_error("// codegen:user-begin(a)\n// codegen:user-end(a)\n  // codegen:user-begin(a)\n  // codegen:user-end(a)\n");
+        assert_eq!(*error.kind(), RegionErrorKind::Duplicate("a".into()));
+        assert_eq!(error.line(), 3);
+        assert_eq!(error.to_string(), "duplicate user region `a` on line 3");
+    }
+
+    #[test]
+    fn unterminated() {
+        let error = parse_error("fn f() {}\n// codegen:user-begin(a)\nf();\n");
+        assert_eq!(*error.kind(), RegionErrorKind::Unclosed("a".into()));
+        assert_eq!(error.line(), 2);
+
+        // Regions cannot be nested
+        let error = parse_error("// codegen:user-begin(a)\n// codegen:user-begin(b)\n// codegen:user-end(b)\n// codegen:user-end(a)\n");
+        assert_eq!(*error.kind(), RegionErrorKind::Unclosed("a".into()));
+        assert_eq!(error.line(), 1);
+    }
+
+    #[test]
+    fn unexpected_end() {
+        let error = parse_error("// codegen:user-end(a)\n");
+        assert_eq!(*error.kind(), RegionErrorKind::UnexpectedEnd("a".into()));
+        assert_eq!(error.line(), 1);
+
+        let error = parse_error("// codegen:user-begin(a)\n// codegen:user-end(b)\n");
+        assert_eq!(*error.kind(), RegionErrorKind::UnexpectedEnd("b".into()));
+        assert_eq!(error.line(), 2);
+    }
+
+    #[test]
+    fn regenerate() {
+        let path =
+            std::env::temp_dir().join(format!("codegen-rs-regions-{}.rs", std::process::id()));
+        _ = std::fs::remove_file(&path);
+        assert!(module().regenerate(&path).unwrap().is_empty());
+        let code = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, code.replace("todo!()", "start();")).unwrap();
+
+        assert!(module().regenerate(&path).unwrap().is_empty());
+        let code = std::fs::read_to_string(&path).unwrap();
+        assert!(code.contains("        start();\n"));
+        assert!(!code.contains("todo!()"));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,648 @@
+//! Traversing the node tree
+//!
+//! [`Visit`] walks a tree by reference, [`VisitMut`] by mutable reference and [`Fold`] rebuilds
+//! it from owned nodes. Every trait method defaults to the free function of the same name, which
+//! visits the children of a node. An implementation overrides the methods it is interested in
+//! and calls the free function to continue into the children.
+//!
+//! ```
+//! use codegen_rs::{expr::Binding, nodes::Module, visit::{self, Visit}};
+//!
+//! #[derive(Default)]
+//! struct Types(Vec<String>);
+//!
+//! impl Visit for Types {
+//!     fn visit_type(&mut self, r#type: &str) {
+//!         self.0.push(r#type.to_owned());
+//!     }
+//! }
+//!
+//! let mut module = Module::new();
+//! module.add_struct("A").add_field("x", "Vec<u8>");
+//! module.add_function("f").add_param("a", "u32").set_return_type("bool");
+//!
+//! let mut types = Types::default();
+//! types.visit_module(&module);
+//! assert_eq!(types.0, ["Vec<u8>", "u32", "bool"]);
+//! ```
+
+use crate::{
+    expr::{
+        Array, BinaryOperation, Binding, Call, Cast, Expr, FieldAccess, Literal, MacroCall, Match,
+        MatchArm, MethodCall, StructInitializer, Tuple, TupleStructInitializer, UnaryOperation,
+    },
+    nodes::{
+        extern_block::{ExternBlock, ExternNode, ExternStatic},
+        extern_crate::ExternCrate,
+        function::{Function, Param},
+        macro_rules::MacroRules,
+        r#const::Const,
+        r#enum::{Enum, EnumVariant, VariantFields},
+        r#impl::{Impl, ImplNode},
+        r#static::Static,
+        r#struct::{Field, Struct},
+        r#trait::{Trait, TraitNode},
+        r#type::TypeAlias,
+        r#use::Use,
+        raw::Raw,
+        statement::{
+            AssignStatement, Block, ExprStatement, IfStatement, LetStatement, ReturnStatement,
+            Statement,
+        },
+        thread_local::ThreadLocal,
+        user_region::UserRegion,
+        Module, ModuleNode, Submodule,
+    },
+};
+
+pub use self::{fold::Fold, visit_mut::VisitMut};
+
+pub mod fold;
+pub mod visit_mut;
+
+pub trait Visit {
+    fn visit_module(&mut self, node: &Module) {
+        visit_module(self, node);
+    }
+
+    fn visit_module_node(&mut self, node: &ModuleNode) {
+        visit_module_node(self, node);
+    }
+
+    fn visit_use(&mut self, node: &Use) {
+        _ = node;
+    }
+
+    fn visit_submodule(&mut self, node: &Submodule) {
+        visit_submodule(self, node);
+    }
+
+    fn visit_const(&mut self, node: &Const) {
+        visit_const(self, node);
+    }
+
+    fn visit_static(&mut self, node: &Static) {
+        visit_static(self, node);
+    }
+
+    fn visit_struct(&mut self, node: &Struct) {
+        visit_struct(self, node);
+    }
+
+    fn visit_field(&mut self, node: &Field) {
+        visit_field(self, node);
+    }
+
+    fn visit_enum(&mut self, node: &Enum) {
+        visit_enum(self, node);
+    }
+
+    fn visit_enum_variant(&mut self, node: &EnumVariant) {
+        visit_enum_variant(self, node);
+    }
+
+    fn visit_trait(&mut self, node: &Trait) {
+        visit_trait(self, node);
+    }
+
+    fn visit_trait_node(&mut self, node: &TraitNode) {
+        visit_trait_node(self, node);
+    }
+
+    fn visit_impl(&mut self, node: &Impl) {
+        visit_impl(self, node);
+    }
+
+    fn visit_impl_node(&mut self, node: &ImplNode) {
+        visit_impl_node(self, node);
+    }
+
+    fn visit_function(&mut self, node: &Function) {
+        visit_function(self, node);
+    }
+
+    fn visit_param(&mut self, node: &Param) {
+        visit_param(self, node);
+    }
+
+    fn visit_block(&mut self, node: &Block) {
+        visit_block(self, node);
+    }
+
+    fn visit_statement(&mut self, node: &Statement) {
+        visit_statement(self, node);
+    }
+
+    fn visit_let_statement(&mut self, node: &LetStatement) {
+        visit_let_statement(self, node);
+    }
+
+    fn visit_return_statement(&mut self, node: &ReturnStatement) {
+        visit_return_statement(self, node);
+    }
+
+    fn visit_expr_statement(&mut self, node: &ExprStatement) {
+        visit_expr_statement(self, node);
+    }
+
+    fn visit_assign_statement(&mut self, node: &AssignStatement) {
+        visit_assign_statement(self, node);
+    }
+
+    fn visit_if_statement(&mut self, node: &IfStatement) {
+        visit_if_statement(self, node);
+    }
+
+    fn visit_type_alias(&mut self, node: &TypeAlias) {
+        visit_type_alias(self, node);
+    }
+
+    fn visit_extern_crate(&mut self, node: &ExternCrate) {
+        _ = node;
+    }
+
+    fn visit_extern_block(&mut self, node: &ExternBlock) {
+        visit_extern_block(self, node);
+    }
+
+    fn visit_extern_node(&mut self, node: &ExternNode) {
+        visit_extern_node(self, node);
+    }
+
+    fn visit_extern_static(&mut self, node: &ExternStatic) {
+        visit_extern_static(self, node);
+    }
+
+    fn visit_macro_rules(&mut self, node: &MacroRules) {
+        _ = node;
+    }
+
+    fn visit_thread_local(&mut self, node: &ThreadLocal) {
+        visit_thread_local(self, node);
+    }
+
+    fn visit_raw(&mut self, node: &Raw) {
+        _ = node;
+    }
+
+    fn visit_user_region(&mut self, node: &UserRegion) {
+        visit_user_region(self, node);
+    }
+
+    fn visit_expr(&mut self, node: &Expr) {
+        visit_expr(self, node);
+    }
+
+    fn visit_binding(&mut self, node: &Binding) {
+        _ = node;
+    }
+
+    fn visit_literal(&mut self, node: &Literal) {
+        _ = node;
+    }
+
+    fn visit_call(&mut self, node: &Call) {
+        visit_call(self, node);
+    }
+
+    fn visit_macro_call(&mut self, node: &MacroCall) {
+        visit_macro_call(self, node);
+    }
+
+    fn visit_method_call(&mut self, node: &MethodCall) {
+        visit_method_call(self, node);
+    }
+
+    fn visit_field_access(&mut self, node: &FieldAccess) {
+        visit_field_access(self, node);
+    }
+
+    fn visit_unary_operation(&mut self, node: &UnaryOperation) {
+        visit_unary_operation(self, node);
+    }
+
+    fn visit_binary_operation(&mut self, node: &BinaryOperation) {
+        visit_binary_operation(self, node);
+    }
+
+    fn visit_cast(&mut self, node: &Cast) {
+        visit_cast(self, node);
+    }
+
+    fn visit_tuple(&mut self, node: &Tuple) {
+        visit_tuple(self, node);
+    }
+
+    fn visit_array(&mut self, node: &Array) {
+        visit_array(self, node);
+    }
+
+    fn visit_struct_initializer(&mut self, node: &StructInitializer) {
+        visit_struct_initializer(self, node);
+    }
+
+    fn visit_tuple_struct_initializer(&mut self, node: &TupleStructInitializer) {
+        visit_tuple_struct_initializer(self, node);
+    }
+
+    fn visit_match(&mut self, node: &Match) {
+        visit_match(self, node);
+    }
+
+    fn visit_match_arm(&mut self, node: &MatchArm) {
+        visit_match_arm(self, node);
+    }
+
+    /// Called for every type, such as field, parameter and return types, the type of struct
+    /// initializers and the path before the function of calls like `Vec::<u8>::new`
+    fn visit_type(&mut self, r#type: &str) {
+        _ = r#type;
+    }
+}
+
+pub fn visit_module<V: Visit + ?Sized>(v: &mut V, node: &Module) {
+    for child in &node.nodes {
+        v.visit_module_node(child);
+    }
+}
+
+pub fn visit_module_node<V: Visit + ?Sized>(v: &mut V, node: &ModuleNode) {
+    match node {
+        ModuleNode::Use(child) => v.visit_use(child),
+        ModuleNode::Submodule(child) => v.visit_submodule(child),
+        ModuleNode::Const(child) => v.visit_const(child),
+        ModuleNode::Static(child) => v.visit_static(child),
+        ModuleNode::Struct(child) => v.visit_struct(child),
+        ModuleNode::Enum(child) => v.visit_enum(child),
+        ModuleNode::Trait(child) => v.visit_trait(child),
+        ModuleNode::Impl(child) => v.visit_impl(child),
+        ModuleNode::Function(child) => v.visit_function(child),
+        ModuleNode::TypeAlias(child) => v.visit_type_alias(child),
+        ModuleNode::ExternCrate(child) => v.visit_extern_crate(child),
+        ModuleNode::ExternBlock(child) => v.visit_extern_block(child),
+        ModuleNode::MacroRules(child) => v.visit_macro_rules(child),
+        ModuleNode::ThreadLocal(child) => v.visit_thread_local(child),
+        ModuleNode::Raw(child) => v.visit_raw(child),
+    }
+}
+
+pub fn visit_submodule<V: Visit + ?Sized>(v: &mut V, node: &Submodule) {
+    v.visit_module(&node.module);
+}
+
+pub fn visit_const<V: Visit + ?Sized>(v: &mut V, node: &Const) {
+    v.visit_type(&node.r#type);
+    v.visit_expr(&node.value);
+}
+
+pub fn visit_static<V: Visit + ?Sized>(v: &mut V, node: &Static) {
+    v.visit_type(&node.r#type);
+    v.visit_expr(&node.value);
+}
+
+pub fn visit_struct<V: Visit + ?Sized>(v: &mut V, node: &Struct) {
+    for field in &node.fields {
+        v.visit_field(field);
+    }
+}
+
+pub fn visit_field<V: Visit + ?Sized>(v: &mut V, node: &Field) {
+    v.visit_type(&node.r#type);
+}
+
+pub fn visit_enum<V: Visit + ?Sized>(v: &mut V, node: &Enum) {
+    for variant in &node.variants {
+        v.visit_enum_variant(variant);
+    }
+}
+
+pub fn visit_enum_variant<V: Visit + ?Sized>(v: &mut V, node: &EnumVariant) {
+    match &node.fields {
+        VariantFields::Unit => {}
+        VariantFields::Unnamed(types) => {
+            for r#type in types {
+                v.visit_type(r#type);
+            }
+        }
+        VariantFields::Named(fields) => {
+            for field in fields {
+                v.visit_field(field);
+            }
+        }
+    }
+    if let Some(discriminant) = &node.discriminant {
+        v.visit_expr(discriminant);
+    }
+}
+
+pub fn visit_trait<V: Visit + ?Sized>(v: &mut V, node: &Trait) {
+    for child in &node.nodes {
+        v.visit_trait_node(child);
+    }
+}
+
+pub fn visit_trait_node<V: Visit + ?Sized>(v: &mut V, node: &TraitNode) {
+    match node {
+        TraitNode::Function(child) => v.visit_function(child),
+        TraitNode::Raw(child) => v.visit_raw(child),
+    }
+}
+
+pub fn visit_impl<V: Visit + ?Sized>(v: &mut V, node: &Impl) {
+    if let Some(r#trait) = &node.r#trait {
+        v.visit_type(r#trait);
+    }
+    v.visit_type(&node.target);
+    for child in &node.nodes {
+        v.visit_impl_node(child);
+    }
+}
+
+pub fn visit_impl_node<V: Visit + ?Sized>(v: &mut V, node: &ImplNode) {
+    match node {
+        ImplNode::Function(child) => v.visit_function(child),
+        ImplNode::Const(child) => v.visit_const(child),
+        ImplNode::TypeAlias(child) => v.visit_type_alias(child),
+        ImplNode::Raw(child) => v.visit_raw(child),
+        ImplNode::UserRegion(child) => v.visit_user_region(child),
+    }
+}
+
+pub fn visit_function<V: Visit + ?Sized>(v: &mut V, node: &Function) {
+    for param in &node.params {
+        v.visit_param(param);
+    }
+    if let Some(return_type) = &node.return_type {
+        v.visit_type(return_type);
+    }
+    if let Some(body) = &node.body {
+        v.visit_block(body);
+    }
+}
+
+pub fn visit_param<V: Visit + ?Sized>(v: &mut V, node: &Param) {
+    if let Some(r#type) = &node.r#type {
+        v.visit_type(r#type);
+    }
+}
+
+pub fn visit_block<V: Visit + ?Sized>(v: &mut V, node: &Block) {
+    for statement in &node.statements {
+        v.visit_statement(statement);
+    }
+}
+
+pub fn visit_statement<V: Visit + ?Sized>(v: &mut V, node: &Statement) {
+    match node {
+        Statement::Let(child) => v.visit_let_statement(child),
+        Statement::Return(child) => v.visit_return_statement(child),
+        Statement::Expr(child) => v.visit_expr_statement(child),
+        Statement::Assign(child) => v.visit_assign_statement(child),
+        Statement::If(child) => v.visit_if_statement(child),
+        Statement::Raw(child) => v.visit_raw(child),
+        Statement::UserRegion(child) => v.visit_user_region(child),
+    }
+}
+
+pub fn visit_let_statement<V: Visit + ?Sized>(v: &mut V, node: &LetStatement) {
+    v.visit_expr(&node.value);
+}
+
+pub fn visit_return_statement<V: Visit + ?Sized>(v: &mut V, node: &ReturnStatement) {
+    if let Some(value) = &node.value {
+        v.visit_expr(value);
+    }
+}
+
+pub fn visit_expr_statement<V: Visit + ?Sized>(v: &mut V, node: &ExprStatement) {
+    v.visit_expr(&node.expr);
+}
+
+pub fn visit_assign_statement<V: Visit + ?Sized>(v: &mut V, node: &AssignStatement) {
+    v.visit_expr(&node.target);
+    v.visit_expr(&node.value);
+}
+
+pub fn visit_if_statement<V: Visit + ?Sized>(v: &mut V, node: &IfStatement) {
+    v.visit_expr(&node.condition);
+    v.visit_block(&node.then);
+    if let Some(otherwise) = &node.otherwise {
+        v.visit_block(otherwise);
+    }
+}
+
+pub fn visit_type_alias<V: Visit + ?Sized>(v: &mut V, node: &TypeAlias) {
+    v.visit_type(&node.r#type);
+}
+
+pub fn visit_extern_block<V: Visit + ?Sized>(v: &mut V, node: &ExternBlock) {
+    for child in &node.nodes {
+        v.visit_extern_node(child);
+    }
+}
+
+pub fn visit_extern_node<V: Visit + ?Sized>(v: &mut V, node: &ExternNode) {
+    match node {
+        ExternNode::Function(child) => v.visit_function(child),
+        ExternNode::Static(child) => v.visit_extern_static(child),
+    }
+}
+
+pub fn visit_extern_static<V: Visit + ?Sized>(v: &mut V, node: &ExternStatic) {
+    v.visit_type(&node.r#type);
+}
+
+pub fn visit_thread_local<V: Visit + ?Sized>(v: &mut V, node: &ThreadLocal) {
+    for static_ in &node.statics {
+        v.visit_static(static_);
+    }
+}
+
+pub fn visit_user_region<V: Visit + ?Sized>(v: &mut V, node: &UserRegion) {
+    if let Some(default) = &node.default {
+        v.visit_raw(default);
+    }
+}
+
+pub fn visit_expr<V: Visit + ?Sized>(v: &mut V, node: &Expr) {
+    match node {
+        Expr::Binding(child) => v.visit_binding(child),
+        Expr::Literal(child) => v.visit_literal(child),
+        Expr::Call(child) => v.visit_call(child),
+        Expr::MacroCall(child) => v.visit_macro_call(child),
+        Expr::MethodCall(child) => v.visit_method_call(child),
+        Expr::FieldAccess(child) => v.visit_field_access(child),
+        Expr::UnaryOperation(child) => v.visit_unary_operation(child),
+        Expr::BinaryOperation(child) => v.visit_binary_operation(child),
+        Expr::Cast(child) => v.visit_cast(child),
+        Expr::Tuple(child) => v.visit_tuple(child),
+        Expr::Array(child) => v.visit_array(child),
+        Expr::StructInitializer(child) => v.visit_struct_initializer(child),
+        Expr::TupleStructInitializer(child) => v.visit_tuple_struct_initializer(child),
+        Expr::Match(child) => v.visit_match(child),
+    }
+}
+
+pub fn visit_call<V: Visit + ?Sized>(v: &mut V, node: &Call) {
+    if let Some((path, _)) = split_call(&node.name) {
+        v.visit_type(path);
+    }
+    for arg in &node.args {
+        v.visit_expr(arg);
+    }
+}
+
+pub fn visit_macro_call<V: Visit + ?Sized>(v: &mut V, node: &MacroCall) {
+    for arg in &node.args {
+        v.visit_expr(arg);
+    }
+}
+
+pub fn visit_method_call<V: Visit + ?Sized>(v: &mut V, node: &MethodCall) {
+    v.visit_expr(&node.receiver);
+    for arg in &node.args {
+        v.visit_expr(arg);
+    }
+}
+
+pub fn visit_field_access<V: Visit + ?Sized>(v: &mut V, node: &FieldAccess) {
+    v.visit_expr(&node.expr);
+}
+
+pub fn visit_unary_operation<V: Visit + ?Sized>(v: &mut V, node: &UnaryOperation) {
+    v.visit_expr(&node.expr);
+}
+
+pub fn visit_binary_operation<V: Visit + ?Sized>(v: &mut V, node: &BinaryOperation) {
+    v.visit_expr(&node.left);
+    v.visit_expr(&node.right);
+}
+
+pub fn visit_cast<V: Visit + ?Sized>(v: &mut V, node: &Cast) {
+    v.visit_expr(&node.expr);
+    v.visit_type(&node.r#type);
+}
+
+pub fn visit_tuple<V: Visit + ?Sized>(v: &mut V, node: &Tuple) {
+    for value in &node.values {
+        v.visit_expr(value);
+    }
+}
+
+pub fn visit_array<V: Visit + ?Sized>(v: &mut V, node: &Array) {
+    for value in &node.values {
+        v.visit_expr(value);
+    }
+}
+
+pub fn visit_struct_initializer<V: Visit + ?Sized>(v: &mut V, node: &StructInitializer) {
+    v.visit_type(&node.name);
+    for (name, value) in &node.fields {
+        match value {
+            Some(value) => v.visit_expr(value),
+            // `Point { x }` reads the binding `x`
+            None => v.visit_binding(&Binding::new(name.clone())),
+        }
+    }
+}
+
+pub fn visit_tuple_struct_initializer<V: Visit + ?Sized>(v: &mut V, node: &TupleStructInitializer) {
+    for value in &node.tuple.values {
+        v.visit_expr(value);
+    }
+}
+
+pub fn visit_match<V: Visit + ?Sized>(v: &mut V, node: &Match) {
+    v.visit_expr(&node.expr);
+    for arm in &node.arms {
+        v.visit_match_arm(arm);
+    }
+}
+
+pub fn visit_match_arm<V: Visit + ?Sized>(v: &mut V, node: &MatchArm) {
+    if let Some(guard) = &node.guard {
+        v.visit_expr(guard);
+    }
+    v.visit_expr(&node.value);
+}
+
+/// Splits the name of a call into the path before the function and the function, e.g.
+/// `Vec::<u8>` and `new`, or returns `None` for a plain function name
+pub(crate) fn split_call(name: &str) -> Option<(&str, &str)> {
+    let mut depth = 0usize;
+    let mut split = None;
+    for (i, c) in name.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            // `f::<u8>` is a turbofish, not a path
+            ':' if depth == 0
+                && i > 0
+                && name[i..].starts_with("::")
+                && !name[i + 2..].starts_with('<') =>
+            {
+                split = Some(i);
+            }
+            _ => {}
+        }
+    }
+    split.map(|i| (&name[..i], &name[i + 2..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `Point { x, y: Vec::<u8>::with_capacity(n), z: f::<u8>() }`
+    pub(super) fn initializer() -> Expr {
+        StructInitializer::new("Point")
+            .field_auto("x")
+            .field(
+                "y",
+                Call::new("Vec::<u8>::with_capacity").arg(Binding::new("n")),
+            )
+            .field("z", Call::new("f::<u8>"))
+            .into()
+    }
+
+    #[derive(Default)]
+    struct Names {
+        types: Vec<String>,
+        bindings: Vec<String>,
+    }
+
+    impl Visit for Names {
+        fn visit_type(&mut self, r#type: &str) {
+            self.types.push(r#type.to_owned());
+        }
+
+        fn visit_binding(&mut self, node: &Binding) {
+            self.bindings.push(node.name().to_owned());
+        }
+    }
+
+    #[test]
+    fn struct_initializers_and_calls() {
+        let mut names = Names::default();
+        names.visit_expr(&initializer());
+        assert_eq!(names.types, ["Point", "Vec::<u8>"]);
+        assert_eq!(names.bindings, ["x", "n"]);
+    }
+
+    #[test]
+    fn call_paths() {
+        assert_eq!(split_call("f"), None);
+        assert_eq!(split_call("f::<u8>"), None);
+        assert_eq!(split_call("::f"), None);
+        assert_eq!(split_call("Vec::new"), Some(("Vec", "new")));
+        assert_eq!(
+            split_call("HashMap::<K, Vec<V>>::new::<u8>"),
+            Some(("HashMap::<K, Vec<V>>", "new::<u8>"))
+        );
+        assert_eq!(
+            split_call("<T as Default>::default"),
+            Some(("<T as Default>", "default"))
+        );
+        assert_eq!(split_call("std::mem::swap"), Some(("std::mem", "swap")));
+    }
+}
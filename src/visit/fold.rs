@@ -0,0 +1,672 @@
+//! Rebuilding the node tree from owned nodes, see [`visit`](crate::visit)
+
+use crate::{
+    expr::{
+        Array, BinaryOperation, Binding, Call, Cast, Expr, FieldAccess, Literal, MacroCall, Match,
+        MatchArm, MethodCall, StructInitializer, Tuple, TupleStructInitializer, UnaryOperation,
+    },
+    nodes::{
+        extern_block::{ExternBlock, ExternNode, ExternStatic},
+        extern_crate::ExternCrate,
+        function::{Function, Param},
+        macro_rules::MacroRules,
+        r#const::Const,
+        r#enum::{Enum, EnumVariant, VariantFields},
+        r#impl::{Impl, ImplNode},
+        r#static::Static,
+        r#struct::{Field, Struct},
+        r#trait::{Trait, TraitNode},
+        r#type::TypeAlias,
+        r#use::Use,
+        raw::Raw,
+        statement::{
+            AssignStatement, Block, ExprStatement, IfStatement, LetStatement, ReturnStatement,
+            Statement,
+        },
+        thread_local::ThreadLocal,
+        user_region::UserRegion,
+        Module, ModuleNode, Submodule,
+    },
+    Str,
+};
+
+use super::split_call;
+
+pub trait Fold {
+    fn fold_module(&mut self, node: Module) -> Module {
+        fold_module(self, node)
+    }
+
+    fn fold_module_node(&mut self, node: ModuleNode) -> ModuleNode {
+        fold_module_node(self, node)
+    }
+
+    fn fold_use(&mut self, node: Use) -> Use {
+        node
+    }
+
+    fn fold_submodule(&mut self, node: Submodule) -> Submodule {
+        fold_submodule(self, node)
+    }
+
+    fn fold_const(&mut self, node: Const) -> Const {
+        fold_const(self, node)
+    }
+
+    fn fold_static(&mut self, node: Static) -> Static {
+        fold_static(self, node)
+    }
+
+    fn fold_struct(&mut self, node: Struct) -> Struct {
+        fold_struct(self, node)
+    }
+
+    fn fold_field(&mut self, node: Field) -> Field {
+        fold_field(self, node)
+    }
+
+    fn fold_enum(&mut self, node: Enum) -> Enum {
+        fold_enum(self, node)
+    }
+
+    fn fold_enum_variant(&mut self, node: EnumVariant) -> EnumVariant {
+        fold_enum_variant(self, node)
+    }
+
+    fn fold_trait(&mut self, node: Trait) -> Trait {
+        fold_trait(self, node)
+    }
+
+    fn fold_trait_node(&mut self, node: TraitNode) -> TraitNode {
+        fold_trait_node(self, node)
+    }
+
+    fn fold_impl(&mut self, node: Impl) -> Impl {
+        fold_impl(self, node)
+    }
+
+    fn fold_impl_node(&mut self, node: ImplNode) -> ImplNode {
+        fold_impl_node(self, node)
+    }
+
+    fn fold_function(&mut self, node: Function) -> Function {
+        fold_function(self, node)
+    }
+
+    fn fold_param(&mut self, node: Param) -> Param {
+        fold_param(self, node)
+    }
+
+    fn fold_block(&mut self, node: Block) -> Block {
+        fold_block(self, node)
+    }
+
+    fn fold_statement(&mut self, node: Statement) -> Statement {
+        fold_statement(self, node)
+    }
+
+    fn fold_let_statement(&mut self, node: LetStatement) -> LetStatement {
+        fold_let_statement(self, node)
+    }
+
+    fn fold_return_statement(&mut self, node: ReturnStatement) -> ReturnStatement {
+        fold_return_statement(self, node)
+    }
+
+    fn fold_expr_statement(&mut self, node: ExprStatement) -> ExprStatement {
+        fold_expr_statement(self, node)
+    }
+
+    fn fold_assign_statement(&mut self, node: AssignStatement) -> AssignStatement {
+        fold_assign_statement(self, node)
+    }
+
+    fn fold_if_statement(&mut self, node: IfStatement) -> IfStatement {
+        fold_if_statement(self, node)
+    }
+
+    fn fold_type_alias(&mut self, node: TypeAlias) -> TypeAlias {
+        fold_type_alias(self, node)
+    }
+
+    fn fold_extern_crate(&mut self, node: ExternCrate) -> ExternCrate {
+        node
+    }
+
+    fn fold_extern_block(&mut self, node: ExternBlock) -> ExternBlock {
+        fold_extern_block(self, node)
+    }
+
+    fn fold_extern_node(&mut self, node: ExternNode) -> ExternNode {
+        fold_extern_node(self, node)
+    }
+
+    fn fold_extern_static(&mut self, node: ExternStatic) -> ExternStatic {
+        fold_extern_static(self, node)
+    }
+
+    fn fold_macro_rules(&mut self, node: MacroRules) -> MacroRules {
+        node
+    }
+
+    fn fold_thread_local(&mut self, node: ThreadLocal) -> ThreadLocal {
+        fold_thread_local(self, node)
+    }
+
+    fn fold_raw(&mut self, node: Raw) -> Raw {
+        node
+    }
+
+    fn fold_user_region(&mut self, node: UserRegion) -> UserRegion {
+        fold_user_region(self, node)
+    }
+
+    fn fold_expr(&mut self, node: Expr) -> Expr {
+        fold_expr(self, node)
+    }
+
+    fn fold_binding(&mut self, node: Binding) -> Binding {
+        node
+    }
+
+    fn fold_literal(&mut self, node: Literal) -> Literal {
+        node
+    }
+
+    fn fold_call(&mut self, node: Call) -> Call {
+        fold_call(self, node)
+    }
+
+    fn fold_macro_call(&mut self, node: MacroCall) -> MacroCall {
+        fold_macro_call(self, node)
+    }
+
+    fn fold_method_call(&mut self, node: MethodCall) -> MethodCall {
+        fold_method_call(self, node)
+    }
+
+    fn fold_field_access(&mut self, node: FieldAccess) -> FieldAccess {
+        fold_field_access(self, node)
+    }
+
+    fn fold_unary_operation(&mut self, node: UnaryOperation) -> UnaryOperation {
+        fold_unary_operation(self, node)
+    }
+
+    fn fold_binary_operation(&mut self, node: BinaryOperation) -> BinaryOperation {
+        fold_binary_operation(self, node)
+    }
+
+    fn fold_cast(&mut self, node: Cast) -> Cast {
+        fold_cast(self, node)
+    }
+
+    fn fold_tuple(&mut self, node: Tuple) -> Tuple {
+        fold_tuple(self, node)
+    }
+
+    fn fold_array(&mut self, node: Array) -> Array {
+        fold_array(self, node)
+    }
+
+    fn fold_struct_initializer(&mut self, node: StructInitializer) -> StructInitializer {
+        fold_struct_initializer(self, node)
+    }
+
+    fn fold_tuple_struct_initializer(
+        &mut self,
+        node: TupleStructInitializer,
+    ) -> TupleStructInitializer {
+        fold_tuple_struct_initializer(self, node)
+    }
+
+    fn fold_match(&mut self, node: Match) -> Match {
+        fold_match(self, node)
+    }
+
+    fn fold_match_arm(&mut self, node: MatchArm) -> MatchArm {
+        fold_match_arm(self, node)
+    }
+
+    /// Called for every type, see [`Visit::visit_type`](super::Visit::visit_type)
+    fn fold_type(&mut self, r#type: Str) -> Str {
+        r#type
+    }
+}
+
+pub fn fold_module<F: Fold + ?Sized>(f: &mut F, node: Module) -> Module {
+    Module {
+        nodes: fold_all(node.nodes, |child| f.fold_module_node(child)),
+    }
+}
+
+pub fn fold_module_node<F: Fold + ?Sized>(f: &mut F, node: ModuleNode) -> ModuleNode {
+    match node {
+        ModuleNode::Use(child) => ModuleNode::Use(f.fold_use(child)),
+        ModuleNode::Submodule(child) => ModuleNode::Submodule(f.fold_submodule(child)),
+        ModuleNode::Const(child) => ModuleNode::Const(f.fold_const(child)),
+        ModuleNode::Static(child) => ModuleNode::Static(f.fold_static(child)),
+        ModuleNode::Struct(child) => ModuleNode::Struct(f.fold_struct(child)),
+        ModuleNode::Enum(child) => ModuleNode::Enum(f.fold_enum(child)),
+        ModuleNode::Trait(child) => ModuleNode::Trait(f.fold_trait(child)),
+        ModuleNode::Impl(child) => ModuleNode::Impl(f.fold_impl(child)),
+        ModuleNode::Function(child) => ModuleNode::Function(f.fold_function(child)),
+        ModuleNode::TypeAlias(child) => ModuleNode::TypeAlias(f.fold_type_alias(child)),
+        ModuleNode::ExternCrate(child) => ModuleNode::ExternCrate(f.fold_extern_crate(child)),
+        ModuleNode::ExternBlock(child) => ModuleNode::ExternBlock(f.fold_extern_block(child)),
+        ModuleNode::MacroRules(child) => ModuleNode::MacroRules(f.fold_macro_rules(child)),
+        ModuleNode::ThreadLocal(child) => ModuleNode::ThreadLocal(f.fold_thread_local(child)),
+        ModuleNode::Raw(child) => ModuleNode::Raw(f.fold_raw(child)),
+    }
+}
+
+pub fn fold_submodule<F: Fold + ?Sized>(f: &mut F, node: Submodule) -> Submodule {
+    Submodule {
+        module: f.fold_module(node.module),
+        ..node
+    }
+}
+
+pub fn fold_const<F: Fold + ?Sized>(f: &mut F, node: Const) -> Const {
+    Const {
+        r#type: f.fold_type(node.r#type),
+        value: f.fold_expr(node.value),
+        ..node
+    }
+}
+
+pub fn fold_static<F: Fold + ?Sized>(f: &mut F, node: Static) -> Static {
+    Static {
+        r#type: f.fold_type(node.r#type),
+        value: f.fold_expr(node.value),
+        ..node
+    }
+}
+
+pub fn fold_struct<F: Fold + ?Sized>(f: &mut F, node: Struct) -> Struct {
+    Struct {
+        fields: fold_all(node.fields, |field| f.fold_field(field)),
+        ..node
+    }
+}
+
+pub fn fold_field<F: Fold + ?Sized>(f: &mut F, node: Field) -> Field {
+    Field {
+        r#type: f.fold_type(node.r#type),
+        ..node
+    }
+}
+
+pub fn fold_enum<F: Fold + ?Sized>(f: &mut F, node: Enum) -> Enum {
+    Enum {
+        variants: fold_all(node.variants, |variant| f.fold_enum_variant(variant)),
+        ..node
+    }
+}
+
+pub fn fold_enum_variant<F: Fold + ?Sized>(f: &mut F, node: EnumVariant) -> EnumVariant {
+    EnumVariant {
+        fields: match node.fields {
+            VariantFields::Unit => VariantFields::Unit,
+            VariantFields::Unnamed(types) => {
+                VariantFields::Unnamed(fold_all(types, |r#type| f.fold_type(r#type)))
+            }
+            VariantFields::Named(fields) => {
+                VariantFields::Named(fold_all(fields, |field| f.fold_field(field)))
+            }
+        },
+        discriminant: node
+            .discriminant
+            .map(|discriminant| f.fold_expr(discriminant)),
+        ..node
+    }
+}
+
+pub fn fold_trait<F: Fold + ?Sized>(f: &mut F, node: Trait) -> Trait {
+    Trait {
+        nodes: fold_all(node.nodes, |child| f.fold_trait_node(child)),
+        ..node
+    }
+}
+
+pub fn fold_trait_node<F: Fold + ?Sized>(f: &mut F, node: TraitNode) -> TraitNode {
+    match node {
+        TraitNode::Function(child) => TraitNode::Function(f.fold_function(child)),
+        TraitNode::Raw(child) => TraitNode::Raw(f.fold_raw(child)),
+    }
+}
+
+pub fn fold_impl<F: Fold + ?Sized>(f: &mut F, node: Impl) -> Impl {
+    Impl {
+        r#trait: node.r#trait.map(|r#trait| f.fold_type(r#trait)),
+        target: f.fold_type(node.target),
+        nodes: fold_all(node.nodes, |child| f.fold_impl_node(child)),
+        ..node
+    }
+}
+
+pub fn fold_impl_node<F: Fold + ?Sized>(f: &mut F, node: ImplNode) -> ImplNode {
+    match node {
+        ImplNode::Function(child) => ImplNode::Function(f.fold_function(child)),
+        ImplNode::Const(child) => ImplNode::Const(f.fold_const(child)),
+        ImplNode::TypeAlias(child) => ImplNode::TypeAlias(f.fold_type_alias(child)),
+        ImplNode::Raw(child) => ImplNode::Raw(f.fold_raw(child)),
+        ImplNode::UserRegion(child) => ImplNode::UserRegion(f.fold_user_region(child)),
+    }
+}
+
+pub fn fold_function<F: Fold + ?Sized>(f: &mut F, node: Function) -> Function {
+    Function {
+        params: fold_all(node.params, |param| f.fold_param(param)),
+        return_type: node.return_type.map(|return_type| f.fold_type(return_type)),
+        body: node.body.map(|body| f.fold_block(body)),
+        ..node
+    }
+}
+
+pub fn fold_param<F: Fold + ?Sized>(f: &mut F, node: Param) -> Param {
+    Param {
+        r#type: node.r#type.map(|r#type| f.fold_type(r#type)),
+        ..node
+    }
+}
+
+pub fn fold_block<F: Fold + ?Sized>(f: &mut F, node: Block) -> Block {
+    Block {
+        statements: fold_all(node.statements, |statement| f.fold_statement(statement)),
+        ..node
+    }
+}
+
+pub fn fold_statement<F: Fold + ?Sized>(f: &mut F, node: Statement) -> Statement {
+    match node {
+        Statement::Let(child) => Statement::Let(Box::new(f.fold_let_statement(*child))),
+        Statement::Return(child) => Statement::Return(Box::new(f.fold_return_statement(*child))),
+        Statement::Expr(child) => Statement::Expr(Box::new(f.fold_expr_statement(*child))),
+        Statement::Assign(child) => Statement::Assign(Box::new(f.fold_assign_statement(*child))),
+        Statement::If(child) => Statement::If(Box::new(f.fold_if_statement(*child))),
+        Statement::Raw(child) => Statement::Raw(Box::new(f.fold_raw(*child))),
+        Statement::UserRegion(child) => Statement::UserRegion(Box::new(f.fold_user_region(*child))),
+    }
+}
+
+pub fn fold_let_statement<F: Fold + ?Sized>(f: &mut F, node: LetStatement) -> LetStatement {
+    LetStatement {
+        value: f.fold_expr(node.value),
+        ..node
+    }
+}
+
+pub fn fold_return_statement<F: Fold + ?Sized>(
+    f: &mut F,
+    node: ReturnStatement,
+) -> ReturnStatement {
+    ReturnStatement {
+        value: node.value.map(|value| f.fold_expr(value)),
+    }
+}
+
+pub fn fold_expr_statement<F: Fold + ?Sized>(f: &mut F, node: ExprStatement) -> ExprStatement {
+    ExprStatement {
+        expr: f.fold_expr(node.expr),
+        ..node
+    }
+}
+
+pub fn fold_assign_statement<F: Fold + ?Sized>(
+    f: &mut F,
+    node: AssignStatement,
+) -> AssignStatement {
+    AssignStatement {
+        target: f.fold_expr(node.target),
+        value: f.fold_expr(node.value),
+        ..node
+    }
+}
+
+pub fn fold_if_statement<F: Fold + ?Sized>(f: &mut F, node: IfStatement) -> IfStatement {
+    IfStatement {
+        condition: f.fold_expr(node.condition),
+        then: f.fold_block(node.then),
+        otherwise: node.otherwise.map(|otherwise| f.fold_block(otherwise)),
+    }
+}
+
+pub fn fold_type_alias<F: Fold + ?Sized>(f: &mut F, node: TypeAlias) -> TypeAlias {
+    TypeAlias {
+        r#type: f.fold_type(node.r#type),
+        ..node
+    }
+}
+
+pub fn fold_extern_block<F: Fold + ?Sized>(f: &mut F, node: ExternBlock) -> ExternBlock {
+    ExternBlock {
+        nodes: fold_all(node.nodes, |child| f.fold_extern_node(child)),
+        ..node
+    }
+}
+
+pub fn fold_extern_node<F: Fold + ?Sized>(f: &mut F, node: ExternNode) -> ExternNode {
+    match node {
+        ExternNode::Function(child) => ExternNode::Function(f.fold_function(child)),
+        ExternNode::Static(child) => ExternNode::Static(f.fold_extern_static(child)),
+    }
+}
+
+pub fn fold_extern_static<F: Fold + ?Sized>(f: &mut F, node: ExternStatic) -> ExternStatic {
+    ExternStatic {
+        r#type: f.fold_type(node.r#type),
+        ..node
+    }
+}
+
+pub fn fold_thread_local<F: Fold + ?Sized>(f: &mut F, node: ThreadLocal) -> ThreadLocal {
+    ThreadLocal {
+        statics: fold_all(node.statics, |static_| f.fold_static(static_)),
+    }
+}
+
+pub fn fold_user_region<F: Fold + ?Sized>(f: &mut F, node: UserRegion) -> UserRegion {
+    UserRegion {
+        default: node.default.map(|default| f.fold_raw(default)),
+        ..node
+    }
+}
+
+pub fn fold_expr<F: Fold + ?Sized>(f: &mut F, node: Expr) -> Expr {
+    match node {
+        Expr::Binding(child) => Expr::Binding(Box::new(f.fold_binding(*child))),
+        Expr::Literal(child) => Expr::Literal(Box::new(f.fold_literal(*child))),
+        Expr::Call(child) => Expr::Call(Box::new(f.fold_call(*child))),
+        Expr::MacroCall(child) => Expr::MacroCall(Box::new(f.fold_macro_call(*child))),
+        Expr::MethodCall(child) => Expr::MethodCall(Box::new(f.fold_method_call(*child))),
+        Expr::FieldAccess(child) => Expr::FieldAccess(Box::new(f.fold_field_access(*child))),
+        Expr::UnaryOperation(child) => {
+            Expr::UnaryOperation(Box::new(f.fold_unary_operation(*child)))
+        }
+        Expr::BinaryOperation(child) => {
+            Expr::BinaryOperation(Box::new(f.fold_binary_operation(*child)))
+        }
+        Expr::Cast(child) => Expr::Cast(Box::new(f.fold_cast(*child))),
+        Expr::Tuple(child) => Expr::Tuple(Box::new(f.fold_tuple(*child))),
+        Expr::Array(child) => Expr::Array(Box::new(f.fold_array(*child))),
+        Expr::StructInitializer(child) => {
+            Expr::StructInitializer(Box::new(f.fold_struct_initializer(*child)))
+        }
+        Expr::TupleStructInitializer(child) => {
+            Expr::TupleStructInitializer(Box::new(f.fold_tuple_struct_initializer(*child)))
+        }
+        Expr::Match(child) => Expr::Match(Box::new(f.fold_match(*child))),
+    }
+}
+
+pub fn fold_call<F: Fold + ?Sized>(f: &mut F, node: Call) -> Call {
+    let name = match split_call(&node.name) {
+        Some((path, function)) => {
+            let new_path = f.fold_type(path.to_owned().into());
+            match new_path == path {
+                true => node.name,
+                false => format!("{new_path}::{function}").into(),
+            }
+        }
+        None => node.name,
+    };
+    Call {
+        name,
+        args: fold_all(node.args, |arg| f.fold_expr(arg)),
+    }
+}
+
+pub fn fold_macro_call<F: Fold + ?Sized>(f: &mut F, node: MacroCall) -> MacroCall {
+    MacroCall {
+        args: fold_all(node.args, |arg| f.fold_expr(arg)),
+        ..node
+    }
+}
+
+pub fn fold_method_call<F: Fold + ?Sized>(f: &mut F, node: MethodCall) -> MethodCall {
+    MethodCall {
+        receiver: f.fold_expr(node.receiver),
+        args: fold_all(node.args, |arg| f.fold_expr(arg)),
+        ..node
+    }
+}
+
+pub fn fold_field_access<F: Fold + ?Sized>(f: &mut F, node: FieldAccess) -> FieldAccess {
+    FieldAccess {
+        expr: f.fold_expr(node.expr),
+        ..node
+    }
+}
+
+pub fn fold_unary_operation<F: Fold + ?Sized>(f: &mut F, node: UnaryOperation) -> UnaryOperation {
+    UnaryOperation {
+        expr: f.fold_expr(node.expr),
+        ..node
+    }
+}
+
+pub fn fold_binary_operation<F: Fold + ?Sized>(
+    f: &mut F,
+    node: BinaryOperation,
+) -> BinaryOperation {
+    BinaryOperation {
+        left: f.fold_expr(node.left),
+        right: f.fold_expr(node.right),
+        ..node
+    }
+}
+
+pub fn fold_cast<F: Fold + ?Sized>(f: &mut F, node: Cast) -> Cast {
+    Cast {
+        expr: f.fold_expr(node.expr),
+        r#type: f.fold_type(node.r#type),
+    }
+}
+
+pub fn fold_tuple<F: Fold + ?Sized>(f: &mut F, node: Tuple) -> Tuple {
+    Tuple {
+        values: fold_all(node.values, |value| f.fold_expr(value)),
+        ..node
+    }
+}
+
+pub fn fold_array<F: Fold + ?Sized>(f: &mut F, node: Array) -> Array {
+    Array {
+        values: fold_all(node.values, |value| f.fold_expr(value)),
+        ..node
+    }
+}
+
+pub fn fold_struct_initializer<F: Fold + ?Sized>(
+    f: &mut F,
+    node: StructInitializer,
+) -> StructInitializer {
+    StructInitializer {
+        name: f.fold_type(node.name),
+        fields: fold_all(node.fields, |(name, value)| match value {
+            Some(value) => (name, Some(f.fold_expr(value))),
+            None => {
+                // `Point { x }` reads the binding `x`, a renamed binding becomes `Point { x: y }`
+                let binding = f.fold_binding(Binding::new(name.clone()));
+                match binding.name == name {
+                    true => (name, None),
+                    false => (name, Some(binding.into())),
+                }
+            }
+        }),
+        ..node
+    }
+}
+
+pub fn fold_tuple_struct_initializer<F: Fold + ?Sized>(
+    f: &mut F,
+    node: TupleStructInitializer,
+) -> TupleStructInitializer {
+    TupleStructInitializer {
+        tuple: Tuple {
+            values: fold_all(node.tuple.values, |value| f.fold_expr(value)),
+            ..node.tuple
+        },
+        ..node
+    }
+}
+
+pub fn fold_match<F: Fold + ?Sized>(f: &mut F, node: Match) -> Match {
+    Match {
+        expr: f.fold_expr(node.expr),
+        arms: fold_all(node.arms, |arm| f.fold_match_arm(arm)),
+    }
+}
+
+pub fn fold_match_arm<F: Fold + ?Sized>(f: &mut F, node: MatchArm) -> MatchArm {
+    MatchArm {
+        guard: node.guard.map(|guard| f.fold_expr(guard)),
+        value: f.fold_expr(node.value),
+        ..node
+    }
+}
+
+fn fold_all<T>(values: Vec<T>, f: impl FnMut(T) -> T) -> Vec<T> {
+    values.into_iter().map(f).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::GenerateCode;
+
+    /// Renames `n` to `len` and `Point` to `Vec2`
+    struct Rename;
+
+    impl Fold for Rename {
+        fn fold_type(&mut self, r#type: Str) -> Str {
+            match &*r#type {
+                "Point" => "Vec2".into(),
+                _ => r#type,
+            }
+        }
+
+        fn fold_binding(&mut self, node: Binding) -> Binding {
+            match node.name() {
+                "n" => Binding::new("len"),
+                _ => node,
+            }
+        }
+    }
+
+    #[test]
+    fn struct_initializers_and_calls() {
+        let expr = Rename.fold_expr(crate::visit::tests::initializer());
+        assert_eq!(
+            expr.to_code_string(),
+            "Vec2 { x, y: Vec::<u8>::with_capacity(len), z: f::<u8>() }"
+        );
+
+        let expr = StructInitializer::new("Point").field_auto("n");
+        assert_eq!(
+            Rename.fold_struct_initializer(expr).to_code_string(),
+            "Vec2 { n: len }"
+        );
+    }
+}
@@ -0,0 +1,585 @@
+//! Traversing the node tree by mutable reference, see [`visit`](crate::visit)
+
+use crate::{
+    expr::{
+        Array, BinaryOperation, Binding, Call, Cast, Expr, FieldAccess, Literal, MacroCall, Match,
+        MatchArm, MethodCall, StructInitializer, Tuple, TupleStructInitializer, UnaryOperation,
+    },
+    nodes::{
+        extern_block::{ExternBlock, ExternNode, ExternStatic},
+        extern_crate::ExternCrate,
+        function::{Function, Param},
+        macro_rules::MacroRules,
+        r#const::Const,
+        r#enum::{Enum, EnumVariant, VariantFields},
+        r#impl::{Impl, ImplNode},
+        r#static::Static,
+        r#struct::{Field, Struct},
+        r#trait::{Trait, TraitNode},
+        r#type::TypeAlias,
+        r#use::Use,
+        raw::Raw,
+        statement::{
+            AssignStatement, Block, ExprStatement, IfStatement, LetStatement, ReturnStatement,
+            Statement,
+        },
+        thread_local::ThreadLocal,
+        user_region::UserRegion,
+        Module, ModuleNode, Submodule,
+    },
+    Str,
+};
+
+use super::split_call;
+
+pub trait VisitMut {
+    fn visit_module_mut(&mut self, node: &mut Module) {
+        visit_module_mut(self, node);
+    }
+
+    fn visit_module_node_mut(&mut self, node: &mut ModuleNode) {
+        visit_module_node_mut(self, node);
+    }
+
+    fn visit_use_mut(&mut self, node: &mut Use) {
+        _ = node;
+    }
+
+    fn visit_submodule_mut(&mut self, node: &mut Submodule) {
+        visit_submodule_mut(self, node);
+    }
+
+    fn visit_const_mut(&mut self, node: &mut Const) {
+        visit_const_mut(self, node);
+    }
+
+    fn visit_static_mut(&mut self, node: &mut Static) {
+        visit_static_mut(self, node);
+    }
+
+    fn visit_struct_mut(&mut self, node: &mut Struct) {
+        visit_struct_mut(self, node);
+    }
+
+    fn visit_field_mut(&mut self, node: &mut Field) {
+        visit_field_mut(self, node);
+    }
+
+    fn visit_enum_mut(&mut self, node: &mut Enum) {
+        visit_enum_mut(self, node);
+    }
+
+    fn visit_enum_variant_mut(&mut self, node: &mut EnumVariant) {
+        visit_enum_variant_mut(self, node);
+    }
+
+    fn visit_trait_mut(&mut self, node: &mut Trait) {
+        visit_trait_mut(self, node);
+    }
+
+    fn visit_trait_node_mut(&mut self, node: &mut TraitNode) {
+        visit_trait_node_mut(self, node);
+    }
+
+    fn visit_impl_mut(&mut self, node: &mut Impl) {
+        visit_impl_mut(self, node);
+    }
+
+    fn visit_impl_node_mut(&mut self, node: &mut ImplNode) {
+        visit_impl_node_mut(self, node);
+    }
+
+    fn visit_function_mut(&mut self, node: &mut Function) {
+        visit_function_mut(self, node);
+    }
+
+    fn visit_param_mut(&mut self, node: &mut Param) {
+        visit_param_mut(self, node);
+    }
+
+    fn visit_block_mut(&mut self, node: &mut Block) {
+        visit_block_mut(self, node);
+    }
+
+    fn visit_statement_mut(&mut self, node: &mut Statement) {
+        visit_statement_mut(self, node);
+    }
+
+    fn visit_let_statement_mut(&mut self, node: &mut LetStatement) {
+        visit_let_statement_mut(self, node);
+    }
+
+    fn visit_return_statement_mut(&mut self, node: &mut ReturnStatement) {
+        visit_return_statement_mut(self, node);
+    }
+
+    fn visit_expr_statement_mut(&mut self, node: &mut ExprStatement) {
+        visit_expr_statement_mut(self, node);
+    }
+
+    fn visit_assign_statement_mut(&mut self, node: &mut AssignStatement) {
+        visit_assign_statement_mut(self, node);
+    }
+
+    fn visit_if_statement_mut(&mut self, node: &mut IfStatement) {
+        visit_if_statement_mut(self, node);
+    }
+
+    fn visit_type_alias_mut(&mut self, node: &mut TypeAlias) {
+        visit_type_alias_mut(self, node);
+    }
+
+    fn visit_extern_crate_mut(&mut self, node: &mut ExternCrate) {
+        _ = node;
+    }
+
+    fn visit_extern_block_mut(&mut self, node: &mut ExternBlock) {
+        visit_extern_block_mut(self, node);
+    }
+
+    fn visit_extern_node_mut(&mut self, node: &mut ExternNode) {
+        visit_extern_node_mut(self, node);
+    }
+
+    fn visit_extern_static_mut(&mut self, node: &mut ExternStatic) {
+        visit_extern_static_mut(self, node);
+    }
+
+    fn visit_macro_rules_mut(&mut self, node: &mut MacroRules) {
+        _ = node;
+    }
+
+    fn visit_thread_local_mut(&mut self, node: &mut ThreadLocal) {
+        visit_thread_local_mut(self, node);
+    }
+
+    fn visit_raw_mut(&mut self, node: &mut Raw) {
+        _ = node;
+    }
+
+    fn visit_user_region_mut(&mut self, node: &mut UserRegion) {
+        visit_user_region_mut(self, node);
+    }
+
+    fn visit_expr_mut(&mut self, node: &mut Expr) {
+        visit_expr_mut(self, node);
+    }
+
+    fn visit_binding_mut(&mut self, node: &mut Binding) {
+        _ = node;
+    }
+
+    fn visit_literal_mut(&mut self, node: &mut Literal) {
+        _ = node;
+    }
+
+    fn visit_call_mut(&mut self, node: &mut Call) {
+        visit_call_mut(self, node);
+    }
+
+    fn visit_macro_call_mut(&mut self, node: &mut MacroCall) {
+        visit_macro_call_mut(self, node);
+    }
+
+    fn visit_method_call_mut(&mut self, node: &mut MethodCall) {
+        visit_method_call_mut(self, node);
+    }
+
+    fn visit_field_access_mut(&mut self, node: &mut FieldAccess) {
+        visit_field_access_mut(self, node);
+    }
+
+    fn visit_unary_operation_mut(&mut self, node: &mut UnaryOperation) {
+        visit_unary_operation_mut(self, node);
+    }
+
+    fn visit_binary_operation_mut(&mut self, node: &mut BinaryOperation) {
+        visit_binary_operation_mut(self, node);
+    }
+
+    fn visit_cast_mut(&mut self, node: &mut Cast) {
+        visit_cast_mut(self, node);
+    }
+
+    fn visit_tuple_mut(&mut self, node: &mut Tuple) {
+        visit_tuple_mut(self, node);
+    }
+
+    fn visit_array_mut(&mut self, node: &mut Array) {
+        visit_array_mut(self, node);
+    }
+
+    fn visit_struct_initializer_mut(&mut self, node: &mut StructInitializer) {
+        visit_struct_initializer_mut(self, node);
+    }
+
+    fn visit_tuple_struct_initializer_mut(&mut self, node: &mut TupleStructInitializer) {
+        visit_tuple_struct_initializer_mut(self, node);
+    }
+
+    fn visit_match_mut(&mut self, node: &mut Match) {
+        visit_match_mut(self, node);
+    }
+
+    fn visit_match_arm_mut(&mut self, node: &mut MatchArm) {
+        visit_match_arm_mut(self, node);
+    }
+
+    /// Called for every type, see [`Visit::visit_type`](super::Visit::visit_type)
+    fn visit_type_mut(&mut self, r#type: &mut Str) {
+        _ = r#type;
+    }
+}
+
+pub fn visit_module_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Module) {
+    for child in &mut node.nodes {
+        v.visit_module_node_mut(child);
+    }
+}
+
+pub fn visit_module_node_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut ModuleNode) {
+    match node {
+        ModuleNode::Use(child) => v.visit_use_mut(child),
+        ModuleNode::Submodule(child) => v.visit_submodule_mut(child),
+        ModuleNode::Const(child) => v.visit_const_mut(child),
+        ModuleNode::Static(child) => v.visit_static_mut(child),
+        ModuleNode::Struct(child) => v.visit_struct_mut(child),
+        ModuleNode::Enum(child) => v.visit_enum_mut(child),
+        ModuleNode::Trait(child) => v.visit_trait_mut(child),
+        ModuleNode::Impl(child) => v.visit_impl_mut(child),
+        ModuleNode::Function(child) => v.visit_function_mut(child),
+        ModuleNode::TypeAlias(child) => v.visit_type_alias_mut(child),
+        ModuleNode::ExternCrate(child) => v.visit_extern_crate_mut(child),
+        ModuleNode::ExternBlock(child) => v.visit_extern_block_mut(child),
+        ModuleNode::MacroRules(child) => v.visit_macro_rules_mut(child),
+        ModuleNode::ThreadLocal(child) => v.visit_thread_local_mut(child),
+        ModuleNode::Raw(child) => v.visit_raw_mut(child),
+    }
+}
+
+pub fn visit_submodule_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Submodule) {
+    v.visit_module_mut(&mut node.module);
+}
+
+pub fn visit_const_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Const) {
+    v.visit_type_mut(&mut node.r#type);
+    v.visit_expr_mut(&mut node.value);
+}
+
+pub fn visit_static_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Static) {
+    v.visit_type_mut(&mut node.r#type);
+    v.visit_expr_mut(&mut node.value);
+}
+
+pub fn visit_struct_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Struct) {
+    for field in &mut node.fields {
+        v.visit_field_mut(field);
+    }
+}
+
+pub fn visit_field_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Field) {
+    v.visit_type_mut(&mut node.r#type);
+}
+
+pub fn visit_enum_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Enum) {
+    for variant in &mut node.variants {
+        v.visit_enum_variant_mut(variant);
+    }
+}
+
+pub fn visit_enum_variant_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut EnumVariant) {
+    match &mut node.fields {
+        VariantFields::Unit => {}
+        VariantFields::Unnamed(types) => {
+            for r#type in types {
+                v.visit_type_mut(r#type);
+            }
+        }
+        VariantFields::Named(fields) => {
+            for field in fields {
+                v.visit_field_mut(field);
+            }
+        }
+    }
+    if let Some(discriminant) = &mut node.discriminant {
+        v.visit_expr_mut(discriminant);
+    }
+}
+
+pub fn visit_trait_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Trait) {
+    for child in &mut node.nodes {
+        v.visit_trait_node_mut(child);
+    }
+}
+
+pub fn visit_trait_node_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut TraitNode) {
+    match node {
+        TraitNode::Function(child) => v.visit_function_mut(child),
+        TraitNode::Raw(child) => v.visit_raw_mut(child),
+    }
+}
+
+pub fn visit_impl_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Impl) {
+    if let Some(r#trait) = &mut node.r#trait {
+        v.visit_type_mut(r#trait);
+    }
+    v.visit_type_mut(&mut node.target);
+    for child in &mut node.nodes {
+        v.visit_impl_node_mut(child);
+    }
+}
+
+pub fn visit_impl_node_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut ImplNode) {
+    match node {
+        ImplNode::Function(child) => v.visit_function_mut(child),
+        ImplNode::Const(child) => v.visit_const_mut(child),
+        ImplNode::TypeAlias(child) => v.visit_type_alias_mut(child),
+        ImplNode::Raw(child) => v.visit_raw_mut(child),
+        ImplNode::UserRegion(child) => v.visit_user_region_mut(child),
+    }
+}
+
+pub fn visit_function_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Function) {
+    for param in &mut node.params {
+        v.visit_param_mut(param);
+    }
+    if let Some(return_type) = &mut node.return_type {
+        v.visit_type_mut(return_type);
+    }
+    if let Some(body) = &mut node.body {
+        v.visit_block_mut(body);
+    }
+}
+
+pub fn visit_param_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Param) {
+    if let Some(r#type) = &mut node.r#type {
+        v.visit_type_mut(r#type);
+    }
+}
+
+pub fn visit_block_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Block) {
+    for statement in &mut node.statements {
+        v.visit_statement_mut(statement);
+    }
+}
+
+pub fn visit_statement_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Statement) {
+    match node {
+        Statement::Let(child) => v.visit_let_statement_mut(child),
+        Statement::Return(child) => v.visit_return_statement_mut(child),
+        Statement::Expr(child) => v.visit_expr_statement_mut(child),
+        Statement::Assign(child) => v.visit_assign_statement_mut(child),
+        Statement::If(child) => v.visit_if_statement_mut(child),
+        Statement::Raw(child) => v.visit_raw_mut(child),
+        Statement::UserRegion(child) => v.visit_user_region_mut(child),
+    }
+}
+
+pub fn visit_let_statement_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut LetStatement) {
+    v.visit_expr_mut(&mut node.value);
+}
+
+pub fn visit_return_statement_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut ReturnStatement) {
+    if let Some(value) = &mut node.value {
+        v.visit_expr_mut(value);
+    }
+}
+
+pub fn visit_expr_statement_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut ExprStatement) {
+    v.visit_expr_mut(&mut node.expr);
+}
+
+pub fn visit_assign_statement_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut AssignStatement) {
+    v.visit_expr_mut(&mut node.target);
+    v.visit_expr_mut(&mut node.value);
+}
+
+pub fn visit_if_statement_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut IfStatement) {
+    v.visit_expr_mut(&mut node.condition);
+    v.visit_block_mut(&mut node.then);
+    if let Some(otherwise) = &mut node.otherwise {
+        v.visit_block_mut(otherwise);
+    }
+}
+
+pub fn visit_type_alias_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut TypeAlias) {
+    v.visit_type_mut(&mut node.r#type);
+}
+
+pub fn visit_extern_block_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut ExternBlock) {
+    for child in &mut node.nodes {
+        v.visit_extern_node_mut(child);
+    }
+}
+
+pub fn visit_extern_node_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut ExternNode) {
+    match node {
+        ExternNode::Function(child) => v.visit_function_mut(child),
+        ExternNode::Static(child) => v.visit_extern_static_mut(child),
+    }
+}
+
+pub fn visit_extern_static_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut ExternStatic) {
+    v.visit_type_mut(&mut node.r#type);
+}
+
+pub fn visit_thread_local_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut ThreadLocal) {
+    for static_ in &mut node.statics {
+        v.visit_static_mut(static_);
+    }
+}
+
+pub fn visit_user_region_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut UserRegion) {
+    if let Some(default) = &mut node.default {
+        v.visit_raw_mut(default);
+    }
+}
+
+pub fn visit_expr_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Expr) {
+    match node {
+        Expr::Binding(child) => v.visit_binding_mut(child),
+        Expr::Literal(child) => v.visit_literal_mut(child),
+        Expr::Call(child) => v.visit_call_mut(child),
+        Expr::MacroCall(child) => v.visit_macro_call_mut(child),
+        Expr::MethodCall(child) => v.visit_method_call_mut(child),
+        Expr::FieldAccess(child) => v.visit_field_access_mut(child),
+        Expr::UnaryOperation(child) => v.visit_unary_operation_mut(child),
+        Expr::BinaryOperation(child) => v.visit_binary_operation_mut(child),
+        Expr::Cast(child) => v.visit_cast_mut(child),
+        Expr::Tuple(child) => v.visit_tuple_mut(child),
+        Expr::Array(child) => v.visit_array_mut(child),
+        Expr::StructInitializer(child) => v.visit_struct_initializer_mut(child),
+        Expr::TupleStructInitializer(child) => v.visit_tuple_struct_initializer_mut(child),
+        Expr::Match(child) => v.visit_match_mut(child),
+    }
+}
+
+pub fn visit_call_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Call) {
+    if let Some((path, function)) = split_call(&node.name) {
+        let mut new_path = Str::from(path.to_owned());
+        v.visit_type_mut(&mut new_path);
+        if new_path != path {
+            node.name = format!("{new_path}::{function}").into();
+        }
+    }
+    for arg in &mut node.args {
+        v.visit_expr_mut(arg);
+    }
+}
+
+pub fn visit_macro_call_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut MacroCall) {
+    for arg in &mut node.args {
+        v.visit_expr_mut(arg);
+    }
+}
+
+pub fn visit_method_call_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut MethodCall) {
+    v.visit_expr_mut(&mut node.receiver);
+    for arg in &mut node.args {
+        v.visit_expr_mut(arg);
+    }
+}
+
+pub fn visit_field_access_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut FieldAccess) {
+    v.visit_expr_mut(&mut node.expr);
+}
+
+pub fn visit_unary_operation_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut UnaryOperation) {
+    v.visit_expr_mut(&mut node.expr);
+}
+
+pub fn visit_binary_operation_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut BinaryOperation) {
+    v.visit_expr_mut(&mut node.left);
+    v.visit_expr_mut(&mut node.right);
+}
+
+pub fn visit_cast_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Cast) {
+    v.visit_expr_mut(&mut node.expr);
+    v.visit_type_mut(&mut node.r#type);
+}
+
+pub fn visit_tuple_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Tuple) {
+    for value in &mut node.values {
+        v.visit_expr_mut(value);
+    }
+}
+
+pub fn visit_array_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Array) {
+    for value in &mut node.values {
+        v.visit_expr_mut(value);
+    }
+}
+
+pub fn visit_struct_initializer_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut StructInitializer) {
+    v.visit_type_mut(&mut node.name);
+    for (name, value) in &mut node.fields {
+        match value {
+            Some(value) => v.visit_expr_mut(value),
+            None => {
+                // `Point { x }` reads the binding `x`, a renamed binding becomes `Point { x: y }`
+                let mut binding = Binding::new(name.clone());
+                v.visit_binding_mut(&mut binding);
+                if binding.name != *name {
+                    *value = Some(binding.into());
+                }
+            }
+        }
+    }
+}
+
+pub fn visit_tuple_struct_initializer_mut<V: VisitMut + ?Sized>(
+    v: &mut V,
+    node: &mut TupleStructInitializer,
+) {
+    for value in &mut node.tuple.values {
+        v.visit_expr_mut(value);
+    }
+}
+
+pub fn visit_match_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Match) {
+    v.visit_expr_mut(&mut node.expr);
+    for arm in &mut node.arms {
+        v.visit_match_arm_mut(arm);
+    }
+}
+
+pub fn visit_match_arm_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut MatchArm) {
+    if let Some(guard) = &mut node.guard {
+        v.visit_expr_mut(guard);
+    }
+    v.visit_expr_mut(&mut node.value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::GenerateCode;
+
+    /// Renames `x` to `x2` and `Point` to `Vec2`, and prefixes the other types with `crate::`
+    struct Rename;
+
+    impl VisitMut for Rename {
+        fn visit_type_mut(&mut self, r#type: &mut Str) {
+            *r#type = match &**r#type {
+                "Point" => "Vec2".into(),
+                other => format!("crate::{other}").into(),
+            };
+        }
+
+        fn visit_binding_mut(&mut self, node: &mut Binding) {
+            if node.name() == "x" {
+                *node.name_mut() = "x2".into();
+            }
+        }
+    }
+
+    #[test]
+    fn struct_initializers_and_calls() {
+        let mut expr = crate::visit::tests::initializer();
+        Rename.visit_expr_mut(&mut expr);
+        assert_eq!(
+            expr.to_code_string(),
+            "Vec2 { x: x2, y: crate::Vec::<u8>::with_capacity(n), z: f::<u8>() }"
+        );
+    }
+}